@@ -11,7 +11,11 @@ fn main() {
     cc::Build::new()
         .cpp(true)
         .define("DT_POLYREF64", "1")
-        .includes(Some(Path::new("recastnavigation/Detour/Include")))
+        .includes([
+            Path::new("recastnavigation/Detour/Include"),
+            Path::new("recastnavigation/DetourCrowd/Include"),
+            Path::new("recastnavigation/DetourTileCache/Include"),
+        ])
         .file("recastnavigation/Detour/Source/DetourAlloc.cpp")
         .file("recastnavigation/Detour/Source/DetourAssert.cpp")
         .file("recastnavigation/Detour/Source/DetourCommon.cpp")
@@ -19,6 +23,14 @@ fn main() {
         .file("recastnavigation/Detour/Source/DetourNavMeshBuilder.cpp")
         .file("recastnavigation/Detour/Source/DetourNavMeshQuery.cpp")
         .file("recastnavigation/Detour/Source/DetourNode.cpp")
+        .file("recastnavigation/DetourCrowd/Source/DetourCrowd.cpp")
+        .file("recastnavigation/DetourCrowd/Source/DetourLocalBoundary.cpp")
+        .file("recastnavigation/DetourCrowd/Source/DetourObstacleAvoidance.cpp")
+        .file("recastnavigation/DetourCrowd/Source/DetourPathCorridor.cpp")
+        .file("recastnavigation/DetourCrowd/Source/DetourPathQueue.cpp")
+        .file("recastnavigation/DetourCrowd/Source/DetourProximityGrid.cpp")
+        .file("recastnavigation/DetourTileCache/Source/DetourTileCache.cpp")
+        .file("recastnavigation/DetourTileCache/Source/DetourTileCacheBuilder.cpp")
         .file("src/extern.cpp")
         .compile("detour");
 }
@@ -2,8 +2,8 @@ use byteorder::{LittleEndian, ReadBytesExt};
 use log::{info, trace, LevelFilter};
 
 use divert_f64::{
-    DivertResult, DtStraightPathFlags, NavMesh, NavMeshParams, NavMeshQuery, PolyRef, QueryFilter,
-    Vector,
+    dedup_corridor, DivertResult, DtStraightPathFlags, NavMesh, NavMeshParams, NavMeshQuery,
+    PolyRef, QueryFilter, StraightPathOptions, UpAxis, Vector,
 };
 
 use std::{
@@ -14,11 +14,10 @@ use std::{
     io::{self, Read, Seek, SeekFrom},
 };
 
+// The example's positions are z-up, so the cylinder is measured around z rather than Detour's y
 fn in_range(source: &Vector, destination: &Vector, radius: f64, height: f64) -> bool {
-    let dx = destination.y - source.y;
-    let dy = destination.z - source.z;
-    let dz = destination.x - source.x;
-    (dx * dx + dz * dz) < radius * radius && dy.abs() < height
+    UpAxis::Z.distance_2d(source, destination) < radius
+        && (UpAxis::Z.height(destination) - UpAxis::Z.height(source)).abs() < height
 }
 
 fn world_to_trinity(world_x: f64, world_y: f64) -> (u32, u32) {
@@ -98,6 +97,7 @@ struct TrinityTileProvider {
 }
 
 impl TileProvider for TrinityTileProvider {
+    #[allow(clippy::needless_borrows_for_generic_args)]
     fn read_tile_data(&self, tile_x: u32, tile_y: u32) -> io::Result<Vec<u8>> {
         let mut tile_file = File::open(
             &[
@@ -160,7 +160,8 @@ impl<'a> Navigator<'a> {
         let params = read_nav_mesh_params_from(map_params_file)?;
 
         let nav_mesh = NavMesh::new(&params)?;
-        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 2048)?;
+        let mut nav_mesh_query = NavMeshQuery::new(&nav_mesh, 2048)?;
+        nav_mesh_query.set_default_extents(Vector::new(3.0, 3.0, 5.0));
 
         let mut query_filter = QueryFilter::new()?;
         query_filter.set_include_flags(1 | 8 | 4 | 2);
@@ -184,12 +185,11 @@ impl<'a> Navigator<'a> {
     }
 
     fn find_nearest_poly(&mut self, position: &Vector) -> DivertResult<(PolyRef, Vector)> {
-        //let extents = Vector::from_yzx(3.0f32, 5.0f32, 3.0f32);
-        let extents = Vector::new(3.0f64, 3.0f64, 5.0f64);
         self.nav_mesh_query
-            .find_nearest_poly(position, &extents, &self.query_filter)
+            .find_nearest_poly_default(position, &self.query_filter)
     }
 
+    #[allow(clippy::unnecessary_cast)]
     fn packed_tile_id(tile_x: u32, tile_y: u32) -> u32 {
         (tile_x << 16 | tile_y) as u32
     }
@@ -221,7 +221,7 @@ impl<'a> Navigator<'a> {
             end_pos,
             &self.poly_path,
             self.settings.max_steer_points,
-            0,
+            StraightPathOptions::empty(),
         )?;
 
         for (mut steer_point, steer_flag, steer_poly) in steer_points {
@@ -244,13 +244,9 @@ impl<'a> Navigator<'a> {
     fn find_smooth_path(&mut self, start_pos: &Vector, end_pos: &Vector) -> DivertResult<()> {
         self.smooth_path.clear();
 
-        let mut iter_pos = self
-            .nav_mesh_query
-            .closest_point_on_poly_boundary(*self.poly_path.first().unwrap(), start_pos)?;
-
-        let target_pos = self
-            .nav_mesh_query
-            .closest_point_on_poly_boundary(*self.poly_path.last().unwrap(), end_pos)?;
+        let (mut iter_pos, target_pos) =
+            self.nav_mesh_query
+                .clamp_to_corridor(&self.poly_path, start_pos, end_pos)?;
 
         self.smooth_path.push(iter_pos);
         while !self.poly_path.is_empty() && (self.smooth_path.len() < self.smooth_path.capacity()) {
@@ -283,13 +279,13 @@ impl<'a> Navigator<'a> {
                 )?;
 
                 fix_up_corridor(&mut self.poly_path, &self.move_along_path);
+                dedup_corridor(&mut self.poly_path);
 
                 let height = self
                     .nav_mesh_query
                     .get_poly_height(self.poly_path[0], &move_result)
                     .unwrap_or(0.0);
-                //iter_pos = Vector::from_yzx(move_result.y, height + 0.5, move_result.x);
-                iter_pos = Vector::new(move_result.x, move_result.y, height + 0.5);
+                iter_pos = UpAxis::Z.with_height(&move_result, height + 0.5);
                 self.smooth_path.push(iter_pos);
             } else {
                 break;
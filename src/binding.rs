@@ -16,9 +16,33 @@ pub enum DtNavMeshQuery {}
 
 pub enum DtQueryFilter {}
 
-pub type DtTileRef = u64;
+pub enum DtMeshTile {}
 
-pub type DtPolyRef = u64;
+pub enum DtPoly {}
+
+pub enum DtCrowd {}
+
+pub enum DtCrowdAgent {}
+
+pub enum DtTileCache {}
+
+pub enum DtTileCacheAlloc {}
+
+pub enum DtTileCacheCompressor {}
+
+pub enum DtTileCacheMeshProcess {}
+
+/// Tile references cross the ffi boundary as the repr(transparent) TileRef newtype
+pub type DtTileRef = crate::TileRef;
+
+/// Poly references cross the ffi boundary as the repr(transparent) PolyRef newtype
+pub type DtPolyRef = crate::PolyRef;
+
+/// Obstacle references cross the ffi boundary as the repr(transparent) ObstacleRef newtype
+pub type DtObstacleRef = crate::ObstacleRef;
+
+/// Compressed tile references cross the ffi boundary as the repr(transparent) CompressedTileRef newtype
+pub type DtCompressedTileRef = crate::CompressedTileRef;
 
 #[repr(C)]
 #[derive(Debug)]
@@ -30,6 +54,123 @@ pub struct DtNavMeshParams {
     pub max_polys: i32,
 }
 
+#[repr(C)]
+#[derive(Debug)]
+pub struct DtNavMeshCreateParams {
+    pub verts: *const u16,
+    pub vert_count: i32,
+    pub polys: *const u16,
+    pub poly_flags: *const u16,
+    pub poly_areas: *const u8,
+    pub poly_count: i32,
+    pub nvp: i32,
+    pub detail_meshes: *const u32,
+    pub detail_verts: *const f64,
+    pub detail_verts_count: i32,
+    pub detail_tris: *const u8,
+    pub detail_tri_count: i32,
+    pub off_mesh_con_verts: *const f64,
+    pub off_mesh_con_rad: *const f64,
+    pub off_mesh_con_flags: *const u16,
+    pub off_mesh_con_areas: *const u8,
+    pub off_mesh_con_dir: *const u8,
+    pub off_mesh_con_user_id: *const u32,
+    pub off_mesh_con_count: i32,
+    pub user_id: u32,
+    pub tile_x: i32,
+    pub tile_y: i32,
+    pub tile_layer: i32,
+    pub b_min: [f64; 3],
+    pub b_max: [f64; 3],
+    pub walkable_height: f64,
+    pub walkable_radius: f64,
+    pub walkable_climb: f64,
+    pub cs: f64,
+    pub ch: f64,
+    pub build_bv_tree: bool,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct DtCrowdAgentParams {
+    pub radius: f64,
+    pub height: f64,
+    pub max_acceleration: f64,
+    pub max_speed: f64,
+    pub collision_query_range: f64,
+    pub path_optimization_range: f64,
+    pub separation_weight: f64,
+    pub update_flags: u8,
+    pub obstacle_avoidance_type: u8,
+    pub query_filter_type: u8,
+    pub user_data: *mut std::ffi::c_void,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct DtTileCacheParams {
+    pub orig: [f64; 3],
+    pub cs: f64,
+    pub ch: f64,
+    pub width: i32,
+    pub height: i32,
+    pub walkable_height: f64,
+    pub walkable_radius: f64,
+    pub walkable_climb: f64,
+    pub max_simplification_error: f64,
+    pub max_tiles: i32,
+    pub max_obstacles: i32,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct DtTileCacheLayerHeader {
+    pub magic: i32,
+    pub version: i32,
+    pub tx: i32,
+    pub ty: i32,
+    pub tlayer: i32,
+    pub b_min: [f64; 3],
+    pub b_max: [f64; 3],
+    pub h_min: u16,
+    pub h_max: u16,
+    pub width: u8,
+    pub height: u8,
+    pub min_x: u8,
+    pub max_x: u8,
+    pub min_y: u8,
+    pub max_y: u8,
+}
+
+pub const DT_TILECACHE_MAGIC: i32 =
+    (b'D' as i32) << 24 | (b'T' as i32) << 16 | (b'L' as i32) << 8 | b'R' as i32;
+pub const DT_TILECACHE_VERSION: i32 = 1;
+
+pub type DtMaxCompressedSizeFn =
+    extern "C" fn(user: *mut std::ffi::c_void, buffer_size: i32) -> i32;
+pub type DtCompressFn = extern "C" fn(
+    user: *mut std::ffi::c_void,
+    buffer: *const u8,
+    buffer_size: i32,
+    compressed: *mut u8,
+    max_compressed_size: i32,
+    compressed_size: *mut i32,
+) -> DtStatus;
+pub type DtDecompressFn = extern "C" fn(
+    user: *mut std::ffi::c_void,
+    compressed: *const u8,
+    compressed_size: i32,
+    buffer: *mut u8,
+    max_buffer_size: i32,
+    buffer_size: *mut i32,
+) -> DtStatus;
+pub type DtMeshProcessFn = extern "C" fn(
+    user: *mut std::ffi::c_void,
+    poly_count: i32,
+    poly_areas: *mut u8,
+    poly_flags: *mut u16,
+);
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct DtMeshHeader {
@@ -49,10 +190,108 @@ pub struct DtMeshHeader {
     off_mesh_con_count: i32,
     off_mesh_base: i32,
     walkable_height: f64,
+    walkable_radius: f64,
     walkable_climb: f64,
     b_min: [f64; 3],
     b_max: [f64; 3],
-    bv_quant_factor: f64
+    bv_quant_factor: f64,
+}
+
+impl DtMeshHeader {
+    /// Tile x location within the nav mesh tile grid
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+
+    /// Tile y location within the nav mesh tile grid
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+
+    /// Tile layer at its location within the nav mesh tile grid
+    pub fn layer(&self) -> i32 {
+        self.layer
+    }
+
+    /// User defined id of the tile
+    pub fn user_id(&self) -> u32 {
+        self.user_id
+    }
+
+    /// Number of polygons in the tile, off-mesh connections included
+    pub fn poly_count(&self) -> i32 {
+        self.poly_count
+    }
+
+    /// Number of vertices in the tile, off-mesh connection endpoints included
+    pub fn vert_count(&self) -> i32 {
+        self.vert_count
+    }
+
+    /// Number of links allocated for the tile
+    pub fn max_link_count(&self) -> i32 {
+        self.max_link_count
+    }
+
+    /// Number of sub-meshes in the tile's detail mesh
+    pub fn detail_mesh_count(&self) -> i32 {
+        self.detail_mesh_count
+    }
+
+    /// Number of unique vertices in the tile's detail mesh, polygon vertices excluded
+    pub fn detail_vert_count(&self) -> i32 {
+        self.detail_vert_count
+    }
+
+    /// Number of triangles in the tile's detail mesh
+    pub fn detail_tri_count(&self) -> i32 {
+        self.detail_tri_count
+    }
+
+    /// Number of nodes in the tile's BV tree, zero if the tile has none
+    pub fn bv_node_count(&self) -> i32 {
+        self.bv_node_count
+    }
+
+    /// Number of off-mesh connections in the tile
+    pub fn off_mesh_con_count(&self) -> i32 {
+        self.off_mesh_con_count
+    }
+
+    /// Index of the first polygon that is an off-mesh connection
+    pub fn off_mesh_base(&self) -> i32 {
+        self.off_mesh_base
+    }
+
+    /// Height of the agents the tile was built for
+    pub fn walkable_height(&self) -> f64 {
+        self.walkable_height
+    }
+
+    /// Radius of the agents the tile was built for
+    pub fn walkable_radius(&self) -> f64 {
+        self.walkable_radius
+    }
+
+    /// Maximum ledge height the agents the tile was built for can climb
+    pub fn walkable_climb(&self) -> f64 {
+        self.walkable_climb
+    }
+
+    /// Minimum bounds of the tile's AABB
+    pub fn bmin(&self) -> DtVector {
+        DtVector::from_array(self.b_min)
+    }
+
+    /// Maximum bounds of the tile's AABB
+    pub fn bmax(&self) -> DtVector {
+        DtVector::from_array(self.b_max)
+    }
+
+    /// Factor converting world units relative to bmin into the tile's quantized BV tree space
+    pub fn bv_quant_factor(&self) -> f64 {
+        self.bv_quant_factor
+    }
 }
 
 // High level status.
@@ -61,7 +300,7 @@ pub const DT_SUCCESS: u32 = 1 << 30; // Operation succeed.
 pub const DT_IN_PROGRESS: u32 = 1 << 29; // Operation still in progress.
 
 // Detail information for status.
-// pub const DT_STATUS_DETAIL_MASK: u32 = 0x0ffffff;
+pub const DT_STATUS_DETAIL_MASK: u32 = 0x0ffffff;
 pub const DT_WRONG_MAGIC: u32 = 1 << 0; // Input data is not recognized.
 pub const DT_WRONG_VERSION: u32 = 1 << 1; // Input data is in wrong version.
 pub const DT_OUT_OF_MEMORY: u32 = 1 << 2; // Operation ran out of memory.
@@ -101,6 +340,41 @@ impl DtStatus {
     pub fn is_failed(&self) -> bool {
         self.contains(DtStatus::FAILURE)
     }
+
+    /// Detail bits of the status (OUT_OF_NODES, INVALID_PARAM, ...), without SUCCESS, FAILURE or IN_PROGRESS
+    pub fn detail(&self) -> DtStatus {
+        DtStatus::from_bits_truncate(self.bits() & DT_STATUS_DETAIL_MASK)
+    }
+
+    /// Failed status carrying the given detail bits, as Detour reports e.g. DT_FAILURE | DT_INVALID_PARAM
+    pub fn failure_with(detail: DtStatus) -> DtStatus {
+        DtStatus::FAILURE | detail
+    }
+
+    /// Successful status carrying the given detail bits, as Detour reports e.g. DT_SUCCESS | DT_PARTIAL_RESULT
+    pub fn success_with(detail: DtStatus) -> DtStatus {
+        DtStatus::SUCCESS | detail
+    }
+
+    /// Failure Detour reports for invalid input parameters
+    pub fn invalid_param() -> DtStatus {
+        DtStatus::failure_with(DtStatus::INVALID_PARAM)
+    }
+
+    /// Failure Detour reports when an allocation fails
+    pub fn out_of_memory() -> DtStatus {
+        DtStatus::failure_with(DtStatus::OUT_OF_MEMORY)
+    }
+
+    /// Success Detour reports when a search ran out of nodes, returning its best partial result
+    pub fn out_of_nodes() -> DtStatus {
+        DtStatus::success_with(DtStatus::OUT_OF_NODES | DtStatus::PARTIAL_RESULT)
+    }
+
+    /// Success Detour reports when a result buffer was too small to hold every result
+    pub fn buffer_too_small() -> DtStatus {
+        DtStatus::success_with(DtStatus::BUFFER_TOO_SMALL)
+    }
 }
 
 bitflags! {
@@ -108,14 +382,55 @@ bitflags! {
     pub struct DtStraightPathFlags: u8 {
         const START = 0x01;
         const END = 0x02;
-        const OFFMESH_CONNECTION = 0x03;
+        const OFFMESH_CONNECTION = 0x04;
+    }
+}
+
+bitflags! {
+    /// Options for dtNavMeshQuery::findStraightPath, wrapping the DT_STRAIGHTPATH_* values
+    #[repr(transparent)]
+    #[derive(Default)]
+    pub struct StraightPathOptions: i32 {
+        /// Adds a waypoint at every poly edge crossing where the area changes
+        const AREA_CROSSINGS = DT_STRAIGHTPATH_AREA_CROSSINGS;
+        /// Adds a waypoint at every poly edge crossing
+        const ALL_CROSSINGS = DT_STRAIGHTPATH_ALL_CROSSINGS;
     }
 }
 
+bitflags! {
+    /// Flags for dtNavMesh::addTile, wrapping the DT_TILE_* values
+    #[repr(transparent)]
+    #[derive(Default)]
+    pub struct TileFlags: i32 {
+        /// The dtNavMesh owns a dtAlloc'ed copy of the tile data and frees it with dtFree once the
+        /// tile is removed
+        const FREE_DATA = DT_TILE_FREE_DATA;
+    }
+}
+
+// The maximum number of user defined area ids.
+pub const DT_MAX_AREAS: usize = 64;
+
+// A link index that terminates a poly's link list.
+pub const DT_NULL_LINK: u32 = 0xffffffff;
+
+// Poly type of an off-mesh connection, as returned by dtPoly::getType.
+pub const DT_POLYTYPE_OFFMESH_CONNECTION: u8 = 1;
+
+// Flags for dtNavMesh::addTile.
+pub const DT_TILE_FREE_DATA: i32 = 0x01; // The navigation mesh owns the tile memory and is responsible for freeing it.
+
+// Options for dtNavMeshQuery::findStraightPath.
+pub const DT_STRAIGHTPATH_AREA_CROSSINGS: i32 = 0x01; // Add a vertex at every polygon edge crossing where area changes.
+pub const DT_STRAIGHTPATH_ALL_CROSSINGS: i32 = 0x02; // Add a vertex at every polygon edge crossing.
+
 #[link(name = "detour", kind = "static")]
 extern "C" {
     pub fn dtNavMesh_alloc() -> *mut DtNavMesh;
     pub fn dtNavMesh_init(_self: *mut DtNavMesh, params: *const DtNavMeshParams) -> DtStatus;
+    pub fn dtNavMesh_initSingle(_self: *mut DtNavMesh, data: *const u8, data_size: i32)
+        -> DtStatus;
     pub fn dtNavMesh_addTile(
         _self: *mut DtNavMesh,
         data: *mut u8,
@@ -124,7 +439,91 @@ extern "C" {
         last_ref: DtTileRef,
         result: *mut DtTileRef,
     ) -> DtStatus;
+    pub fn dtNavMesh_addTileCopy(
+        _self: *mut DtNavMesh,
+        data: *const u8,
+        data_size: i32,
+        flags: i32,
+        last_ref: DtTileRef,
+        result: *mut DtTileRef,
+    ) -> DtStatus;
     pub fn dtNavMesh_free(_self: *mut DtNavMesh);
+    pub fn dtNavMesh_removeTile(
+        _self: *mut DtNavMesh,
+        tile_ref: DtTileRef,
+        data: *mut *mut u8,
+        data_size: *mut i32,
+    ) -> DtStatus;
+    pub fn dtNavMesh_getParams(_self: *const DtNavMesh) -> *const DtNavMeshParams;
+    pub fn dtNavMesh_getMaxTiles(_self: *const DtNavMesh) -> i32;
+    pub fn dtNavMesh_getBaseMemoryUsage(_self: *const DtNavMesh) -> usize;
+    pub fn dtMeshHeader_getDataSize(header: *const DtMeshHeader) -> usize;
+    pub fn dtNavMesh_getTile(_self: *const DtNavMesh, index: i32) -> *const DtMeshTile;
+    pub fn dtNavMesh_getTileAt(
+        _self: *const DtNavMesh,
+        x: i32,
+        y: i32,
+        layer: i32,
+    ) -> *const DtMeshTile;
+    pub fn dtNavMesh_calcTileLoc(
+        _self: *const DtNavMesh,
+        pos: *const DtVector,
+        tx: *mut i32,
+        ty: *mut i32,
+    );
+    pub fn dtNavMesh_getTileByRef(
+        _self: *const DtNavMesh,
+        tile_ref: DtTileRef,
+    ) -> *const DtMeshTile;
+    pub fn dtNavMesh_getTileRef(_self: *const DtNavMesh, tile: *const DtMeshTile) -> DtTileRef;
+    pub fn dtNavMesh_getPolyArea(
+        _self: *const DtNavMesh,
+        poly_ref: DtPolyRef,
+        result_area: *mut u8,
+    ) -> DtStatus;
+    pub fn dtNavMesh_getPolyFlags(
+        _self: *const DtNavMesh,
+        poly_ref: DtPolyRef,
+        result_flags: *mut u16,
+    ) -> DtStatus;
+    pub fn dtNavMesh_setPolyFlags(
+        _self: *mut DtNavMesh,
+        poly_ref: DtPolyRef,
+        flags: u16,
+    ) -> DtStatus;
+    pub fn dtNavMesh_setPolyArea(_self: *mut DtNavMesh, poly_ref: DtPolyRef, area: u8) -> DtStatus;
+
+    pub fn dtNavMesh_getTileAndPolyByRef(
+        _self: *const DtNavMesh,
+        poly_ref: DtPolyRef,
+        tile: *mut *const DtMeshTile,
+        poly: *mut *const DtPoly,
+    ) -> DtStatus;
+    pub fn dtNavMesh_getOffMeshConnectionPolyEndPoints(
+        _self: *const DtNavMesh,
+        prev_ref: DtPolyRef,
+        poly_ref: DtPolyRef,
+        start_pos: *mut DtVector,
+        end_pos: *mut DtVector,
+    ) -> DtStatus;
+
+    pub fn dtMeshTile_getHeader(_self: *const DtMeshTile) -> *const DtMeshHeader;
+    pub fn dtMeshTile_getData(_self: *const DtMeshTile) -> *const u8;
+    pub fn dtMeshTile_getDataSize(_self: *const DtMeshTile) -> i32;
+    pub fn dtMeshTile_getVerts(_self: *const DtMeshTile) -> *const DtVector;
+    pub fn dtPoly_getVertCount(_self: *const DtPoly) -> u8;
+    pub fn dtPoly_getVerts(_self: *const DtPoly) -> *const u16;
+    pub fn dtPoly_getFirstLink(_self: *const DtPoly) -> u32;
+    pub fn dtPoly_getType(_self: *const DtPoly) -> u8;
+    pub fn dtMeshTile_getLinkRef(_self: *const DtMeshTile, link: u32) -> DtPolyRef;
+    pub fn dtMeshTile_getLinkNext(_self: *const DtMeshTile, link: u32) -> u32;
+
+    pub fn dtNavMeshData_create(
+        params: *const DtNavMeshCreateParams,
+        out_data: *mut *mut u8,
+        out_data_size: *mut i32,
+    ) -> bool;
+    pub fn dtNavMeshData_free(data: *mut u8);
 
     pub fn dtQueryFilter_alloc() -> *mut DtQueryFilter;
     pub fn dtQueryFilter_free(_self: *mut DtQueryFilter);
@@ -132,6 +531,8 @@ extern "C" {
     pub fn dtQueryFilter_getIncludeFlags(_self: *mut DtQueryFilter) -> u16;
     pub fn dtQueryFilter_setExcludeFlags(_self: *mut DtQueryFilter, exclude_flags: u16);
     pub fn dtQueryFilter_getExcludeFlags(_self: *mut DtQueryFilter) -> u16;
+    pub fn dtQueryFilter_setAreaCost(_self: *mut DtQueryFilter, i: i32, cost: f64);
+    pub fn dtQueryFilter_getAreaCost(_self: *const DtQueryFilter, i: i32) -> f64;
 
     pub fn dtNavMeshQuery_alloc() -> *mut DtNavMeshQuery;
     pub fn dtNavMeshQuery_init(
@@ -145,13 +546,14 @@ extern "C" {
         position: *const DtVector,
         height: *mut f64,
     ) -> DtStatus;
-    pub fn dtNavMeshQuery_findNearestPoly(
+    pub fn dtNavMeshQuery_findNearestPolyEx(
         _self: *mut DtNavMeshQuery,
         center: *const DtVector,
         extents: *const DtVector,
         filter: *const DtQueryFilter,
         nearest_ref: *mut DtPolyRef,
         nearest_point: *mut DtVector,
+        is_over_poly: *mut bool,
     ) -> DtStatus;
     pub fn dtNavMeshQuery_closestPointOnPoly(
         _self: *mut DtNavMeshQuery,
@@ -166,6 +568,14 @@ extern "C" {
         position: *const DtVector,
         closest: *mut DtVector,
     ) -> DtStatus;
+    pub fn dtNavMeshQuery_closestPointOnDetailEdges(
+        _self: *const DtNavMeshQuery,
+        poly_ref: DtPolyRef,
+        position: *const DtVector,
+        only_boundary: bool,
+        dist_sqr: *mut f64,
+        closest: *mut DtVector,
+    ) -> DtStatus;
     pub fn dtNavMeshQuery_findPath(
         _self: *mut DtNavMeshQuery,
         start_ref: DtPolyRef,
@@ -188,8 +598,39 @@ extern "C" {
         straight_path_polys: *mut DtPolyRef,
         straight_path_count: *mut i32,
         max_straight_path: i32,
-        options: i32,
+        options: StraightPathOptions,
+    ) -> DtStatus;
+    pub fn dtNavMeshQuery_initSlicedFindPath(
+        _self: *mut DtNavMeshQuery,
+        start_ref: DtPolyRef,
+        end_ref: DtPolyRef,
+        start_pos: *const DtVector,
+        end_pos: *const DtVector,
+        filter: *const DtQueryFilter,
+        options: u32,
     ) -> DtStatus;
+    pub fn dtNavMeshQuery_updateSlicedFindPath(
+        _self: *mut DtNavMeshQuery,
+        max_iter: i32,
+        done_iters: *mut i32,
+    ) -> DtStatus;
+    pub fn dtNavMeshQuery_finalizeSlicedFindPath(
+        _self: *mut DtNavMeshQuery,
+        path: *mut DtPolyRef,
+        path_count: *mut i32,
+        max_path: i32,
+    ) -> DtStatus;
+    pub fn dtNavMeshQuery_cancelSlicedFindPath(_self: *mut DtNavMeshQuery);
+    pub fn dtNavMeshQuery_getNodePoolSize(_self: *const DtNavMeshQuery) -> i32;
+    pub fn dtNavMeshQuery_getNodesUsed(_self: *const DtNavMeshQuery) -> i32;
+    pub fn dtNavMeshQuery_getPortalPoints(
+        _self: *const DtNavMeshQuery,
+        from: DtPolyRef,
+        to: DtPolyRef,
+        left: *mut DtVector,
+        right: *mut DtVector,
+    ) -> DtStatus;
+    pub fn dtNavMeshQuery_getAttachedNavMesh(_self: *const DtNavMeshQuery) -> *const DtNavMesh;
     pub fn dtNavMeshQuery_free(_self: *mut DtNavMeshQuery);
     pub fn dtNavMeshQuery_moveAlongSurface(
         _self: *mut DtNavMeshQuery,
@@ -202,4 +643,161 @@ extern "C" {
         visited_count: *mut i32,
         max_visited_size: i32,
     ) -> DtStatus;
+    pub fn dtNavMeshQuery_raycast(
+        _self: *const DtNavMeshQuery,
+        start_ref: DtPolyRef,
+        start_pos: *const DtVector,
+        end_pos: *const DtVector,
+        filter: *const DtQueryFilter,
+        t: *mut f64,
+        hit_normal: *mut DtVector,
+        path: *mut DtPolyRef,
+        path_count: *mut i32,
+        max_path: i32,
+    ) -> DtStatus;
+
+    pub fn dtCrowd_alloc() -> *mut DtCrowd;
+    pub fn dtCrowd_init(
+        _self: *mut DtCrowd,
+        max_agents: i32,
+        max_agent_radius: f64,
+        nav: *mut DtNavMesh,
+    ) -> bool;
+    pub fn dtCrowd_getAgentCount(_self: *const DtCrowd) -> i32;
+    pub fn dtCrowd_getAgent(_self: *mut DtCrowd, idx: i32) -> *const DtCrowdAgent;
+    pub fn dtCrowd_addAgent(
+        _self: *mut DtCrowd,
+        pos: *const DtVector,
+        params: *const DtCrowdAgentParams,
+    ) -> i32;
+    pub fn dtCrowd_updateAgentParameters(
+        _self: *mut DtCrowd,
+        idx: i32,
+        params: *const DtCrowdAgentParams,
+    );
+    pub fn dtCrowd_removeAgent(_self: *mut DtCrowd, idx: i32);
+    pub fn dtCrowd_requestMoveTarget(
+        _self: *mut DtCrowd,
+        idx: i32,
+        poly_ref: DtPolyRef,
+        pos: *const DtVector,
+    ) -> bool;
+    pub fn dtCrowd_update(_self: *mut DtCrowd, dt: f64);
+    pub fn dtCrowd_free(_self: *mut DtCrowd);
+    pub fn dtCrowdAgent_isActive(_self: *const DtCrowdAgent) -> bool;
+    pub fn dtCrowdAgent_getNeighbourCount(_self: *const DtCrowdAgent) -> i32;
+    pub fn dtCrowdAgent_getNeighbourIdx(_self: *const DtCrowdAgent, i: i32) -> i32;
+    pub fn dtCrowdAgent_getTargetState(_self: *const DtCrowdAgent) -> u8;
+    pub fn dtCrowdAgent_getPos(_self: *const DtCrowdAgent) -> *const DtVector;
+    pub fn dtCrowdAgent_getVel(_self: *const DtCrowdAgent) -> *const DtVector;
+    pub fn dtTileCacheAlloc_alloc() -> *mut DtTileCacheAlloc;
+    pub fn dtTileCacheAlloc_free(_self: *mut DtTileCacheAlloc);
+    pub fn dtTileCacheCompressor_alloc(
+        user: *mut std::ffi::c_void,
+        max_compressed_size: DtMaxCompressedSizeFn,
+        compress: DtCompressFn,
+        decompress: DtDecompressFn,
+    ) -> *mut DtTileCacheCompressor;
+    pub fn dtTileCacheCompressor_free(_self: *mut DtTileCacheCompressor);
+    pub fn dtTileCacheMeshProcess_alloc(
+        user: *mut std::ffi::c_void,
+        process: DtMeshProcessFn,
+    ) -> *mut DtTileCacheMeshProcess;
+    pub fn dtTileCacheMeshProcess_free(_self: *mut DtTileCacheMeshProcess);
+    pub fn dtTileCache_alloc() -> *mut DtTileCache;
+    pub fn dtTileCache_init(
+        _self: *mut DtTileCache,
+        params: *const DtTileCacheParams,
+        alloc: *mut DtTileCacheAlloc,
+        comp: *mut DtTileCacheCompressor,
+        proc: *mut DtTileCacheMeshProcess,
+    ) -> DtStatus;
+    pub fn dtTileCache_free(_self: *mut DtTileCache);
+    pub fn dtTileCache_buildTileCacheLayer(
+        _self: *mut DtTileCache,
+        header: *mut DtTileCacheLayerHeader,
+        heights: *const u8,
+        areas: *const u8,
+        cons: *const u8,
+        out_data: *mut *mut u8,
+        out_data_size: *mut i32,
+    ) -> DtStatus;
+    pub fn dtTileCache_addTile(
+        _self: *mut DtTileCache,
+        data: *const u8,
+        data_size: i32,
+        result: *mut DtCompressedTileRef,
+    ) -> DtStatus;
+    pub fn dtTileCache_addObstacle(
+        _self: *mut DtTileCache,
+        pos: *const DtVector,
+        radius: f64,
+        height: f64,
+        result: *mut DtObstacleRef,
+    ) -> DtStatus;
+    pub fn dtTileCache_addBoxObstacle(
+        _self: *mut DtTileCache,
+        b_min: *const DtVector,
+        b_max: *const DtVector,
+        result: *mut DtObstacleRef,
+    ) -> DtStatus;
+    pub fn dtTileCache_removeObstacle(
+        _self: *mut DtTileCache,
+        obstacle_ref: DtObstacleRef,
+    ) -> DtStatus;
+    pub fn dtTileCache_update(
+        _self: *mut DtTileCache,
+        dt: f64,
+        nav_mesh: *mut DtNavMesh,
+        up_to_date: *mut bool,
+    ) -> DtStatus;
+    pub fn dtTileCache_buildNavMeshTilesAt(
+        _self: *mut DtTileCache,
+        tx: i32,
+        ty: i32,
+        nav_mesh: *mut DtNavMesh,
+    ) -> DtStatus;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DtStatus;
+
+    #[test]
+    fn test_status_detail() {
+        let status = DtStatus::SUCCESS | DtStatus::PARTIAL_RESULT | DtStatus::OUT_OF_NODES;
+        assert_eq!(
+            status.detail(),
+            DtStatus::PARTIAL_RESULT | DtStatus::OUT_OF_NODES
+        );
+        assert_eq!(DtStatus::invalid_param().detail(), DtStatus::INVALID_PARAM);
+        assert!(DtStatus::IN_PROGRESS.detail().is_empty());
+    }
+
+    #[test]
+    fn test_status_constructors() {
+        let failure = DtStatus::failure_with(DtStatus::WRONG_MAGIC);
+        assert!(failure.is_failed());
+        assert!(!failure.is_success());
+        assert!(failure.contains(DtStatus::WRONG_MAGIC));
+
+        let success = DtStatus::success_with(DtStatus::PARTIAL_RESULT);
+        assert!(success.is_success());
+        assert!(!success.is_failed());
+        assert!(success.contains(DtStatus::PARTIAL_RESULT));
+
+        assert!(DtStatus::invalid_param().is_failed());
+        assert!(DtStatus::invalid_param().contains(DtStatus::INVALID_PARAM));
+        assert!(DtStatus::out_of_memory().is_failed());
+        assert!(DtStatus::out_of_memory().contains(DtStatus::OUT_OF_MEMORY));
+
+        let out_of_nodes = DtStatus::out_of_nodes();
+        assert!(out_of_nodes.is_success());
+        assert!(out_of_nodes.contains(DtStatus::OUT_OF_NODES | DtStatus::PARTIAL_RESULT));
+
+        let buffer_too_small = DtStatus::buffer_too_small();
+        assert!(buffer_too_small.is_success());
+        assert!(!buffer_too_small.is_in_progress());
+        assert!(buffer_too_small.contains(DtStatus::BUFFER_TOO_SMALL));
+    }
 }
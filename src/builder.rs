@@ -0,0 +1,525 @@
+use std::ptr;
+
+use crate::{binding::*, DivertError, DivertResult};
+
+/// Maximum number of vertices per polygon supported by Detour
+pub const DT_VERTS_PER_POLYGON: i32 = 6;
+
+/// Off-mesh connection direction allowing traversal from either endpoint
+pub const DT_OFFMESH_CON_BIDIR: u8 = 1;
+
+/// Index terminating a poly's vertex list, or marking a missing neighbour
+const MESH_NULL_IDX: u16 = 0xffff;
+/// Neighbour value flag marking a border or portal edge rather than a neighbour poly index
+const PORTAL_FLAG: u16 = 0x8000;
+/// Portal direction of a neighbour value marking a plain border edge
+const BORDER_DIR: u16 = 0xf;
+
+/// Direction an off-mesh connection can be traversed in, as stored in its dir value
+#[repr(u8)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OffMeshConnectionDirection {
+    /// Traversable from the start endpoint to the end endpoint only
+    #[default]
+    OneWay = 0,
+    /// Traversable from either endpoint
+    BiDirectional = DT_OFFMESH_CON_BIDIR,
+}
+
+/// Safe representation of dtNavMeshCreateParams
+/// Borrows Recast poly mesh (and optional detail mesh) data to be built into tile data
+/// Poly count is derived from poly_flags, detail data is optional and may be left empty
+#[derive(Debug, Default)]
+pub struct NavMeshCreateParams<'a> {
+    /// Poly mesh vertices in voxel units [(x, y, z) * vert_count]
+    pub verts: &'a [[u16; 3]],
+    /// Poly vertex indices followed by neighbour info [poly_count * 2 * nvp]
+    pub polys: &'a [u16],
+    /// User defined flags per poly \[poly_count\]
+    pub poly_flags: &'a [u16],
+    /// User defined area ids per poly \[poly_count\]
+    pub poly_areas: &'a [u8],
+    /// Maximum number of vertices per poly
+    pub nvp: i32,
+    /// Detail sub-mesh data per poly [(vert_base, vert_count, tri_base, tri_count) * poly_count]
+    pub detail_meshes: &'a [[u32; 4]],
+    /// Detail mesh vertices in world units
+    pub detail_verts: &'a [[f64; 3]],
+    /// Detail mesh triangles [(vert_a, vert_b, vert_c, flags) * detail_tri_count]
+    pub detail_tris: &'a [[u8; 4]],
+    /// Off-mesh connection endpoints in world units [(start x, y, z, end x, y, z) * connection count]
+    pub off_mesh_con_verts: &'a [[f64; 6]],
+    /// Off-mesh connection endpoint radii [connection count]
+    pub off_mesh_con_rad: &'a [f64],
+    /// User defined flags per off-mesh connection [connection count]
+    pub off_mesh_con_flags: &'a [u16],
+    /// User defined area ids per off-mesh connection [connection count]
+    pub off_mesh_con_areas: &'a [u8],
+    /// Off-mesh connection directions [connection count]
+    pub off_mesh_con_dir: &'a [OffMeshConnectionDirection],
+    /// User defined ids per off-mesh connection [connection count]
+    pub off_mesh_con_user_id: &'a [u32],
+    pub user_id: u32,
+    pub tile_x: i32,
+    pub tile_y: i32,
+    pub tile_layer: i32,
+    pub b_min: [f64; 3],
+    pub b_max: [f64; 3],
+    pub walkable_height: f64,
+    pub walkable_radius: f64,
+    pub walkable_climb: f64,
+    /// Cell size of the poly mesh on the xz-plane
+    pub cs: f64,
+    /// Cell height of the poly mesh on the y-axis
+    pub ch: f64,
+    pub build_bv_tree: bool,
+}
+
+impl<'a> NavMeshCreateParams<'a> {
+    /// Validates the borrowed slices against each other before handing raw pointers to Detour
+    fn validate(&self) -> DivertResult<()> {
+        let poly_count = self.poly_flags.len();
+
+        if !(3..=DT_VERTS_PER_POLYGON).contains(&self.nvp) {
+            return Err(DivertError::InvalidParam("nvp must be within 3..=6"));
+        }
+
+        if self.verts.is_empty() || self.verts.len() >= 0xffff {
+            return Err(DivertError::InvalidParam(
+                "verts must hold 1..65535 vertices",
+            ));
+        }
+
+        if poly_count == 0 {
+            return Err(DivertError::InvalidParam(
+                "poly mesh must hold at least one poly",
+            ));
+        }
+
+        if self.polys.len() != poly_count * 2 * self.nvp as usize
+            || self.poly_areas.len() != poly_count
+        {
+            return Err(DivertError::InvalidParam(
+                "polys and poly_areas must match poly_flags length",
+            ));
+        }
+
+        if !self.detail_meshes.is_empty() && self.detail_meshes.len() != poly_count {
+            return Err(DivertError::InvalidParam(
+                "detail_meshes must be empty or match poly_flags length",
+            ));
+        }
+
+        // Detour indexes vertices, neighbours and detail data with these values unchecked
+        let nvp = self.nvp as usize;
+        for (poly_index, poly) in self.polys.chunks_exact(2 * nvp).enumerate() {
+            let (poly_verts, neighbours) = poly.split_at(nvp);
+            let vert_count = poly_verts
+                .iter()
+                .take_while(|&&vert| vert != MESH_NULL_IDX)
+                .count();
+
+            for (&vert, &neighbour) in poly_verts.iter().zip(neighbours).take(vert_count) {
+                if vert as usize >= self.verts.len() {
+                    return Err(DivertError::InvalidParam(
+                        "poly vertex indices must be below the vertex count",
+                    ));
+                }
+
+                let valid_neighbour = if neighbour & PORTAL_FLAG != 0 {
+                    matches!(neighbour & 0xf, 0..=3 | BORDER_DIR)
+                } else {
+                    (neighbour as usize) < poly_count
+                };
+                if !valid_neighbour {
+                    return Err(DivertError::InvalidParam(
+                        "poly neighbours must be poly indices, portal directions or borders",
+                    ));
+                }
+            }
+
+            if let Some(&detail_mesh) = self.detail_meshes.get(poly_index) {
+                self.validate_detail_mesh(detail_mesh, vert_count)?;
+            }
+        }
+
+        let off_mesh_con_count = self.off_mesh_con_verts.len();
+        if self.off_mesh_con_rad.len() != off_mesh_con_count
+            || self.off_mesh_con_flags.len() != off_mesh_con_count
+            || self.off_mesh_con_areas.len() != off_mesh_con_count
+            || self.off_mesh_con_dir.len() != off_mesh_con_count
+            || self.off_mesh_con_user_id.len() != off_mesh_con_count
+        {
+            return Err(DivertError::InvalidParam(
+                "off-mesh connection arrays must match off_mesh_con_verts length",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validates a poly's detail sub-mesh against the detail vertices and triangles
+    fn validate_detail_mesh(
+        &self,
+        [vert_base, vert_count, tri_base, tri_count]: [u32; 4],
+        poly_vert_count: usize,
+    ) -> DivertResult<()> {
+        if self.detail_verts.is_empty() || self.detail_tris.is_empty() {
+            return Err(DivertError::InvalidParam(
+                "detail_verts and detail_tris must not be empty when detail_meshes is set",
+            ));
+        }
+
+        let (vert_base, vert_count) = (vert_base as usize, vert_count as usize);
+        let (tri_base, tri_count) = (tri_base as usize, tri_count as usize);
+        if vert_base + vert_count > self.detail_verts.len() {
+            return Err(DivertError::InvalidParam(
+                "detail mesh vertices must lie within detail_verts",
+            ));
+        }
+        if tri_base + tri_count > self.detail_tris.len() {
+            return Err(DivertError::InvalidParam(
+                "detail mesh triangles must lie within detail_tris",
+            ));
+        }
+
+        // The poly's own vertices lead each detail sub-mesh, Detour stores counts as u8
+        if vert_count < poly_vert_count
+            || vert_count - poly_vert_count > u8::MAX as usize
+            || tri_count > u8::MAX as usize
+        {
+            return Err(DivertError::InvalidParam(
+                "detail mesh must hold the poly's vertices and at most 255 extra vertices and triangles",
+            ));
+        }
+
+        let tris = &self.detail_tris[tri_base..tri_base + tri_count];
+        if tris
+            .iter()
+            .any(|tri| tri[..3].iter().any(|&vert| vert as usize >= vert_count))
+        {
+            return Err(DivertError::InvalidParam(
+                "detail triangle vertices must be below the detail mesh vertex count",
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn as_raw(&self) -> DtNavMeshCreateParams {
+        let detail_meshes = !self.detail_meshes.is_empty();
+
+        DtNavMeshCreateParams {
+            verts: self.verts.as_ptr().cast(),
+            vert_count: self.verts.len() as i32,
+            polys: self.polys.as_ptr(),
+            poly_flags: self.poly_flags.as_ptr(),
+            poly_areas: self.poly_areas.as_ptr(),
+            poly_count: self.poly_flags.len() as i32,
+            nvp: self.nvp,
+            detail_meshes: if detail_meshes {
+                self.detail_meshes.as_ptr().cast()
+            } else {
+                ptr::null()
+            },
+            detail_verts: if detail_meshes {
+                self.detail_verts.as_ptr().cast()
+            } else {
+                ptr::null()
+            },
+            detail_verts_count: self.detail_verts.len() as i32,
+            detail_tris: if detail_meshes {
+                self.detail_tris.as_ptr().cast()
+            } else {
+                ptr::null()
+            },
+            detail_tri_count: self.detail_tris.len() as i32,
+            off_mesh_con_verts: self.off_mesh_con_verts.as_ptr().cast(),
+            off_mesh_con_rad: self.off_mesh_con_rad.as_ptr(),
+            off_mesh_con_flags: self.off_mesh_con_flags.as_ptr(),
+            off_mesh_con_areas: self.off_mesh_con_areas.as_ptr(),
+            off_mesh_con_dir: self.off_mesh_con_dir.as_ptr().cast(),
+            off_mesh_con_user_id: self.off_mesh_con_user_id.as_ptr(),
+            off_mesh_con_count: self.off_mesh_con_verts.len() as i32,
+            user_id: self.user_id,
+            tile_x: self.tile_x,
+            tile_y: self.tile_y,
+            tile_layer: self.tile_layer,
+            b_min: self.b_min,
+            b_max: self.b_max,
+            walkable_height: self.walkable_height,
+            walkable_radius: self.walkable_radius,
+            walkable_climb: self.walkable_climb,
+            cs: self.cs,
+            ch: self.ch,
+            build_bv_tree: self.build_bv_tree,
+        }
+    }
+}
+
+/// Builds dtTile data from Recast poly mesh data with dtCreateNavMeshData
+/// The returned bytes are copied out of Detour's allocation and can be passed to NavMesh::add_tile
+/// Errors if the params are inconsistent, or dtCreateNavMeshData reports a failure
+pub fn create_nav_mesh_data(params: &NavMeshCreateParams) -> DivertResult<Vec<u8>> {
+    params.validate()?;
+
+    let raw_params = params.as_raw();
+    let mut data: *mut u8 = ptr::null_mut();
+    let mut data_size = 0;
+
+    let created = unsafe { dtNavMeshData_create(&raw_params, &mut data, &mut data_size) };
+    if !created || data.is_null() {
+        return Err(DivertError::CreateNavMeshDataFailure());
+    }
+
+    let tile_data = unsafe { std::slice::from_raw_parts(data, data_size as usize) }.to_vec();
+    unsafe { dtNavMeshData_free(data) };
+
+    Ok(tile_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create_nav_mesh_data, NavMeshCreateParams, OffMeshConnectionDirection};
+    use crate::{
+        fixtures, DivertError, DtStraightPathFlags, NavMesh, NavMeshQuery, PolyRef, QueryFilter,
+        StraightPathOptions,
+    };
+
+    #[test]
+    fn test_create_nav_mesh_data_add_tile() {
+        let tile_data = fixtures::create_tile_data(0, 0, &["...", "...", "..."]);
+        assert!(!tile_data.is_empty());
+
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(3, 3, 1)).unwrap();
+        nav_mesh.add_tile(tile_data).unwrap();
+
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(2, 2);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+        assert_ne!(start_ref, PolyRef::default());
+        assert_ne!(end_ref, PolyRef::default());
+        assert_ne!(start_ref, end_ref);
+
+        let path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+            .unwrap();
+        assert_eq!(path.first(), Some(&start_ref));
+        assert_eq!(path.last(), Some(&end_ref));
+    }
+
+    #[test]
+    fn test_create_nav_mesh_data_invalid_params() {
+        let params = NavMeshCreateParams {
+            verts: &[[0, 0, 0], [1, 0, 0], [1, 0, 1]],
+            polys: &[0, 1, 2],
+            poly_flags: &[1],
+            poly_areas: &[0],
+            nvp: 3,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            create_nav_mesh_data(&params),
+            Err(DivertError::InvalidParam(_))
+        ));
+    }
+
+    fn triangle_params() -> NavMeshCreateParams<'static> {
+        NavMeshCreateParams {
+            verts: &[[0, 0, 0], [0, 0, 4], [4, 0, 0]],
+            polys: &[0, 1, 2, 0xffff, 0xffff, 0xffff],
+            poly_flags: &[1],
+            poly_areas: &[0],
+            nvp: 3,
+            detail_meshes: &[[0, 3, 0, 1]],
+            detail_verts: &[[0.0, 0.0, 0.0], [0.0, 0.0, 4.0], [4.0, 0.0, 0.0]],
+            detail_tris: &[[0, 1, 2, 0b010101]],
+            b_max: [4.0, 1.0, 4.0],
+            cs: 1.0,
+            ch: 1.0,
+            ..Default::default()
+        }
+    }
+
+    fn assert_invalid(params: &NavMeshCreateParams) {
+        assert!(matches!(
+            create_nav_mesh_data(params),
+            Err(DivertError::InvalidParam(_))
+        ));
+    }
+
+    #[test]
+    fn test_create_nav_mesh_data_validates_polys() {
+        assert!(create_nav_mesh_data(&triangle_params()).is_ok());
+
+        assert_invalid(&NavMeshCreateParams {
+            polys: &[0, 1, 3, 0xffff, 0xffff, 0xffff],
+            ..triangle_params()
+        });
+        assert_invalid(&NavMeshCreateParams {
+            polys: &[0, 1, 2, 1, 0xffff, 0xffff],
+            ..triangle_params()
+        });
+        assert_invalid(&NavMeshCreateParams {
+            polys: &[0, 1, 2, 0x8004, 0xffff, 0xffff],
+            ..triangle_params()
+        });
+
+        // Vertex slots after the terminating MESH_NULL_IDX aren't read
+        assert!(create_nav_mesh_data(&NavMeshCreateParams {
+            polys: &[0, 1, 2, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff],
+            nvp: 4,
+            ..triangle_params()
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn test_create_nav_mesh_data_validates_detail_meshes() {
+        assert_invalid(&NavMeshCreateParams {
+            detail_verts: &[],
+            ..triangle_params()
+        });
+        assert_invalid(&NavMeshCreateParams {
+            detail_tris: &[],
+            ..triangle_params()
+        });
+        assert_invalid(&NavMeshCreateParams {
+            detail_meshes: &[[1, 3, 0, 1]],
+            ..triangle_params()
+        });
+        assert_invalid(&NavMeshCreateParams {
+            detail_meshes: &[[0, 3, 1, 1]],
+            ..triangle_params()
+        });
+        assert_invalid(&NavMeshCreateParams {
+            detail_meshes: &[[0, 2, 0, 1]],
+            ..triangle_params()
+        });
+        assert_invalid(&NavMeshCreateParams {
+            detail_tris: &[[0, 1, 3, 0b010101]],
+            ..triangle_params()
+        });
+    }
+
+    #[test]
+    fn test_create_nav_mesh_data_off_mesh_connection() {
+        let start = fixtures::cell_center(1, 0);
+        let end = fixtures::cell_center(3, 0);
+        let tile_data = fixtures::create_tile_data_with(
+            0,
+            0,
+            &["..#.."],
+            &NavMeshCreateParams {
+                off_mesh_con_verts: &[[start.x, start.y, start.z, end.x, end.y, end.z]],
+                off_mesh_con_rad: &[1.0],
+                off_mesh_con_flags: &[fixtures::GROUND_FLAG],
+                off_mesh_con_areas: &[fixtures::GROUND_AREA],
+                off_mesh_con_dir: &[OffMeshConnectionDirection::BiDirectional],
+                off_mesh_con_user_id: &[7],
+                ..Default::default()
+            },
+        );
+
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(5, 1, 1)).unwrap();
+        nav_mesh.add_tile(tile_data).unwrap();
+
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let mut filter = QueryFilter::new().unwrap();
+        filter.set_include_flags(fixtures::GROUND_FLAG);
+
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(4, 0);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+
+        let path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+            .unwrap();
+        assert_eq!(path.last(), Some(&end_ref));
+
+        let straight_path = nav_mesh_query
+            .find_straight_path(
+                &start_pos,
+                &end_pos,
+                &path,
+                16,
+                StraightPathOptions::empty(),
+            )
+            .unwrap();
+        assert!(straight_path
+            .iter()
+            .any(|(_, flags, _)| flags.contains(DtStraightPathFlags::OFFMESH_CONNECTION)));
+    }
+
+    #[test]
+    fn test_off_mesh_connection_direction() {
+        let connection = |z| {
+            let start = fixtures::cell_center(1, z);
+            let end = fixtures::cell_center(3, z);
+            [start.x, start.y, start.z, end.x, end.y, end.z]
+        };
+        let tile_data = fixtures::create_tile_data_with(
+            0,
+            0,
+            &["..#..", "#####", "..#.."],
+            &NavMeshCreateParams {
+                off_mesh_con_verts: &[connection(0), connection(2)],
+                off_mesh_con_rad: &[1.0, 1.0],
+                off_mesh_con_flags: &[fixtures::GROUND_FLAG, fixtures::GROUND_FLAG],
+                off_mesh_con_areas: &[fixtures::GROUND_AREA, fixtures::GROUND_AREA],
+                off_mesh_con_dir: &[
+                    OffMeshConnectionDirection::BiDirectional,
+                    OffMeshConnectionDirection::OneWay,
+                ],
+                off_mesh_con_user_id: &[1, 2],
+                ..Default::default()
+            },
+        );
+
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(5, 3, 1)).unwrap();
+        nav_mesh.add_tile(tile_data).unwrap();
+
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let reaches = |from: (i32, i32), to: (i32, i32)| {
+            let start_pos = fixtures::cell_center(from.0, from.1);
+            let end_pos = fixtures::cell_center(to.0, to.1);
+            let (start_ref, end_ref) =
+                fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+
+            nav_mesh_query
+                .is_reachable(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+                .unwrap()
+        };
+
+        assert!(reaches((0, 0), (4, 0)));
+        assert!(reaches((4, 0), (0, 0)));
+        assert!(reaches((0, 2), (4, 2)));
+        assert!(!reaches((4, 2), (0, 2)));
+    }
+
+    #[test]
+    fn test_create_nav_mesh_data_mismatched_off_mesh_connection() {
+        let params = NavMeshCreateParams {
+            verts: &[[0, 0, 0], [1, 0, 0], [1, 0, 1]],
+            polys: &[0, 1, 2, 0xffff, 0xffff, 0xffff],
+            poly_flags: &[1],
+            poly_areas: &[0],
+            nvp: 3,
+            off_mesh_con_verts: &[[0.0; 6]],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            create_nav_mesh_data(&params),
+            Err(DivertError::InvalidParam(_))
+        ));
+    }
+}
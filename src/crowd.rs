@@ -0,0 +1,542 @@
+use std::{marker, ptr};
+
+use crate::{binding::*, check_finite, DivertError, DivertResult, NavMesh, PolyRef, Vector};
+
+/// Steers agents around the corners of their path ahead of time
+pub const DT_CROWD_ANTICIPATE_TURNS: u8 = 1;
+/// Avoids collisions with other agents through velocity sampling
+pub const DT_CROWD_OBSTACLE_AVOIDANCE: u8 = 2;
+/// Pushes agents apart according to their separation_weight
+pub const DT_CROWD_SEPARATION: u8 = 4;
+/// Shortcuts the path corridor along visible segments
+pub const DT_CROWD_OPTIMIZE_VIS: u8 = 8;
+/// Periodically replans the path corridor locally
+pub const DT_CROWD_OPTIMIZE_TOPO: u8 = 16;
+
+/// Number of obstacle avoidance configurations of a dtCrowd, bounding obstacle_avoidance_type
+pub const DT_CROWD_MAX_OBSTAVOIDANCE_PARAMS: u8 = 8;
+/// Number of query filters of a dtCrowd, bounding query_filter_type
+pub const DT_CROWD_MAX_QUERY_FILTER_TYPE: u8 = 16;
+
+/// Safe representation of dtCrowdAgentParams
+/// Configures how an agent moves and steers within a Crowd
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CrowdAgentParams {
+    /// Agent radius, at most the Crowd's max_agent_radius
+    pub radius: f64,
+    /// Agent height
+    pub height: f64,
+    /// Maximum allowed acceleration
+    pub max_acceleration: f64,
+    /// Maximum allowed speed
+    pub max_speed: f64,
+    /// How close other agents and walls must be to be considered for steering
+    pub collision_query_range: f64,
+    /// Range of the path visibility optimization
+    pub path_optimization_range: f64,
+    /// How strongly other agents are pushed away when DT_CROWD_SEPARATION is set
+    pub separation_weight: f64,
+    /// Combination of the DT_CROWD_* steering flags
+    pub update_flags: u8,
+    /// Index of the obstacle avoidance configuration used by the agent
+    pub obstacle_avoidance_type: u8,
+    /// Index of the crowd query filter used by the agent
+    pub query_filter_type: u8,
+}
+
+impl CrowdAgentParams {
+    /// Checks the params can be handed to a dtCrowd initialized with max_agent_radius
+    /// dtCrowd indexes its filter and obstacle avoidance arrays with the params unchecked
+    /// Errors if a type index is out of range, a value isn't finite, or radius exceeds max_agent_radius
+    pub fn validate(&self, max_agent_radius: f64) -> DivertResult<()> {
+        if self.query_filter_type >= DT_CROWD_MAX_QUERY_FILTER_TYPE {
+            return Err(DivertError::InvalidParam(
+                "query_filter_type must be below DT_CROWD_MAX_QUERY_FILTER_TYPE",
+            ));
+        }
+        if self.obstacle_avoidance_type >= DT_CROWD_MAX_OBSTAVOIDANCE_PARAMS {
+            return Err(DivertError::InvalidParam(
+                "obstacle_avoidance_type must be below DT_CROWD_MAX_OBSTAVOIDANCE_PARAMS",
+            ));
+        }
+
+        let values = [
+            self.radius,
+            self.height,
+            self.max_acceleration,
+            self.max_speed,
+            self.collision_query_range,
+            self.path_optimization_range,
+            self.separation_weight,
+        ];
+        if !values.iter().all(|value| value.is_finite()) {
+            return Err(DivertError::InvalidParam("agent params must be finite"));
+        }
+        if self.radius > max_agent_radius {
+            return Err(DivertError::InvalidParam(
+                "radius must not exceed the crowd's max_agent_radius",
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn to_dt_params(self) -> DtCrowdAgentParams {
+        DtCrowdAgentParams {
+            radius: self.radius,
+            height: self.height,
+            max_acceleration: self.max_acceleration,
+            max_speed: self.max_speed,
+            collision_query_range: self.collision_query_range,
+            path_optimization_range: self.path_optimization_range,
+            separation_weight: self.separation_weight,
+            update_flags: self.update_flags,
+            obstacle_avoidance_type: self.obstacle_avoidance_type,
+            query_filter_type: self.query_filter_type,
+            user_data: ptr::null_mut(),
+        }
+    }
+}
+
+/// State of an agent's move request, mirroring Detour's MoveRequestState
+/// Detour has no arrived state, an agent that reached its target stays Valid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveRequestState {
+    /// No move target was requested
+    None,
+    /// Planning a path to the target failed
+    Failed,
+    /// The agent has a path to the target and follows it
+    Valid,
+    /// A path to the target was requested and is about to be planned
+    Requesting,
+    /// Waiting for a slot in the crowd's path queue
+    WaitingForQueue,
+    /// Waiting for the path queue to finish planning
+    WaitingForPath,
+    /// The agent moves with a requested velocity rather than toward a target
+    Velocity,
+}
+
+impl MoveRequestState {
+    fn from_raw(target_state: u8) -> Option<Self> {
+        match target_state {
+            0 => Some(MoveRequestState::None),
+            1 => Some(MoveRequestState::Failed),
+            2 => Some(MoveRequestState::Valid),
+            3 => Some(MoveRequestState::Requesting),
+            4 => Some(MoveRequestState::WaitingForQueue),
+            5 => Some(MoveRequestState::WaitingForPath),
+            6 => Some(MoveRequestState::Velocity),
+            _ => None,
+        }
+    }
+}
+
+/// Safe bindings to dtCrowd
+/// Handles life time of the dtCrowd and will release resources when dropped
+/// Borrows the NavMesh it plans on, so tiles can't be added or removed while agents use them
+pub struct Crowd<'a> {
+    handle: *mut DtCrowd,
+    max_agent_radius: f64,
+    _phantom: marker::PhantomData<&'a DtNavMesh>,
+}
+
+// SAFETY: The dtCrowd, along with the dtNavMeshQuery, path queue and proximity grid it allocates,
+// is owned by this Crowd alone and only reached through its methods, which take &mut self to mutate
+// Detour keeps no thread-local state, so the dtCrowd can be updated and freed from any thread
+// The dtNavMesh it plans on stays borrowed for 'a, so it can't be mutated or freed meanwhile, and
+// the dtCrowd only reads it through its const dtNavMesh*, as queries do on a SharedNavMesh
+unsafe impl Send for Crowd<'_> {}
+
+/// Provides functionality to interact with Crowd and its underlying dtCrowd
+impl<'a> Crowd<'a> {
+    /// Allocates and initializes a dtCrowd managing up to max_agents agents on nav_mesh
+    /// Errors if allocation returns a null pointer, or the dtCrowd->init function fails
+    pub fn new(
+        nav_mesh: &'a NavMesh,
+        max_agents: i32,
+        max_agent_radius: f64,
+    ) -> DivertResult<Self> {
+        let dt_crowd = unsafe { dtCrowd_alloc() };
+
+        if dt_crowd.is_null() {
+            return Err(DivertError::NullPtr());
+        }
+
+        if !unsafe { dtCrowd_init(dt_crowd, max_agents, max_agent_radius, nav_mesh.handle) } {
+            unsafe { dtCrowd_free(dt_crowd) };
+            return Err(DivertError::CrowdInitFailure());
+        }
+
+        Ok(Self {
+            handle: dt_crowd,
+            max_agent_radius,
+            _phantom: marker::PhantomData,
+        })
+    }
+
+    /// Adds an agent at position, constrained to the nav mesh surface, returning its index
+    /// Errors if position isn't finite, params are invalid (see CrowdAgentParams::validate)
+    /// or the crowd is full
+    pub fn add_agent(&mut self, position: &Vector, params: &CrowdAgentParams) -> DivertResult<i32> {
+        check_finite(position, "position must be finite")?;
+        params.validate(self.max_agent_radius)?;
+
+        let idx = unsafe { dtCrowd_addAgent(self.handle, position, &params.to_dt_params()) };
+        if idx < 0 {
+            return Err(DivertError::AddAgentFailure());
+        }
+
+        Ok(idx)
+    }
+
+    /// Replaces the parameters of the agent at idx, for instance to change its speed
+    /// The agent keeps its position and move target
+    /// Errors if idx doesn't reference an active agent, or params are invalid
+    /// (see CrowdAgentParams::validate)
+    pub fn update_agent_params(&mut self, idx: i32, params: &CrowdAgentParams) -> DivertResult<()> {
+        self.agent(idx)?;
+        params.validate(self.max_agent_radius)?;
+        unsafe { dtCrowd_updateAgentParameters(self.handle, idx, &params.to_dt_params()) };
+
+        Ok(())
+    }
+
+    /// Removes the agent at idx, freeing its slot for later add_agent calls
+    /// Errors if idx doesn't reference an active agent
+    pub fn remove_agent(&mut self, idx: i32) -> DivertResult<()> {
+        self.agent(idx)?;
+        unsafe { dtCrowd_removeAgent(self.handle, idx) };
+
+        Ok(())
+    }
+
+    /// Requests the agent at idx to move to the given (poly, position)
+    /// The path is planned asynchronously over the following update calls
+    /// Errors if idx doesn't reference an active agent, position isn't finite, or the request is rejected
+    pub fn request_move_target(
+        &mut self,
+        idx: i32,
+        poly_ref: PolyRef,
+        position: &Vector,
+    ) -> DivertResult<()> {
+        self.agent(idx)?;
+        check_finite(position, "position must be finite")?;
+        if !unsafe { dtCrowd_requestMoveTarget(self.handle, idx, poly_ref, position) } {
+            return Err(DivertError::RequestMoveTargetFailure());
+        }
+
+        Ok(())
+    }
+
+    /// Advances the simulation by dt seconds, steering and moving every active agent
+    pub fn update(&mut self, dt: f64) {
+        unsafe { dtCrowd_update(self.handle, dt) }
+    }
+
+    /// Retrieves the current position of the agent at idx
+    /// Errors if idx doesn't reference an active agent
+    pub fn agent_position(&self, idx: i32) -> DivertResult<Vector> {
+        let agent = self.agent(idx)?;
+
+        Ok(unsafe { *dtCrowdAgent_getPos(agent) })
+    }
+
+    /// Retrieves the current velocity of the agent at idx
+    /// Errors if idx doesn't reference an active agent
+    pub fn agent_velocity(&self, idx: i32) -> DivertResult<Vector> {
+        let agent = self.agent(idx)?;
+
+        Ok(unsafe { *dtCrowdAgent_getVel(agent) })
+    }
+
+    /// Indices of the agents near the agent at idx, nearest first
+    /// Neighbours within the agent's collision_query_range are gathered by update
+    /// Errors if idx doesn't reference an active agent
+    pub fn agent_neighbors(&self, idx: i32) -> DivertResult<Vec<i32>> {
+        let agent = self.agent(idx)?;
+        let neighbour_count = unsafe { dtCrowdAgent_getNeighbourCount(agent) };
+
+        Ok((0..neighbour_count)
+            .map(|i| unsafe { dtCrowdAgent_getNeighbourIdx(agent, i) })
+            .collect())
+    }
+
+    /// Retrieves the state of the move request of the agent at idx
+    /// Errors if idx doesn't reference an active agent
+    pub fn agent_target_state(&self, idx: i32) -> DivertResult<MoveRequestState> {
+        let agent = self.agent(idx)?;
+
+        MoveRequestState::from_raw(unsafe { dtCrowdAgent_getTargetState(agent) })
+            .ok_or(DivertError::InvalidParam("unknown agent target state"))
+    }
+
+    /// Maximum number of agents the crowd can manage, as set by max_agents on initialization
+    pub fn max_agents(&self) -> i32 {
+        unsafe { dtCrowd_getAgentCount(self.handle) }
+    }
+
+    /// Largest agent radius the crowd accepts, as set by max_agent_radius on initialization
+    pub fn max_agent_radius(&self) -> f64 {
+        self.max_agent_radius
+    }
+
+    /// Number of active agents
+    pub fn agent_count(&self) -> i32 {
+        self.active_agents().count() as i32
+    }
+
+    /// Indices of every active agent, in ascending order
+    pub fn active_agent_indices(&self) -> Vec<i32> {
+        self.active_agents().collect()
+    }
+
+    fn active_agents(&self) -> impl Iterator<Item = i32> + '_ {
+        (0..self.max_agents()).filter(|&idx| self.agent(idx).is_ok())
+    }
+
+    /// Looks up the agent at idx
+    /// Errors if idx doesn't reference an active agent
+    fn agent(&self, idx: i32) -> DivertResult<*const DtCrowdAgent> {
+        let agent = unsafe { dtCrowd_getAgent(self.handle, idx) };
+        if agent.is_null() || !unsafe { dtCrowdAgent_isActive(agent) } {
+            return Err(DivertError::InvalidParam(
+                "idx does not reference an active agent",
+            ));
+        }
+
+        Ok(agent)
+    }
+}
+
+/// Handles freeing the inner dtCrowd
+impl<'a> Drop for Crowd<'a> {
+    /// Frees dtCrowd resources with dtFreeCrowd
+    fn drop(&mut self) {
+        unsafe { dtCrowd_free(self.handle) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Crowd, CrowdAgentParams, MoveRequestState, DT_CROWD_MAX_OBSTAVOIDANCE_PARAMS,
+        DT_CROWD_MAX_QUERY_FILTER_TYPE,
+    };
+    use crate::{fixtures, DivertError, NavMeshQuery, QueryFilter, Vector};
+
+    fn agent_params() -> CrowdAgentParams {
+        CrowdAgentParams {
+            radius: 0.5,
+            height: 2.0,
+            max_acceleration: 8.0,
+            max_speed: 3.5,
+            collision_query_range: 6.0,
+            path_optimization_range: 15.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_crowd_active_agents() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["....", "....", "...."]);
+        let mut crowd = Crowd::new(&nav_mesh, 4, 1.0).unwrap();
+        let params = agent_params();
+        assert_eq!(crowd.max_agents(), 4);
+        assert_eq!(crowd.max_agent_radius(), 1.0);
+        assert_eq!(crowd.agent_count(), 0);
+
+        let first = crowd
+            .add_agent(&fixtures::cell_center(1, 1), &params)
+            .unwrap();
+        let second = crowd
+            .add_agent(&fixtures::cell_center(2, 1), &params)
+            .unwrap();
+        let removed = crowd
+            .add_agent(&fixtures::cell_center(3, 1), &params)
+            .unwrap();
+        crowd.remove_agent(removed).unwrap();
+
+        assert_eq!(crowd.agent_count(), 2);
+        assert_eq!(crowd.active_agent_indices(), vec![first, second]);
+        assert!(matches!(
+            crowd.agent_position(removed),
+            Err(DivertError::InvalidParam(_))
+        ));
+        assert!(crowd
+            .agent_position(second)
+            .unwrap()
+            .abs_diff_eq(fixtures::cell_center(2, 1), 1e-9));
+    }
+
+    #[test]
+    fn test_crowd_add_agent_invalid_params() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...."]);
+        let mut crowd = Crowd::new(&nav_mesh, 1, 1.0).unwrap();
+        let position = fixtures::cell_center(1, 0);
+
+        let invalid_params = [
+            CrowdAgentParams {
+                query_filter_type: DT_CROWD_MAX_QUERY_FILTER_TYPE,
+                ..agent_params()
+            },
+            CrowdAgentParams {
+                obstacle_avoidance_type: DT_CROWD_MAX_OBSTAVOIDANCE_PARAMS,
+                ..agent_params()
+            },
+            CrowdAgentParams {
+                radius: 1.5,
+                ..agent_params()
+            },
+            CrowdAgentParams {
+                max_speed: f64::NAN,
+                ..agent_params()
+            },
+            CrowdAgentParams {
+                height: f64::INFINITY,
+                ..agent_params()
+            },
+        ];
+        for params in &invalid_params {
+            assert!(matches!(
+                crowd.add_agent(&position, params),
+                Err(DivertError::InvalidParam(_))
+            ));
+        }
+        assert_eq!(crowd.agent_count(), 0);
+
+        let params = CrowdAgentParams {
+            query_filter_type: DT_CROWD_MAX_QUERY_FILTER_TYPE - 1,
+            obstacle_avoidance_type: DT_CROWD_MAX_OBSTAVOIDANCE_PARAMS - 1,
+            radius: 1.0,
+            ..agent_params()
+        };
+        crowd.add_agent(&position, &params).unwrap();
+    }
+
+    #[test]
+    fn test_crowd_update_agent_params() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["........"]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let target_pos = fixtures::cell_center(7, 0);
+        let (target_ref, _) = nav_mesh_query
+            .find_nearest_poly(&target_pos, &Vector::new(1.0, 2.0, 1.0), &filter)
+            .unwrap();
+
+        let mut crowd = Crowd::new(&nav_mesh, 1, 1.0).unwrap();
+        let mut params = CrowdAgentParams {
+            max_acceleration: 100.0,
+            max_speed: 1.0,
+            ..agent_params()
+        };
+        let idx = crowd
+            .add_agent(&fixtures::cell_center(0, 0), &params)
+            .unwrap();
+        crowd
+            .request_move_target(idx, target_ref, &target_pos)
+            .unwrap();
+
+        let travel = |crowd: &mut Crowd| {
+            let start = crowd.agent_position(idx).unwrap();
+            for _ in 0..10 {
+                crowd.update(0.1);
+            }
+            crowd.agent_position(idx).unwrap().distance(start)
+        };
+
+        travel(&mut crowd);
+        let slow = travel(&mut crowd);
+        assert!(slow > 0.5 && slow <= 1.0 + 1e-9);
+
+        params.max_speed = 2.0;
+        crowd.update_agent_params(idx, &params).unwrap();
+        travel(&mut crowd);
+        let fast = travel(&mut crowd);
+        assert!(fast > 1.5 && fast <= 2.0 + 1e-9);
+
+        assert!(matches!(
+            crowd.update_agent_params(1, &params),
+            Err(DivertError::InvalidParam(_))
+        ));
+
+        let out_of_range = CrowdAgentParams {
+            query_filter_type: DT_CROWD_MAX_QUERY_FILTER_TYPE,
+            ..params
+        };
+        assert!(matches!(
+            crowd.update_agent_params(idx, &out_of_range),
+            Err(DivertError::InvalidParam(_))
+        ));
+    }
+
+    #[test]
+    fn test_crowd_agent_neighbors() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["....", "....", "...."]);
+        let mut crowd = Crowd::new(&nav_mesh, 3, 1.0).unwrap();
+        let params = agent_params();
+
+        let first = crowd
+            .add_agent(&fixtures::cell_center(1, 1), &params)
+            .unwrap();
+        let second = crowd
+            .add_agent(
+                &(fixtures::cell_center(1, 1) + Vector::new(2.0, 0.0, 0.0)),
+                &params,
+            )
+            .unwrap();
+        let far = crowd
+            .add_agent(
+                &fixtures::cell_center(3, 2),
+                &CrowdAgentParams {
+                    collision_query_range: 1.0,
+                    ..params
+                },
+            )
+            .unwrap();
+        assert!(crowd.agent_neighbors(first).unwrap().is_empty());
+
+        crowd.update(0.1);
+        assert_eq!(crowd.agent_neighbors(first).unwrap(), vec![second]);
+        assert_eq!(crowd.agent_neighbors(second).unwrap(), vec![first]);
+        assert!(crowd.agent_neighbors(far).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_crowd_agent_target_state() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let target_pos = fixtures::cell_center(3, 0);
+        let (target_ref, _) = nav_mesh_query
+            .find_nearest_poly(&target_pos, &Vector::new(1.0, 2.0, 1.0), &filter)
+            .unwrap();
+
+        let mut crowd = Crowd::new(&nav_mesh, 1, 1.0).unwrap();
+        let idx = crowd
+            .add_agent(&fixtures::cell_center(0, 0), &agent_params())
+            .unwrap();
+        assert_eq!(
+            crowd.agent_target_state(idx).unwrap(),
+            MoveRequestState::None
+        );
+
+        crowd
+            .request_move_target(idx, target_ref, &target_pos)
+            .unwrap();
+        assert_eq!(
+            crowd.agent_target_state(idx).unwrap(),
+            MoveRequestState::Requesting
+        );
+
+        for _ in 0..100 {
+            crowd.update(0.1);
+        }
+        assert_eq!(
+            crowd.agent_target_state(idx).unwrap(),
+            MoveRequestState::Valid
+        );
+        assert!(crowd.agent_position(idx).unwrap().distance(target_pos) < 0.5);
+    }
+}
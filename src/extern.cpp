@@ -1,6 +1,71 @@
+#include "../recastnavigation/Detour/Include/DetourAlloc.h"
+#include "../recastnavigation/Detour/Include/DetourCommon.h"
 #include "../recastnavigation/Detour/Include/DetourStatus.h"
 #include "../recastnavigation/Detour/Include/DetourNavMesh.h"
+#include "../recastnavigation/Detour/Include/DetourNavMeshBuilder.h"
 #include "../recastnavigation/Detour/Include/DetourNavMeshQuery.h"
+#include "../recastnavigation/Detour/Include/DetourNode.h"
+#include "../recastnavigation/DetourCrowd/Include/DetourCrowd.h"
+#include "../recastnavigation/DetourTileCache/Include/DetourTileCache.h"
+#include "../recastnavigation/DetourTileCache/Include/DetourTileCacheBuilder.h"
+
+#include <new>
+#include <string.h>
+#include <float.h>
+
+extern "C"
+{
+  typedef int (*dtMaxCompressedSizeFn)(void *user, int bufferSize);
+  typedef dtStatus (*dtCompressFn)(void *user, const unsigned char *buffer, int bufferSize,
+                                   unsigned char *compressed, int maxCompressedSize, int *compressedSize);
+  typedef dtStatus (*dtDecompressFn)(void *user, const unsigned char *compressed, int compressedSize,
+                                     unsigned char *buffer, int maxBufferSize, int *bufferSize);
+  typedef void (*dtMeshProcessFn)(void *user, int polyCount, unsigned char *polyAreas, unsigned short *polyFlags);
+}
+
+// Forwards dtTileCacheCompressor calls to callbacks implemented in Rust
+struct dtCallbackCompressor : public dtTileCacheCompressor
+{
+  void *user;
+  dtMaxCompressedSizeFn maxCompressedSizeFn;
+  dtCompressFn compressFn;
+  dtDecompressFn decompressFn;
+
+  virtual int maxCompressedSize(const int bufferSize)
+  {
+    return maxCompressedSizeFn(user, bufferSize);
+  }
+
+  virtual dtStatus compress(const unsigned char *buffer, const int bufferSize,
+                            unsigned char *compressed, const int maxCompressedSize, int *compressedSize)
+  {
+    return compressFn(user, buffer, bufferSize, compressed, maxCompressedSize, compressedSize);
+  }
+
+  virtual dtStatus decompress(const unsigned char *compressed, const int compressedSize,
+                              unsigned char *buffer, const int maxBufferSize, int *bufferSize)
+  {
+    return decompressFn(user, compressed, compressedSize, buffer, maxBufferSize, bufferSize);
+  }
+};
+
+// Forwards dtTileCacheMeshProcess calls to a callback implemented in Rust
+struct dtCallbackMeshProcess : public dtTileCacheMeshProcess
+{
+  void *user;
+  dtMeshProcessFn processFn;
+
+  virtual void process(struct dtNavMeshCreateParams *params, unsigned char *polyAreas, unsigned short *polyFlags)
+  {
+    processFn(user, params->polyCount, polyAreas, polyFlags);
+  }
+};
+
+// Size_t counterpart of dtAlign4, so oversized counts can't overflow an int
+static size_t dtAlign4Size(size_t x)
+{
+  return (x + 3) & ~(size_t)3;
+}
 
 extern "C"
 {
@@ -15,9 +80,17 @@ extern "C"
     return mesh->init(params);
   }
 
-  dtStatus dtNavMesh_initSingle(dtNavMesh *mesh, unsigned char *data, int dataSize, int flags)
+  dtStatus dtNavMesh_initSingle(dtNavMesh *mesh, const unsigned char *data, int dataSize)
   {
-    return mesh->init(data, dataSize, flags);
+    unsigned char *owned = (unsigned char *)dtAlloc(dataSize, DT_ALLOC_PERM);
+    if (!owned)
+      return DT_FAILURE | DT_OUT_OF_MEMORY;
+    memcpy(owned, data, dataSize);
+
+    dtStatus status = mesh->init(owned, dataSize, DT_TILE_FREE_DATA);
+    if (dtStatusFailed(status))
+      dtFree(owned);
+    return status;
   }
 
   dtStatus dtNavMesh_addTile(dtNavMesh *mesh, unsigned char *data, int dataSize,
@@ -26,6 +99,180 @@ extern "C"
     return mesh->addTile(data, dataSize, flags, lastRef, result);
   }
 
+  dtStatus dtNavMesh_addTileCopy(dtNavMesh *mesh, const unsigned char *data, int dataSize,
+                                 int flags, dtTileRef lastRef, dtTileRef *result)
+  {
+    unsigned char *owned = (unsigned char *)dtAlloc(dataSize, DT_ALLOC_PERM);
+    if (!owned)
+      return DT_FAILURE | DT_OUT_OF_MEMORY;
+    memcpy(owned, data, dataSize);
+
+    dtStatus status = mesh->addTile(owned, dataSize, flags | DT_TILE_FREE_DATA, lastRef, result);
+    if (dtStatusFailed(status))
+      dtFree(owned);
+    return status;
+  }
+
+  dtStatus dtNavMesh_removeTile(dtNavMesh *mesh, dtTileRef ref, unsigned char **data, int *dataSize)
+  {
+    return mesh->removeTile(ref, data, dataSize);
+  }
+
+  const dtNavMeshParams *dtNavMesh_getParams(const dtNavMesh *mesh)
+  {
+    return mesh->getParams();
+  }
+
+  int dtNavMesh_getMaxTiles(const dtNavMesh *mesh)
+  {
+    return mesh->getMaxTiles();
+  }
+
+  // The tile LUT size mirrors the private sizing in dtNavMesh::init (dtNextPow2(maxTiles / 4)),
+  // which isn't exposed, so it has to be kept in sync by hand when Detour is updated
+  size_t dtNavMesh_getBaseMemoryUsage(const dtNavMesh *mesh)
+  {
+    const int maxTiles = mesh->getMaxTiles();
+    unsigned int tileLutSize = dtNextPow2((unsigned int)(maxTiles / 4));
+    if (!tileLutSize)
+      tileLutSize = 1;
+    return sizeof(dtNavMesh) + sizeof(dtMeshTile) * maxTiles + sizeof(dtMeshTile *) * tileLutSize;
+  }
+
+  // The section sizes mirror the private layout in dtNavMesh::addTile (and dtCreateNavMeshData),
+  // which isn't exposed, so they have to be kept in sync by hand when Detour is updated
+  // The counts are expected to be non negative
+  size_t dtMeshHeader_getDataSize(const dtMeshHeader *header)
+  {
+    return dtAlign4Size(sizeof(dtMeshHeader)) +
+           dtAlign4Size(sizeof(double) * 3 * (size_t)header->vertCount) +
+           dtAlign4Size(sizeof(dtPoly) * (size_t)header->polyCount) +
+           dtAlign4Size(sizeof(dtLink) * (size_t)header->maxLinkCount) +
+           dtAlign4Size(sizeof(dtPolyDetail) * (size_t)header->detailMeshCount) +
+           dtAlign4Size(sizeof(double) * 3 * (size_t)header->detailVertCount) +
+           dtAlign4Size(sizeof(unsigned char) * 4 * (size_t)header->detailTriCount) +
+           dtAlign4Size(sizeof(dtBVNode) * (size_t)header->bvNodeCount) +
+           dtAlign4Size(sizeof(dtOffMeshConnection) * (size_t)header->offMeshConCount);
+  }
+
+  const dtMeshTile *dtNavMesh_getTile(const dtNavMesh *mesh, int i)
+  {
+    return mesh->getTile(i);
+  }
+
+  const dtMeshTile *dtNavMesh_getTileAt(const dtNavMesh *mesh, const int x, const int y, const int layer)
+  {
+    return mesh->getTileAt(x, y, layer);
+  }
+
+  void dtNavMesh_calcTileLoc(const dtNavMesh *mesh, const double *pos, int *tx, int *ty)
+  {
+    mesh->calcTileLoc(pos, tx, ty);
+  }
+
+  const dtMeshTile *dtNavMesh_getTileByRef(const dtNavMesh *mesh, dtTileRef ref)
+  {
+    return mesh->getTileByRef(ref);
+  }
+
+  dtTileRef dtNavMesh_getTileRef(const dtNavMesh *mesh, const dtMeshTile *tile)
+  {
+    return mesh->getTileRef(tile);
+  }
+
+  dtStatus dtNavMesh_getPolyArea(const dtNavMesh *mesh, dtPolyRef ref, unsigned char *resultArea)
+  {
+    return mesh->getPolyArea(ref, resultArea);
+  }
+
+  dtStatus dtNavMesh_getPolyFlags(const dtNavMesh *mesh, dtPolyRef ref, unsigned short *resultFlags)
+  {
+    return mesh->getPolyFlags(ref, resultFlags);
+  }
+
+  dtStatus dtNavMesh_setPolyFlags(dtNavMesh *mesh, dtPolyRef ref, unsigned short flags)
+  {
+    return mesh->setPolyFlags(ref, flags);
+  }
+
+  dtStatus dtNavMesh_setPolyArea(dtNavMesh *mesh, dtPolyRef ref, unsigned char area)
+  {
+    return mesh->setPolyArea(ref, area);
+  }
+
+  dtStatus dtNavMesh_getTileAndPolyByRef(const dtNavMesh *mesh, const dtPolyRef ref,
+                                         const dtMeshTile **tile, const dtPoly **poly)
+  {
+    return mesh->getTileAndPolyByRef(ref, tile, poly);
+  }
+
+  dtStatus dtNavMesh_getOffMeshConnectionPolyEndPoints(const dtNavMesh *mesh, dtPolyRef prevRef,
+                                                       dtPolyRef polyRef, double *startPos,
+                                                       double *endPos)
+  {
+    return mesh->getOffMeshConnectionPolyEndPoints(prevRef, polyRef, startPos, endPos);
+  }
+
+  const dtMeshHeader *dtMeshTile_getHeader(const dtMeshTile *tile)
+  {
+    return tile->header;
+  }
+
+  const unsigned char *dtMeshTile_getData(const dtMeshTile *tile)
+  {
+    return tile->data;
+  }
+
+  int dtMeshTile_getDataSize(const dtMeshTile *tile)
+  {
+    return tile->dataSize;
+  }
+
+  const double *dtMeshTile_getVerts(const dtMeshTile *tile)
+  {
+    return tile->verts;
+  }
+
+  unsigned char dtPoly_getVertCount(const dtPoly *poly)
+  {
+    return poly->vertCount;
+  }
+
+  const unsigned short *dtPoly_getVerts(const dtPoly *poly)
+  {
+    return poly->verts;
+  }
+
+  unsigned int dtPoly_getFirstLink(const dtPoly *poly)
+  {
+    return poly->firstLink;
+  }
+
+  unsigned char dtPoly_getType(const dtPoly *poly)
+  {
+    return poly->getType();
+  }
+
+  dtPolyRef dtMeshTile_getLinkRef(const dtMeshTile *tile, unsigned int link)
+  {
+    return tile->links[link].ref;
+  }
+
+  unsigned int dtMeshTile_getLinkNext(const dtMeshTile *tile, unsigned int link)
+  {
+    return tile->links[link].next;
+  }
+
+  bool dtNavMeshData_create(dtNavMeshCreateParams *params, unsigned char **outData, int *outDataSize)
+  {
+    return dtCreateNavMeshData(params, outData, outDataSize);
+  }
+
+  void dtNavMeshData_free(unsigned char *data)
+  {
+    dtFree(data);
+  }
+
   dtNavMeshQuery *dtNavMeshQuery_alloc()
   {
     return dtAllocNavMeshQuery();
@@ -66,6 +313,16 @@ extern "C"
     return filter->getExcludeFlags();
   }
 
+  void dtQueryFilter_setAreaCost(dtQueryFilter *filter, const int i, const double cost)
+  {
+    filter->setAreaCost(i, cost);
+  }
+
+  double dtQueryFilter_getAreaCost(const dtQueryFilter *filter, const int i)
+  {
+    return filter->getAreaCost(i);
+  }
+
   dtStatus dtNavMeshQuery_init(dtNavMeshQuery *query, dtNavMesh *mesh, int maxNodes)
   {
     return query->init(mesh, maxNodes);
@@ -76,11 +333,11 @@ extern "C"
     return query->getPolyHeight(polyRef, pos, height);
   }
 
-  dtStatus dtNavMeshQuery_findNearestPoly(dtNavMeshQuery *query, const double *center, const double *extents,
-                                          const dtQueryFilter *filter,
-                                          dtPolyRef *nearestRef, double *nearestPt)
+  dtStatus dtNavMeshQuery_findNearestPolyEx(dtNavMeshQuery *query, const double *center, const double *extents,
+                                            const dtQueryFilter *filter,
+                                            dtPolyRef *nearestRef, double *nearestPt, bool *isOverPoly)
   {
-    return query->findNearestPoly(center, extents, filter, nearestRef, nearestPt);
+    return query->findNearestPoly(center, extents, filter, nearestRef, nearestPt, isOverPoly);
   }
 
   dtStatus dtNavMeshQuery_closestPointOnPoly(dtNavMeshQuery *query, dtPolyRef ref, const double *pos, double *closest, bool *posOverPoly)
@@ -109,6 +366,14 @@ extern "C"
     return query->moveAlongSurface(startRef, startPos, endPos, filter, resultPos, visited, visitedCount, maxVisitedSize);
   }
 
+  dtStatus dtNavMeshQuery_raycast(dtNavMeshQuery *query, dtPolyRef startRef,
+                                  const double *startPos, const double *endPos,
+                                  const dtQueryFilter *filter,
+                                  double *t, double *hitNormal, dtPolyRef *path, int *pathCount, const int maxPath)
+  {
+    return query->raycast(startRef, startPos, endPos, filter, t, hitNormal, path, pathCount, maxPath);
+  }
+
   dtStatus dtNavMeshQuery_findStraightPath(dtNavMeshQuery *query, const double *startPos, const double *endPos,
                                            const dtPolyRef *path, const int pathSize,
                                            double *straightPath, unsigned char *straightPathFlags, dtPolyRef *straightPathRefs,
@@ -117,8 +382,386 @@ extern "C"
     return query->findStraightPath(startPos, endPos, path, pathSize, straightPath, straightPathFlags, straightPathRefs, straightPathCount, maxStraightPath, options);
   }
 
+  dtStatus dtNavMeshQuery_initSlicedFindPath(dtNavMeshQuery *query, dtPolyRef startRef, dtPolyRef endRef,
+                                             const double *startPos, const double *endPos,
+                                             const dtQueryFilter *filter, const unsigned int options)
+  {
+    return query->initSlicedFindPath(startRef, endRef, startPos, endPos, filter, options);
+  }
+
+  dtStatus dtNavMeshQuery_updateSlicedFindPath(dtNavMeshQuery *query, const int maxIter, int *doneIters)
+  {
+    return query->updateSlicedFindPath(maxIter, doneIters);
+  }
+
+  dtStatus dtNavMeshQuery_finalizeSlicedFindPath(dtNavMeshQuery *query, dtPolyRef *path, int *pathCount, const int maxPath)
+  {
+    return query->finalizeSlicedFindPath(path, pathCount, maxPath);
+  }
+
+  void dtNavMeshQuery_cancelSlicedFindPath(dtNavMeshQuery *query)
+  {
+    // The sliced query state is private, so it is reset through initSlicedFindPath: it clears
+    // m_query to a failed state first, then rejects the null refs through its
+    // m_nav->isValidPolyRef(startRef) guard before touching the positions or the filter.
+    // The dummy inputs are valid anyway, and static so the filter pointer kept in m_query
+    // never dangles.
+    static const double origin[3] = {0, 0, 0};
+    static const dtQueryFilter filter;
+    query->initSlicedFindPath(0, 0, origin, origin, &filter);
+  }
+
+  int dtNavMeshQuery_getNodePoolSize(const dtNavMeshQuery *query)
+  {
+    return query->getNodePool()->getMaxNodes();
+  }
+
+  int dtNavMeshQuery_getNodesUsed(const dtNavMeshQuery *query)
+  {
+    return query->getNodePool()->getNodeCount();
+  }
+
+  // dtNavMeshQuery::getPortalPoints is private, this mirrors it on top of the public dtNavMesh api
+  dtStatus dtNavMeshQuery_getPortalPoints(const dtNavMeshQuery *query, dtPolyRef from, dtPolyRef to,
+                                          double *left, double *right)
+  {
+    const dtNavMesh *mesh = query->getAttachedNavMesh();
+    const dtMeshTile *fromTile = 0;
+    const dtPoly *fromPoly = 0;
+    const dtMeshTile *toTile = 0;
+    const dtPoly *toPoly = 0;
+    if (dtStatusFailed(mesh->getTileAndPolyByRef(from, &fromTile, &fromPoly)) ||
+        dtStatusFailed(mesh->getTileAndPolyByRef(to, &toTile, &toPoly)))
+      return DT_FAILURE | DT_INVALID_PARAM;
+
+    // Find the link that points to the 'to' polygon.
+    const dtLink *link = 0;
+    for (unsigned int i = fromPoly->firstLink; i != DT_NULL_LINK; i = fromTile->links[i].next)
+    {
+      if (fromTile->links[i].ref == to)
+      {
+        link = &fromTile->links[i];
+        break;
+      }
+    }
+    if (!link)
+      return DT_FAILURE | DT_INVALID_PARAM;
+
+    // Off-mesh connections portal through the connection's endpoint vertex.
+    if (fromPoly->getType() == DT_POLYTYPE_OFFMESH_CONNECTION)
+    {
+      const int v = link->edge;
+      dtVcopy(left, &fromTile->verts[fromPoly->verts[v] * 3]);
+      dtVcopy(right, &fromTile->verts[fromPoly->verts[v] * 3]);
+      return DT_SUCCESS;
+    }
+
+    if (toPoly->getType() == DT_POLYTYPE_OFFMESH_CONNECTION)
+    {
+      for (unsigned int i = toPoly->firstLink; i != DT_NULL_LINK; i = toTile->links[i].next)
+      {
+        if (toTile->links[i].ref == from)
+        {
+          const int v = toTile->links[i].edge;
+          dtVcopy(left, &toTile->verts[toPoly->verts[v] * 3]);
+          dtVcopy(right, &toTile->verts[toPoly->verts[v] * 3]);
+          return DT_SUCCESS;
+        }
+      }
+      return DT_FAILURE | DT_INVALID_PARAM;
+    }
+
+    const int v0 = fromPoly->verts[link->edge];
+    const int v1 = fromPoly->verts[(link->edge + 1) % (int)fromPoly->vertCount];
+    dtVcopy(left, &fromTile->verts[v0 * 3]);
+    dtVcopy(right, &fromTile->verts[v1 * 3]);
+
+    // Tile boundary links only span part of the edge.
+    if (link->side != 0xff && (link->bmin != 0 || link->bmax != 255))
+    {
+      const double s = 1.0 / 255.0;
+      dtVlerp(left, &fromTile->verts[v0 * 3], &fromTile->verts[v1 * 3], link->bmin * s);
+      dtVlerp(right, &fromTile->verts[v0 * 3], &fromTile->verts[v1 * 3], link->bmax * s);
+    }
+
+    return DT_SUCCESS;
+  }
+
+  // closestPointOnDetailEdges is internal to DetourNavMesh.cpp, this mirrors it on top of the public dtNavMesh api
+  dtStatus dtNavMeshQuery_closestPointOnDetailEdges(const dtNavMeshQuery *query, dtPolyRef ref, const double *pos,
+                                                    bool onlyBoundary, double *distSqr, double *closest)
+  {
+    const dtNavMesh *mesh = query->getAttachedNavMesh();
+    const dtMeshTile *tile = 0;
+    const dtPoly *poly = 0;
+    if (dtStatusFailed(mesh->getTileAndPolyByRef(ref, &tile, &poly)))
+      return DT_FAILURE | DT_INVALID_PARAM;
+
+    // Off-mesh connections don't have detail polys.
+    if (poly->getType() == DT_POLYTYPE_OFFMESH_CONNECTION)
+      return DT_FAILURE | DT_INVALID_PARAM;
+
+    const unsigned int ip = (unsigned int)(poly - tile->polys);
+    const dtPolyDetail *pd = &tile->detailMeshes[ip];
+
+    double dmin = DBL_MAX;
+    double tmin = 0;
+    const double *pmin = 0;
+    const double *pmax = 0;
+
+    for (int i = 0; i < pd->triCount; i++)
+    {
+      const unsigned char *tris = &tile->detailTris[(pd->triBase + i) * 4];
+      const int ANY_BOUNDARY_EDGE =
+          (DT_DETAIL_EDGE_BOUNDARY << 0) |
+          (DT_DETAIL_EDGE_BOUNDARY << 2) |
+          (DT_DETAIL_EDGE_BOUNDARY << 4);
+      if (onlyBoundary && (tris[3] & ANY_BOUNDARY_EDGE) == 0)
+        continue;
+
+      const double *v[3];
+      for (int j = 0; j < 3; ++j)
+      {
+        if (tris[j] < poly->vertCount)
+          v[j] = &tile->verts[poly->verts[tris[j]] * 3];
+        else
+          v[j] = &tile->detailVerts[(pd->vertBase + (tris[j] - poly->vertCount)) * 3];
+      }
+
+      for (int k = 0, j = 2; k < 3; j = k++)
+      {
+        // Skip internal edges when only looking at the boundary, and visit shared inner edges once.
+        if ((dtGetDetailTriEdgeFlags(tris[3], j) & DT_DETAIL_EDGE_BOUNDARY) == 0 &&
+            (onlyBoundary || tris[j] < tris[k]))
+          continue;
+
+        double t;
+        double d = dtDistancePtSegSqr2D(pos, v[j], v[k], t);
+        if (d < dmin)
+        {
+          dmin = d;
+          tmin = t;
+          pmin = v[j];
+          pmax = v[k];
+        }
+      }
+    }
+
+    if (!pmin)
+      return DT_FAILURE | DT_INVALID_PARAM;
+
+    *distSqr = dmin;
+    dtVlerp(closest, pmin, pmax, tmin);
+    return DT_SUCCESS;
+  }
+
+  const dtNavMesh *dtNavMeshQuery_getAttachedNavMesh(const dtNavMeshQuery *query)
+  {
+    return query->getAttachedNavMesh();
+  }
+
   void dtNavMeshQuery_free(dtNavMeshQuery *query)
   {
     return dtFreeNavMeshQuery(query);
   }
-};
\ No newline at end of file
+
+  dtCrowd *dtCrowd_alloc()
+  {
+    return dtAllocCrowd();
+  }
+
+  bool dtCrowd_init(dtCrowd *crowd, const int maxAgents, const double maxAgentRadius, dtNavMesh *nav)
+  {
+    return crowd->init(maxAgents, maxAgentRadius, nav);
+  }
+
+  int dtCrowd_getAgentCount(const dtCrowd *crowd)
+  {
+    return crowd->getAgentCount();
+  }
+
+  const dtCrowdAgent *dtCrowd_getAgent(dtCrowd *crowd, const int idx)
+  {
+    return crowd->getAgent(idx);
+  }
+
+  int dtCrowd_addAgent(dtCrowd *crowd, const double *pos, const dtCrowdAgentParams *params)
+  {
+    return crowd->addAgent(pos, params);
+  }
+
+  void dtCrowd_updateAgentParameters(dtCrowd *crowd, const int idx, const dtCrowdAgentParams *params)
+  {
+    crowd->updateAgentParameters(idx, params);
+  }
+
+  void dtCrowd_removeAgent(dtCrowd *crowd, const int idx)
+  {
+    crowd->removeAgent(idx);
+  }
+
+  bool dtCrowd_requestMoveTarget(dtCrowd *crowd, const int idx, dtPolyRef ref, const double *pos)
+  {
+    return crowd->requestMoveTarget(idx, ref, pos);
+  }
+
+  void dtCrowd_update(dtCrowd *crowd, const double dt)
+  {
+    crowd->update(dt, 0);
+  }
+
+  void dtCrowd_free(dtCrowd *crowd)
+  {
+    dtFreeCrowd(crowd);
+  }
+
+  bool dtCrowdAgent_isActive(const dtCrowdAgent *agent)
+  {
+    return agent->active;
+  }
+
+  int dtCrowdAgent_getNeighbourCount(const dtCrowdAgent *agent)
+  {
+    return agent->nneis;
+  }
+
+  int dtCrowdAgent_getNeighbourIdx(const dtCrowdAgent *agent, const int i)
+  {
+    return agent->neis[i].idx;
+  }
+
+  unsigned char dtCrowdAgent_getTargetState(const dtCrowdAgent *agent)
+  {
+    return agent->targetState;
+  }
+
+  const double *dtCrowdAgent_getPos(const dtCrowdAgent *agent)
+  {
+    return agent->npos;
+  }
+
+  const double *dtCrowdAgent_getVel(const dtCrowdAgent *agent)
+  {
+    return agent->vel;
+  }
+
+  dtTileCacheAlloc *dtTileCacheAlloc_alloc()
+  {
+    void *mem = dtAlloc(sizeof(dtTileCacheAlloc), DT_ALLOC_PERM);
+    return mem ? new (mem) dtTileCacheAlloc : 0;
+  }
+
+  void dtTileCacheAlloc_free(dtTileCacheAlloc *alloc)
+  {
+    if (!alloc)
+      return;
+    alloc->~dtTileCacheAlloc();
+    dtFree(alloc);
+  }
+
+  dtTileCacheCompressor *dtTileCacheCompressor_alloc(void *user, dtMaxCompressedSizeFn maxCompressedSize,
+                                                     dtCompressFn compress, dtDecompressFn decompress)
+  {
+    void *mem = dtAlloc(sizeof(dtCallbackCompressor), DT_ALLOC_PERM);
+    if (!mem)
+      return 0;
+    dtCallbackCompressor *comp = new (mem) dtCallbackCompressor;
+    comp->user = user;
+    comp->maxCompressedSizeFn = maxCompressedSize;
+    comp->compressFn = compress;
+    comp->decompressFn = decompress;
+    return comp;
+  }
+
+  void dtTileCacheCompressor_free(dtTileCacheCompressor *comp)
+  {
+    if (!comp)
+      return;
+    comp->~dtTileCacheCompressor();
+    dtFree(comp);
+  }
+
+  dtTileCacheMeshProcess *dtTileCacheMeshProcess_alloc(void *user, dtMeshProcessFn process)
+  {
+    void *mem = dtAlloc(sizeof(dtCallbackMeshProcess), DT_ALLOC_PERM);
+    if (!mem)
+      return 0;
+    dtCallbackMeshProcess *proc = new (mem) dtCallbackMeshProcess;
+    proc->user = user;
+    proc->processFn = process;
+    return proc;
+  }
+
+  void dtTileCacheMeshProcess_free(dtTileCacheMeshProcess *proc)
+  {
+    if (!proc)
+      return;
+    proc->~dtTileCacheMeshProcess();
+    dtFree(proc);
+  }
+
+  dtTileCache *dtTileCache_alloc()
+  {
+    return dtAllocTileCache();
+  }
+
+  dtStatus dtTileCache_init(dtTileCache *cache, const dtTileCacheParams *params, dtTileCacheAlloc *alloc,
+                            dtTileCacheCompressor *comp, dtTileCacheMeshProcess *proc)
+  {
+    return cache->init(params, alloc, comp, proc);
+  }
+
+  void dtTileCache_free(dtTileCache *cache)
+  {
+    dtFreeTileCache(cache);
+  }
+
+  dtStatus dtTileCache_buildTileCacheLayer(dtTileCache *cache, dtTileCacheLayerHeader *header,
+                                           const unsigned char *heights, const unsigned char *areas,
+                                           const unsigned char *cons, unsigned char **outData, int *outDataSize)
+  {
+    return dtBuildTileCacheLayer(cache->getCompressor(), header, heights, areas, cons, outData, outDataSize);
+  }
+
+  // Copies the data into a dtAlloc allocation owned and freed by the tile cache
+  dtStatus dtTileCache_addTile(dtTileCache *cache, const unsigned char *data, const int dataSize,
+                               dtCompressedTileRef *result)
+  {
+    unsigned char *owned = (unsigned char *)dtAlloc(dataSize, DT_ALLOC_PERM);
+    if (!owned)
+      return DT_FAILURE | DT_OUT_OF_MEMORY;
+    memcpy(owned, data, dataSize);
+
+    dtStatus status = cache->addTile(owned, dataSize, DT_COMPRESSEDTILE_FREE_DATA, result);
+    if (dtStatusFailed(status))
+      dtFree(owned);
+    return status;
+  }
+
+  dtStatus dtTileCache_addObstacle(dtTileCache *cache, const double *pos, const double radius,
+                                   const double height, dtObstacleRef *result)
+  {
+    return cache->addObstacle(pos, radius, height, result);
+  }
+
+  dtStatus dtTileCache_addBoxObstacle(dtTileCache *cache, const double *bmin, const double *bmax,
+                                      dtObstacleRef *result)
+  {
+    return cache->addBoxObstacle(bmin, bmax, result);
+  }
+
+  dtStatus dtTileCache_removeObstacle(dtTileCache *cache, const dtObstacleRef ref)
+  {
+    return cache->removeObstacle(ref);
+  }
+
+  dtStatus dtTileCache_update(dtTileCache *cache, const double dt, dtNavMesh *navmesh, bool *upToDate)
+  {
+    return cache->update(dt, navmesh, upToDate);
+  }
+
+  dtStatus dtTileCache_buildNavMeshTilesAt(dtTileCache *cache, const int tx, const int ty, dtNavMesh *navmesh)
+  {
+    return cache->buildNavMeshTilesAt(tx, ty, navmesh);
+  }
+};
@@ -0,0 +1,206 @@
+use std::ops::Index;
+
+use crate::{DivertError, DivertResult, NavMeshQuery, PolyRef, QueryFilter, Vector};
+
+/// Index of a QueryFilter within a FilterSet
+pub type FilterIndex = usize;
+
+/// Named collection of QueryFilters, one per movement class (ground, flying, amphibious, ...)
+/// Filters are registered once and referred to by their FilterIndex afterwards,
+/// NavMeshQuery's _by_index methods take the FilterSet and a FilterIndex in place of a filter,
+/// and `&filter_set[index]` can be passed to any other NavMeshQuery method taking a QueryFilter
+#[derive(Default)]
+pub struct FilterSet<'a> {
+    names: Vec<String>,
+    filters: Vec<QueryFilter<'a>>,
+}
+
+impl<'a> FilterSet<'a> {
+    /// Creates an empty FilterSet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a filter under the given name, returning its index
+    /// Registering an existing name replaces its filter and keeps the index
+    pub fn insert(&mut self, name: &str, filter: QueryFilter<'a>) -> FilterIndex {
+        if let Some(index) = self.index_of(name) {
+            self.filters[index] = filter;
+            return index;
+        }
+
+        self.names.push(name.to_owned());
+        self.filters.push(filter);
+        self.filters.len() - 1
+    }
+
+    /// Looks up the index of the filter registered under name
+    pub fn index_of(&self, name: &str) -> Option<FilterIndex> {
+        self.names.iter().position(|registered| registered == name)
+    }
+
+    /// Retrieves the filter at index
+    pub fn get(&self, index: FilterIndex) -> Option<&QueryFilter<'a>> {
+        self.filters.get(index)
+    }
+
+    /// Retrieves the filter at index for reconfiguration
+    pub fn get_mut(&mut self, index: FilterIndex) -> Option<&mut QueryFilter<'a>> {
+        self.filters.get_mut(index)
+    }
+
+    /// Retrieves the filter registered under name
+    pub fn get_by_name(&self, name: &str) -> Option<&QueryFilter<'a>> {
+        self.get(self.index_of(name)?)
+    }
+
+    /// Number of registered filters
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Whether no filters are registered
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Retrieves the filter at index, erroring with InvalidParam if none is registered there
+    fn filter(&self, index: FilterIndex) -> DivertResult<&QueryFilter<'a>> {
+        self.get(index).ok_or(DivertError::InvalidParam(
+            "no filter is registered at the filter index",
+        ))
+    }
+}
+
+/// Query methods using the filter registered at a FilterIndex of a FilterSet
+/// Each errors with InvalidParam if no filter is registered at index, or like the method it
+/// forwards to
+impl<'a> NavMeshQuery<'a> {
+    /// Queries for the nearest polygon like find_nearest_poly, with the filter at index
+    pub fn find_nearest_poly_by_index(
+        &self,
+        center: &Vector,
+        extents: &Vector,
+        filters: &FilterSet,
+        index: FilterIndex,
+    ) -> DivertResult<(PolyRef, Vector)> {
+        self.find_nearest_poly(center, extents, filters.filter(index)?)
+    }
+
+    /// Generates a polygon path like find_path, with the filter at index
+    #[allow(clippy::too_many_arguments)]
+    pub fn find_path_by_index(
+        &self,
+        start_ref: PolyRef,
+        end_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filters: &FilterSet,
+        index: FilterIndex,
+        max_path: i32,
+    ) -> DivertResult<Vec<PolyRef>> {
+        let filter = filters.filter(index)?;
+        self.find_path(start_ref, end_ref, start_pos, end_pos, filter, max_path)
+    }
+
+    /// Moves along the surface like move_along_surface, with the filter at index
+    pub fn move_along_surface_by_index(
+        &self,
+        start_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filters: &FilterSet,
+        index: FilterIndex,
+        max_visit: i32,
+    ) -> DivertResult<(Vector, Vec<PolyRef>)> {
+        let filter = filters.filter(index)?;
+        self.move_along_surface(start_ref, start_pos, end_pos, filter, max_visit)
+    }
+}
+
+/// Panics if no filter is registered at index
+impl<'a> Index<FilterIndex> for FilterSet<'a> {
+    type Output = QueryFilter<'a>;
+
+    fn index(&self, index: FilterIndex) -> &Self::Output {
+        &self.filters[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FilterSet;
+    use crate::{fixtures, DivertError, NavMeshQuery, QueryFilter, Vector};
+
+    #[test]
+    fn test_filter_set_routes() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["..w..", "....."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let mut walker = QueryFilter::new().unwrap();
+        walker.set_include_flags(fixtures::GROUND_FLAG);
+        let mut swimmer = QueryFilter::new().unwrap();
+        swimmer.set_include_flags(fixtures::GROUND_FLAG | fixtures::WATER_FLAG);
+
+        let mut filters = FilterSet::new();
+        let walker_index = filters.insert("walker", walker);
+        let swimmer_index = filters.insert("swimmer", swimmer);
+        assert_eq!(filters.len(), 2);
+        assert_eq!(filters.index_of("swimmer"), Some(swimmer_index));
+        assert!(filters.get_by_name("flyer").is_none());
+
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(4, 0);
+        let find_path = |index| {
+            let (start_ref, _) = nav_mesh_query
+                .find_nearest_poly_by_index(&start_pos, &extents, &filters, index)
+                .unwrap();
+            let (end_ref, _) = nav_mesh_query
+                .find_nearest_poly_by_index(&end_pos, &extents, &filters, index)
+                .unwrap();
+            nav_mesh_query
+                .find_path_by_index(
+                    start_ref, end_ref, &start_pos, &end_pos, &filters, index, 16,
+                )
+                .unwrap()
+        };
+
+        let (water_ref, _) = nav_mesh_query
+            .find_nearest_poly(
+                &fixtures::cell_center(2, 0),
+                &extents,
+                &filters[swimmer_index],
+            )
+            .unwrap();
+
+        let swimmer_path = find_path(swimmer_index);
+        assert_eq!(swimmer_path.len(), 5);
+        assert!(swimmer_path.contains(&water_ref));
+
+        let walker_path = find_path(walker_index);
+        assert!(walker_path.len() > swimmer_path.len());
+        assert!(!walker_path.contains(&water_ref));
+
+        let (start_ref, _) = nav_mesh_query
+            .find_nearest_poly_by_index(&start_pos, &extents, &filters, walker_index)
+            .unwrap();
+        let (end_pos, visited) = nav_mesh_query
+            .move_along_surface_by_index(
+                start_ref,
+                &start_pos,
+                &end_pos,
+                &filters,
+                walker_index,
+                16,
+            )
+            .unwrap();
+        assert!(!visited.contains(&water_ref));
+        assert!(end_pos.x < fixtures::cell_center(2, 0).x);
+
+        assert!(matches!(
+            nav_mesh_query.find_nearest_poly_by_index(&start_pos, &extents, &filters, 2),
+            Err(DivertError::InvalidParam(_))
+        ));
+    }
+}
@@ -0,0 +1,183 @@
+//! Handcrafted tiles shared by the unit tests
+//! Tiles are described by rows of cells along z, each character is a cell along x
+//! '.' is a walkable ground cell, 'w' is a water cell, any other character is blocked
+
+use crate::{
+    create_nav_mesh_data, NavMesh, NavMeshCreateParams, NavMeshParams, NavMeshQuery, PolyRef,
+    QueryFilter, Vector,
+};
+
+/// Size of a single cell in voxels, and in world units as cs and ch are 1.0
+pub const CELL_SIZE: u16 = 4;
+
+pub const GROUND_AREA: u8 = 0;
+pub const WATER_AREA: u8 = 1;
+
+pub const GROUND_FLAG: u16 = 0x01;
+pub const WATER_FLAG: u16 = 0x02;
+
+const NVP: usize = 6;
+const NULL_IDX: u16 = 0xffff;
+const PORTAL_FLAG: u16 = 0x8000;
+
+/// NavMeshParams for tiles built from a layout of the given cell dimensions
+pub fn nav_mesh_params(columns: usize, rows: usize, max_tiles: i32) -> NavMeshParams {
+    NavMeshParams {
+        origin: [0.0, 0.0, 0.0],
+        tile_width: (columns * CELL_SIZE as usize) as f64,
+        tile_height: (rows * CELL_SIZE as usize) as f64,
+        max_tiles,
+        max_polys: 256,
+    }
+}
+
+/// World position at the center of a cell, cells are counted from the world origin across tiles
+pub fn cell_center(x: i32, z: i32) -> Vector {
+    let half_cell = CELL_SIZE as f64 / 2.0;
+    Vector::new(
+        x as f64 * CELL_SIZE as f64 + half_cell,
+        0.0,
+        z as f64 * CELL_SIZE as f64 + half_cell,
+    )
+}
+
+fn cell_area(layout: &[&str], x: isize, z: isize) -> Option<(u8, u16)> {
+    if x < 0 || z < 0 {
+        return None;
+    }
+
+    match layout.get(z as usize)?.as_bytes().get(x as usize)? {
+        b'.' => Some((GROUND_AREA, GROUND_FLAG)),
+        b'w' => Some((WATER_AREA, WATER_FLAG)),
+        _ => None,
+    }
+}
+
+/// Builds tile data for a layout, placing it at (tile_x, tile_y) within the nav mesh grid
+pub fn create_tile_data(tile_x: i32, tile_y: i32, layout: &[&str]) -> Vec<u8> {
+    create_tile_data_with(tile_x, tile_y, layout, &NavMeshCreateParams::default())
+}
+
+/// Builds tile data for a layout, taking off-mesh connections from extra
+pub fn create_tile_data_with(
+    tile_x: i32,
+    tile_y: i32,
+    layout: &[&str],
+    extra: &NavMeshCreateParams,
+) -> Vec<u8> {
+    let rows = layout.len();
+    let columns = layout[0].len();
+
+    let verts: Vec<[u16; 3]> = (0..=rows)
+        .flat_map(|z| (0..=columns).map(move |x| (x, z)))
+        .map(|(x, z)| [x as u16 * CELL_SIZE, 1, z as u16 * CELL_SIZE])
+        .collect();
+    let vert_index = |x: usize, z: usize| (z * (columns + 1) + x) as u16;
+
+    let cells: Vec<(usize, usize)> = (0..rows)
+        .flat_map(|z| (0..columns).map(move |x| (x, z)))
+        .filter(|&(x, z)| cell_area(layout, x as isize, z as isize).is_some())
+        .collect();
+    let poly_index = |x: isize, z: isize| {
+        cells
+            .iter()
+            .position(|&cell| cell == (x as usize, z as usize))
+    };
+
+    let mut polys = Vec::with_capacity(cells.len() * NVP * 2);
+    let mut poly_flags = Vec::with_capacity(cells.len());
+    let mut poly_areas = Vec::with_capacity(cells.len());
+
+    for &(x, z) in &cells {
+        let (area, flags) = cell_area(layout, x as isize, z as isize).unwrap();
+        poly_areas.push(area);
+        poly_flags.push(flags);
+
+        polys.extend_from_slice(&[
+            vert_index(x, z),
+            vert_index(x, z + 1),
+            vert_index(x + 1, z + 1),
+            vert_index(x + 1, z),
+        ]);
+        polys.extend_from_slice(&[NULL_IDX; NVP - 4]);
+
+        // Edges in vertex order face -x, +z, +x, -z, border portals use Recast's direction ids
+        let (x, z) = (x as isize, z as isize);
+        let edges = [
+            (x - 1, z, x == 0, 0),
+            (x, z + 1, z as usize == rows - 1, 1),
+            (x + 1, z, x as usize == columns - 1, 2),
+            (x, z - 1, z == 0, 3),
+        ];
+        for (neighbour_x, neighbour_z, on_border, direction) in edges {
+            let neighbour = if on_border {
+                PORTAL_FLAG | direction
+            } else {
+                poly_index(neighbour_x, neighbour_z).map_or(NULL_IDX, |index| index as u16)
+            };
+            polys.push(neighbour);
+        }
+        polys.extend_from_slice(&[NULL_IDX; NVP - 4]);
+    }
+
+    let tile_width = (columns * CELL_SIZE as usize) as f64;
+    let tile_height = (rows * CELL_SIZE as usize) as f64;
+    let params = NavMeshCreateParams {
+        verts: &verts,
+        polys: &polys,
+        poly_flags: &poly_flags,
+        poly_areas: &poly_areas,
+        nvp: NVP as i32,
+        tile_x,
+        tile_y,
+        b_min: [
+            tile_x as f64 * tile_width,
+            -1.0,
+            tile_y as f64 * tile_height,
+        ],
+        b_max: [
+            (tile_x + 1) as f64 * tile_width,
+            1.0,
+            (tile_y + 1) as f64 * tile_height,
+        ],
+        walkable_height: 2.0,
+        walkable_radius: 0.5,
+        walkable_climb: 1.0,
+        cs: 1.0,
+        ch: 1.0,
+        build_bv_tree: true,
+        off_mesh_con_verts: extra.off_mesh_con_verts,
+        off_mesh_con_rad: extra.off_mesh_con_rad,
+        off_mesh_con_flags: extra.off_mesh_con_flags,
+        off_mesh_con_areas: extra.off_mesh_con_areas,
+        off_mesh_con_dir: extra.off_mesh_con_dir,
+        off_mesh_con_user_id: extra.off_mesh_con_user_id,
+        ..Default::default()
+    };
+
+    create_nav_mesh_data(&params).unwrap()
+}
+
+/// Creates a NavMesh holding a single tile built from the layout
+pub fn nav_mesh_with_tile(layout: &[&str]) -> NavMesh<'static> {
+    let params = nav_mesh_params(layout[0].len(), layout.len(), 1);
+    let mut nav_mesh = NavMesh::new(&params).unwrap();
+    nav_mesh.add_tile(create_tile_data(0, 0, layout)).unwrap();
+    nav_mesh
+}
+
+/// Nearest polys to start_pos and end_pos, searched within one unit across and two units of height
+/// Panics if either query fails
+pub fn endpoints(
+    query: &NavMeshQuery,
+    start_pos: &Vector,
+    end_pos: &Vector,
+    filter: &QueryFilter,
+) -> (PolyRef, PolyRef) {
+    let extents = Vector::new(1.0, 2.0, 1.0);
+    let (start_ref, _) = query
+        .find_nearest_poly(start_pos, &extents, filter)
+        .unwrap();
+    let (end_ref, _) = query.find_nearest_poly(end_pos, &extents, filter).unwrap();
+    (start_ref, end_ref)
+}
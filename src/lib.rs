@@ -1,86 +1,190 @@
 mod binding;
+mod builder;
+mod crowd;
+mod filter_set;
+#[cfg(test)]
+mod fixtures;
+mod path;
+mod query_pool;
+mod sliced;
+mod snapshot;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+mod tile_cache;
+mod vector;
 
 use std::{
-    marker,
+    cell::{RefCell, RefMut},
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    marker, ops,
+    ptr::{self, NonNull},
+    sync::Arc,
 };
 
-pub use binding::DtStatus;
-pub use binding::DtStraightPathFlags;
+pub use binding::{DtMeshHeader, DtStatus};
+pub use binding::{
+    DtStraightPathFlags, StraightPathOptions, TileFlags, DT_MAX_AREAS,
+    DT_STRAIGHTPATH_ALL_CROSSINGS, DT_STRAIGHTPATH_AREA_CROSSINGS,
+};
+pub use builder::{
+    create_nav_mesh_data, NavMeshCreateParams, OffMeshConnectionDirection, DT_OFFMESH_CON_BIDIR,
+    DT_VERTS_PER_POLYGON,
+};
+pub use crowd::{
+    Crowd, CrowdAgentParams, MoveRequestState, DT_CROWD_ANTICIPATE_TURNS,
+    DT_CROWD_MAX_OBSTAVOIDANCE_PARAMS, DT_CROWD_MAX_QUERY_FILTER_TYPE, DT_CROWD_OBSTACLE_AVOIDANCE,
+    DT_CROWD_OPTIMIZE_TOPO, DT_CROWD_OPTIMIZE_VIS, DT_CROWD_SEPARATION,
+};
+pub use filter_set::{FilterIndex, FilterSet};
+pub use path::{
+    dedup_corridor, straight_path_length, straight_path_to_vertices, PathResult,
+    StraightPathBuffers,
+};
+pub use query_pool::{NavMeshQueryPool, QueryGuard};
+pub use sliced::{SlicedFindPath, SlicedPathState};
+pub use tile_cache::{
+    CompressedTileRef, NoCompression, ObstacleRef, TileCache, TileCacheCompressor,
+    TileCacheLayerHeader, TileCacheMeshProcess, TileCacheParams,
+};
+pub use vector::{centroid, extents_bounds, UpAxis, VectorExt};
 
 use binding::*;
 use thiserror::Error;
 
-/// 3D Vector used in Recast Navigation, correspond to a [f32; 3]
-/// This abstraction is provided to combat misunderstanding of point ordering
-/// Recast expects y, z, x ordering while many applications use x, y, z ordering
+/// 3D Vector used in Recast Navigation, glam's `DVec3` holding Detour's f64 x, y, z as is
+/// Detour positions are y-up, while many applications use z-up positions, so
+/// `VectorExt::from_z_up` and `VectorExt::to_z_up` convert between the two frames and `UpAxis`
+/// picks the conversion, height and horizontal distance at runtime
+/// `glam::Vec3` positions convert with `Vec3::as_dvec3` and `Vector::as_vec3` without any feature,
+/// and with the `mint` feature enabled, `From` converts to and from `mint::Vector3<f64>` and
+/// `mint::Point3<f64>`
+/// nalgebra's `Vector3<f64>` and `Point3<f64>` convert through mint, with the `mint` feature
+/// enabled here and nalgebra's `convert-mint` feature, f32 values are cast to f64 first
 pub type Vector = DtVector;
 
-/// Provides functionality to initialize Vectors compatible with Recast
-/// Additionally provides basic math functions used with 3D Vectors
-// impl Vector {
-//     /// Simple function to help with initializing maintaining expected ordering
-//     pub fn from_xyz(x: f64, y: f64, z: f64) -> Self {
-//         Self { x, y, z }
-//     }
-//
-//     /// Simple function to help with initializing maintaining expected ordering
-//     pub fn from_yzx(y: f32, z: f32, x: f32) -> Self {
-//         Self { x, y, z }
-//     }
-//
-//     /// Dot product of the vector and other
-//     pub fn dot(&self, other: &Self) -> f32 {
-//         self.x * other.x + self.y * other.y + self.z * other.z
-//     }
-// }
-//
-// impl Add for Vector {
-//     type Output = Self;
-//
-//     fn add(self, other: Self) -> Self {
-//         Self {
-//             x: self.x + other.x,
-//             y: self.y + other.y,
-//             z: self.z + other.z,
-//         }
-//     }
-// }
-//
-// impl Sub for Vector {
-//     type Output = Self;
-//
-//     fn sub(self, other: Self) -> Self {
-//         Self {
-//             x: self.x - other.x,
-//             y: self.y - other.y,
-//             z: self.z - other.z,
-//         }
-//     }
-// }
-//
-// impl Mul<f32> for Vector {
-//     type Output = Self;
-//
-//     fn mul(self, scalar: f32) -> Self {
-//         Self {
-//             x: self.x * scalar,
-//             y: self.y * scalar,
-//             z: self.z * scalar,
-//         }
-//     }
-// }
-
 /// Typedef to DtNavMeshParams
 /// Affords the ability in future to add custom functionality
 pub type NavMeshParams = DtNavMeshParams;
 
-/// Typedef to DtPolyRef
-/// Affords the ability in future to add custom functionality
-pub type PolyRef = DtPolyRef;
+/// Reference to a polygon, wrapping Detour's 64 bit dtPolyRef
+/// Distinct from TileRef, so the compiler rejects passing a tile ref where a poly ref is expected
+/// The null reference (0) is PolyRef::default()
+///
+/// ```compile_fail
+/// use divert_f64::{NavMesh, TileRef};
+///
+/// fn poly_area(nav_mesh: &NavMesh, tile_ref: TileRef) {
+///     let _ = nav_mesh.get_poly_area(tile_ref);
+/// }
+/// ```
+#[repr(transparent)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PolyRef(u64);
 
-/// Typedef to DtTileRef
-/// Affords the ability in future to add custom functionality
-pub type TileRef = DtTileRef;
+impl PolyRef {
+    /// Wraps a raw dtPolyRef value
+    pub const fn from_raw(raw: u64) -> Self {
+        Self(raw)
+    }
+
+    /// Retrieves the raw dtPolyRef value
+    pub const fn as_raw(self) -> u64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for PolyRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Reference to a tile, wrapping Detour's 64 bit dtTileRef
+/// Distinct from PolyRef, so the compiler rejects passing a poly ref where a tile ref is expected
+/// The null reference (0) is TileRef::default()
+///
+/// ```compile_fail
+/// use divert_f64::{NavMesh, PolyRef};
+///
+/// fn remove(nav_mesh: &mut NavMesh, poly_ref: PolyRef) {
+///     let _ = nav_mesh.remove_tile(poly_ref);
+/// }
+/// ```
+#[repr(transparent)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TileRef(u64);
+
+impl TileRef {
+    /// Wraps a raw dtTileRef value
+    pub const fn from_raw(raw: u64) -> Self {
+        Self(raw)
+    }
+
+    /// Retrieves the raw dtTileRef value
+    pub const fn as_raw(self) -> u64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for TileRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Location of a tile within the nav mesh tile grid, x and y being the tile column and row
+/// Multiple tiles can occupy a location in different layers
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TileCoord {
+    /// Tile column in the nav mesh tile grid
+    pub x: i32,
+    /// Tile row in the nav mesh tile grid
+    pub y: i32,
+    /// Layer of the tile at its grid location
+    pub layer: i32,
+}
+
+impl TileCoord {
+    const AXIS_BITS: u32 = 24;
+    const LAYER_BITS: u32 = 16;
+
+    /// Creates a tile coordinate from its column, row and layer
+    pub const fn new(x: i32, y: i32, layer: i32) -> Self {
+        Self { x, y, layer }
+    }
+
+    /// Packs the coordinate into a single u64 key, for instance for hash maps of loaded tiles
+    /// x and y take 24 bits each and layer the remaining 16 bits
+    /// Errors if x or y lies outside -2^23..2^23, or layer outside 0..2^16
+    pub fn pack(&self) -> DivertResult<u64> {
+        let axis_range = -(1 << (Self::AXIS_BITS - 1))..(1 << (Self::AXIS_BITS - 1));
+        if !axis_range.contains(&self.x) || !axis_range.contains(&self.y) {
+            return Err(DivertError::InvalidParam(
+                "tile x and y must be within -8388608..8388608",
+            ));
+        }
+        if !(0..(1 << Self::LAYER_BITS)).contains(&self.layer) {
+            return Err(DivertError::InvalidParam(
+                "tile layer must be within 0..65536",
+            ));
+        }
+
+        let axis_mask = (1 << Self::AXIS_BITS) - 1;
+        Ok((self.x as u64 & axis_mask)
+            | (self.y as u64 & axis_mask) << Self::AXIS_BITS
+            | (self.layer as u64) << (2 * Self::AXIS_BITS))
+    }
+
+    /// Unpacks a coordinate packed by pack
+    pub fn unpack(packed: u64) -> Self {
+        let axis_shift = 64 - Self::AXIS_BITS;
+        Self {
+            x: ((packed << axis_shift) as i64 >> axis_shift) as i32,
+            y: ((packed << (axis_shift - Self::AXIS_BITS)) as i64 >> axis_shift) as i32,
+            layer: (packed >> (2 * Self::AXIS_BITS)) as i32,
+        }
+    }
+}
 
 #[derive(Error, Debug)]
 pub enum DivertError {
@@ -88,6 +192,38 @@ pub enum DivertError {
     Failure(DtStatus),
     #[error("detour unexpected null ptr failure")]
     NullPtr(),
+    #[error("divert query on a nav mesh without loaded tiles")]
+    EmptyNavMesh(),
+    #[error("divert invalid parameter `{0}`")]
+    InvalidParam(&'static str),
+    #[error("detour nav mesh has no free tile slot left")]
+    NavMeshFull(),
+    #[error("detour nav mesh already has a tile at the given location")]
+    AlreadyOccupied(),
+    #[error("divert nav mesh snapshot is invalid `{0}`")]
+    InvalidSnapshot(&'static str),
+    #[error("divert io failure `{0}`")]
+    Io(#[from] std::io::Error),
+    #[error("detour unexpected dtCreateNavMeshData failure")]
+    CreateNavMeshDataFailure(),
+    #[error("detour crowd unexpected dtCrowd::init failure")]
+    CrowdInitFailure(),
+    #[error("detour crowd unexpected dtCrowd::addAgent failure")]
+    AddAgentFailure(),
+    #[error("detour crowd unexpected dtCrowd::requestMoveTarget failure")]
+    RequestMoveTargetFailure(),
+    #[error("detour nav mesh unexpected dtNavMesh::removeTile failure `{0:?}`")]
+    RemoveTileFailure(DtStatus),
+    #[error("detour nav mesh unexpected dtNavMesh::getTileAndPolyByRef failure `{0:?}`")]
+    GetTileAndPolyByRefFailure(DtStatus),
+    #[error("detour nav mesh unexpected dtNavMesh::getPolyArea failure `{0:?}`")]
+    GetPolyAreaFailure(DtStatus),
+    #[error("detour nav mesh unexpected dtNavMesh::getPolyFlags failure `{0:?}`")]
+    GetPolyFlagsFailure(DtStatus),
+    #[error("detour nav mesh unexpected dtNavMesh::setPolyArea failure `{0:?}`")]
+    SetPolyAreaFailure(DtStatus),
+    #[error("detour nav mesh unexpected dtNavMesh::setPolyFlags failure `{0:?}`")]
+    SetPolyFlagsFailure(DtStatus),
     #[error("detour nav mesh unexpected dtNavMeshQuery::getPolyHeight failure `{0:?}`")]
     GetPolyHeightFailure(DtStatus),
     #[error("detour nav mesh unexpected dtNavMeshQuery::findNearestPoly failure `{0:?}`")]
@@ -98,20 +234,187 @@ pub enum DivertError {
         "detour nav mesh unexpected dtNavMeshQuery::closestPointOnPolyBoundary failure `{0:?}`"
     )]
     ClosestPointOnPolyBoundaryFailure(DtStatus),
+    #[error(
+        "detour nav mesh unexpected dtNavMeshQuery::closestPointOnDetailEdges failure `{0:?}`"
+    )]
+    ClosestPointOnDetailEdgesFailure(DtStatus),
+    #[error("detour nav mesh unexpected dtNavMeshQuery::getPortalPoints failure `{0:?}`")]
+    GetPortalPointsFailure(DtStatus),
     #[error("detour nav mesh unexpected dtNavMeshQuery::findPath failure `{0:?}`")]
     FindPathFailure(DtStatus),
     #[error("detour nav mesh unexpected dtNavMeshQuery::findStraightPath failure `{0:?}`")]
     FindStraightPathFailure(DtStatus),
     #[error("detour nav mesh unexpected dtNavMeshQuery::moveAlongSurface failure `{0:?}`")]
     MoveAlongSurfaceFailure(DtStatus),
+    #[error(
+        "detour nav mesh unexpected dtNavMesh::getOffMeshConnectionPolyEndPoints failure `{0:?}`"
+    )]
+    GetOffMeshConnectionPolyEndPointsFailure(DtStatus),
+    #[error("detour nav mesh unexpected dtNavMeshQuery::raycast failure `{0:?}`")]
+    RaycastFailure(DtStatus),
+    #[error("detour nav mesh unexpected dtNavMeshQuery::initSlicedFindPath failure `{0:?}`")]
+    InitSlicedFindPathFailure(DtStatus),
+    #[error("detour nav mesh unexpected dtNavMeshQuery::updateSlicedFindPath failure `{0:?}`")]
+    UpdateSlicedFindPathFailure(DtStatus),
+    #[error("detour nav mesh unexpected dtNavMeshQuery::finalizeSlicedFindPath failure `{0:?}`")]
+    FinalizeSlicedFindPathFailure(DtStatus),
+    #[error("detour tile cache unexpected dtTileCache::init failure `{0:?}`")]
+    TileCacheInitFailure(DtStatus),
+    #[error("detour tile cache unexpected dtBuildTileCacheLayer failure `{0:?}`")]
+    BuildTileCacheLayerFailure(DtStatus),
+    #[error("detour tile cache unexpected dtTileCache::addTile failure `{0:?}`")]
+    TileCacheAddTileFailure(DtStatus),
+    #[error("detour tile cache unexpected dtTileCache::buildNavMeshTilesAt failure `{0:?}`")]
+    BuildNavMeshTilesAtFailure(DtStatus),
+    #[error("detour tile cache unexpected dtTileCache::addObstacle failure `{0:?}`")]
+    AddObstacleFailure(DtStatus),
+    #[error("detour tile cache unexpected dtTileCache::addBoxObstacle failure `{0:?}`")]
+    AddBoxObstacleFailure(DtStatus),
+    #[error("detour tile cache unexpected dtTileCache::removeObstacle failure `{0:?}`")]
+    RemoveObstacleFailure(DtStatus),
+    #[error("detour tile cache unexpected dtTileCache::update failure `{0:?}`")]
+    TileCacheUpdateFailure(DtStatus),
 }
 
 pub type DivertResult<T> = std::result::Result<T, DivertError>;
 
+/// Upper bound on caller requested buffer sizes such as max_path
+const MAX_BUFFER_SIZE: i32 = 1 << 20;
+
+/// Validates a caller requested buffer size, returning it as a Vec capacity
+/// Errors with InvalidParam for negative or absurdly large sizes instead of panicking
+fn buffer_capacity(size: i32) -> DivertResult<usize> {
+    if !(0..=MAX_BUFFER_SIZE).contains(&size) {
+        return Err(DivertError::InvalidParam(
+            "buffer size must be within 0..=1048576",
+        ));
+    }
+
+    Ok(size as usize)
+}
+
+/// Runs a query with a buffer of initial_max_path results, doubling it up to MAX_BUFFER_SIZE
+/// while the query reports BUFFER_TOO_SMALL, returning the results of the last run
+fn grow_while_too_small<T>(
+    initial_max_path: i32,
+    mut query: impl FnMut(i32) -> DivertResult<(Vec<T>, DtStatus)>,
+) -> DivertResult<Vec<T>> {
+    if !(1..=MAX_BUFFER_SIZE).contains(&initial_max_path) {
+        return Err(DivertError::InvalidParam(
+            "initial_max_path must be within 1..=1048576",
+        ));
+    }
+
+    let mut max_path = initial_max_path;
+    loop {
+        let (results, status) = query(max_path)?;
+        if !status.contains(DtStatus::BUFFER_TOO_SMALL) || max_path >= MAX_BUFFER_SIZE {
+            return Ok(results);
+        }
+
+        max_path = max_path.saturating_mul(2).min(MAX_BUFFER_SIZE);
+        log::debug!(
+            "result buffer too small, retrying with {} results",
+            max_path
+        );
+    }
+}
+
+/// Converts a caller provided buffer capacity to the size passed to Detour
+/// Capacities beyond i32::MAX are clamped, Detour never writes past the size it is given
+fn buffer_size(capacity: usize) -> i32 {
+    i32::try_from(capacity).unwrap_or(i32::MAX)
+}
+
+/// Bounds a result count reported by Detour to the capacity of the buffer it was written into
+/// Detour never reports more results than the size it was given, this guards the set_len calls
+fn result_len(count: i32, capacity: usize) -> usize {
+    debug_assert!(
+        (0..=capacity).contains(&(count as usize)),
+        "detour reported {} results for a buffer of {}",
+        count,
+        capacity
+    );
+
+    usize::try_from(count).unwrap_or(0).min(capacity)
+}
+
+/// Converts an input slice length to the count passed to Detour
+fn input_len(len: usize) -> DivertResult<i32> {
+    i32::try_from(len).map_err(|_| DivertError::InvalidParam("input length exceeds i32::MAX"))
+}
+
+/// Detour merges a straight path's start and end points when they coincide, keeping only the
+/// END flag and the end's null PolyRef, so flag that single point as the START in poly_path too
+fn flag_coincident_start(
+    flags: &mut [DtStraightPathFlags],
+    polys: &mut [PolyRef],
+    poly_path: &[PolyRef],
+) {
+    if let ([flag], [poly_ref]) = (flags, polys) {
+        if *flag == DtStraightPathFlags::END {
+            flag.insert(DtStraightPathFlags::START);
+            *poly_ref = poly_path.first().copied().unwrap_or_default();
+        }
+    }
+}
+
+/// Rejects positions with NaN or infinite components before they reach Detour
+/// Detour doesn't validate its inputs, so these otherwise surface as confusing query failures
+fn check_finite(position: &Vector, message: &'static str) -> DivertResult<()> {
+    if !position.is_finite() {
+        return Err(DivertError::InvalidParam(message));
+    }
+
+    Ok(())
+}
+
+/// Ownership of the tile data passed to NavMesh::add_tile_with_ownership
+///
+/// Detour keeps using the tile data while the tile is loaded, and writes link data into it,
+/// so the data must stay alive and untouched until the tile is removed or the NavMesh dropped
+///
+/// Borrowed data has to outlive the NavMesh:
+///
+/// ```compile_fail
+/// use divert_f64::{NavMesh, NavMeshParams, TileDataOwnership};
+///
+/// let params = NavMeshParams {
+///     origin: [0.0; 3],
+///     tile_width: 1.0,
+///     tile_height: 1.0,
+///     max_tiles: 1,
+///     max_polys: 1,
+/// };
+/// let mut nav_mesh = NavMesh::new(&params).unwrap();
+/// {
+///     let mut tile_data = vec![0u8; 16];
+///     let _ = nav_mesh.add_tile_with_ownership(TileDataOwnership::BorrowedByCaller(&mut tile_data));
+/// }
+/// drop(nav_mesh);
+/// ```
+#[derive(Debug)]
+pub enum TileDataOwnership<'a> {
+    /// The data is copied into a dtAlloc'ed buffer the dtNavMesh takes ownership of
+    /// (DT_TILE_FREE_DATA), freeing it with dtFree, and the Vec is dropped once added
+    TransferToDetour(Vec<u8>),
+    /// The caller keeps ownership, Detour never frees the data
+    /// The data is mutably borrowed for the NavMesh's lifetime, and can be reused once it is dropped
+    BorrowedByCaller(&'a mut [u8]),
+}
+
 /// Safe bindings to dtNavMesh
 /// Handles life time of the dtNavMesh and will release resources when dropped
 pub struct NavMesh<'a> {
     handle: *mut DtNavMesh,
+    /// Bytes of tile data handed to the dtNavMesh and not yet removed, checked when dropped
+    #[cfg(debug_assertions)]
+    tile_bytes: usize,
+    /// Tiles added, removed or edited since the last clear_dirty
+    dirty_tiles: BTreeSet<TileRef>,
+    /// Data of tiles added by add_tile_with without FREE_DATA, kept alive until they're removed
+    /// Held as raw pointers rather than Boxes, as Detour writes to the data while it is loaded
+    retained_tiles: HashMap<TileRef, NonNull<[u8]>>,
     _phantom: marker::PhantomData<&'a DtNavMesh>,
 }
 
@@ -135,537 +438,4013 @@ impl<'a> NavMesh<'a> {
 
         Ok(Self {
             handle: dt_nav_mesh,
+            #[cfg(debug_assertions)]
+            tile_bytes: 0,
+            dirty_tiles: BTreeSet::new(),
+            retained_tiles: HashMap::new(),
+            _phantom: marker::PhantomData,
+        })
+    }
+
+    /// Allocates a dtNavMesh holding a single tile, deriving its params from the tile's header
+    /// The tile data is copied into a dtAlloc'ed buffer owned by the dtNavMesh like add_tile, the
+    /// tile's TileRef can be retrieved through tiles
+    /// Errors if allocation returns a null pointer, or the dtNavMesh->init function returns a failed status
+    pub fn new_single_tile(tile_data: Vec<u8>) -> DivertResult<Self> {
+        let data_size = input_len(tile_data.len())?;
+        let dt_nav_mesh = unsafe { dtNavMesh_alloc() };
+
+        if dt_nav_mesh.is_null() {
+            return Err(DivertError::NullPtr());
+        }
+
+        // Detour frees the tile data with dtFree, so it takes a dtAlloc'ed copy of the data
+        let init_status =
+            unsafe { dtNavMesh_initSingle(dt_nav_mesh, tile_data.as_ptr(), data_size) };
+        if init_status.is_failed() {
+            unsafe { dtNavMesh_free(dt_nav_mesh) };
+            return Err(DivertError::Failure(init_status));
+        }
+
+        Ok(Self {
+            handle: dt_nav_mesh,
+            #[cfg(debug_assertions)]
+            tile_bytes: data_size as usize,
+            dirty_tiles: loaded_tiles(dt_nav_mesh).collect(),
+            retained_tiles: HashMap::new(),
             _phantom: marker::PhantomData,
         })
     }
 
     /// Accepts a byte vector representing a dtTile, adding it to the inner dtNavMesh
-    /// The byte vector is forgotten after being added to the dtNavMesh
-    /// Forgetting the memory is critical, because the memory is now owned by the dtNavMesh
+    /// The bytes are copied into a dtAlloc'ed buffer owned by the dtNavMesh, which frees it with
+    /// dtFree once the tile is removed, so the byte vector is dropped before add_tile returns
+    /// Errors with NavMeshFull once max_tiles tiles are loaded, so callers can evict and retry
+    /// Errors with AlreadyOccupied if a tile is already loaded at the tile's location and layer
+    /// Links to loaded neighbour tiles are connected in both directions before add_tile returns,
+    /// so paths cross the new tile right away, whichever order the tiles are added in
     pub fn add_tile(&mut self, input_data: Vec<u8>) -> DivertResult<TileRef> {
-        let mut boxed_slice = input_data.into_boxed_slice();
-        let data = boxed_slice.as_mut_ptr();
-        let data_size = boxed_slice.len();
+        self.add_tile_with_ownership(TileDataOwnership::TransferToDetour(input_data))
+    }
+
+    /// Adds a dtTile to the inner dtNavMesh, with the given ownership of its data
+    /// See TileDataOwnership for the memory contract of each mode
+    /// Errors with NavMeshFull once max_tiles tiles are loaded, so callers can evict and retry
+    /// Errors with AlreadyOccupied if a tile is already loaded at the tile's location and layer
+    pub fn add_tile_with_ownership(
+        &mut self,
+        tile_data: TileDataOwnership<'a>,
+    ) -> DivertResult<TileRef> {
+        self.add_tile_data(tile_data, TileRef::default())
+    }
+
+    /// Adds a tile like add_tile, with explicit dtNavMesh::addTile flags and last_ref
+    /// With FREE_DATA the data is transferred to the dtNavMesh, like add_tile
+    /// Without it the NavMesh keeps the data alive itself, and drops it when the tile is removed
+    /// A non-null last_ref restores the tile under the TileRef it had when removed
+    /// Errors like add_tile
+    pub fn add_tile_with(
+        &mut self,
+        data: Vec<u8>,
+        flags: TileFlags,
+        last_ref: TileRef,
+    ) -> DivertResult<TileRef> {
+        if flags.contains(TileFlags::FREE_DATA) {
+            return self.add_tile_data(TileDataOwnership::TransferToDetour(data), last_ref);
+        }
+
+        let data = NonNull::from(Box::leak(data.into_boxed_slice()));
+        let tile_ref =
+            match self.add_tile_raw(data.as_ptr().cast(), data.len(), flags.bits(), last_ref) {
+                Ok(tile_ref) => tile_ref,
+                Err(err) => {
+                    unsafe { free_retained_tile(data) };
+                    return Err(err);
+                }
+            };
+        self.retained_tiles.insert(tile_ref, data);
+
+        Ok(tile_ref)
+    }
+
+    /// Replaces the tile at x, y and layer with tile_data, or adds it if no tile is loaded there
+    /// The new tile reuses the replaced tile's TileRef, so PolyRefs into the replaced tile stay
+    /// valid where the new tile has the same polys, and paths held by agents survive rebuilds
+    /// If the new tile can't be added and restore_on_failure is set, the replaced tile's data is
+    /// added back under its TileRef, otherwise the location is left without a tile
+    /// A failed restore is logged, the error returned is always the one adding tile_data failed with
+    /// Errors if tile_data isn't located at x, y and layer, or like add_tile
+    pub fn replace_tile(
+        &mut self,
+        x: i32,
+        y: i32,
+        layer: i32,
+        tile_data: Vec<u8>,
+        restore_on_failure: bool,
+    ) -> DivertResult<TileRef> {
+        if tile_data.len() < std::mem::size_of::<DtMeshHeader>() {
+            return Err(DivertError::InvalidParam(
+                "tile data is too short to hold a header",
+            ));
+        }
+
+        let coord = TileCoord::new(x, y, layer);
+        let header = unsafe { ptr::read_unaligned(tile_data.as_ptr().cast::<DtMeshHeader>()) };
+        if TileCoord::new(header.x(), header.y(), header.layer()) != coord {
+            return Err(DivertError::InvalidParam(
+                "tile data is not located at x, y and layer",
+            ));
+        }
+
+        let Some(replaced_ref) = self.get_tile_at(coord)? else {
+            return self.add_tile(tile_data);
+        };
+
+        let replaced_data = restore_on_failure
+            .then(|| self.tile_data(replaced_ref).map(<[u8]>::to_vec))
+            .transpose()?;
+        self.remove_tile(replaced_ref)?;
+
+        let err = match self
+            .add_tile_data(TileDataOwnership::TransferToDetour(tile_data), replaced_ref)
+        {
+            Ok(tile_ref) => return Ok(tile_ref),
+            Err(err) => err,
+        };
 
+        if let Some(replaced_data) = replaced_data {
+            if let Err(restore_err) = self.add_tile_data(
+                TileDataOwnership::TransferToDetour(replaced_data),
+                replaced_ref,
+            ) {
+                log::warn!(
+                    "NavMesh::replace_tile failed to restore tile {:?} after `{}`: {}",
+                    coord,
+                    err,
+                    restore_err
+                );
+            }
+        }
+
+        Err(err)
+    }
+
+    /// Adds tile data to the inner dtNavMesh, restoring the tile under last_ref if it isn't null
+    fn add_tile_data(
+        &mut self,
+        tile_data: TileDataOwnership<'a>,
+        last_ref: TileRef,
+    ) -> DivertResult<TileRef> {
+        match tile_data {
+            // Detour frees transferred data with dtFree, so it takes a dtAlloc'ed copy of the data
+            TileDataOwnership::TransferToDetour(mut input_data) => self.add_tile_raw(
+                input_data.as_mut_ptr(),
+                input_data.len(),
+                DT_TILE_FREE_DATA,
+                last_ref,
+            ),
+            TileDataOwnership::BorrowedByCaller(input_data) => {
+                self.add_tile_raw(input_data.as_mut_ptr(), input_data.len(), 0, last_ref)
+            }
+        }
+    }
+
+    /// Adds tile data at data to the inner dtNavMesh
+    /// With DT_TILE_FREE_DATA, Detour owns a dtAlloc'ed copy of the data, otherwise the caller is
+    /// responsible for the data's lifetime
+    fn add_tile_raw(
+        &mut self,
+        data: *mut u8,
+        data_size: usize,
+        flags: i32,
+        last_ref: TileRef,
+    ) -> DivertResult<TileRef> {
         let mut tile_ref = TileRef::default();
+        let data_len = input_len(data_size)?;
         let add_tile_status = unsafe {
-            dtNavMesh_addTile(
-                self.handle,
-                data,
-                data_size as i32,
-                1,
-                TileRef::default(),
-                &mut tile_ref,
-            )
+            if flags & DT_TILE_FREE_DATA != 0 {
+                dtNavMesh_addTileCopy(self.handle, data, data_len, flags, last_ref, &mut tile_ref)
+            } else {
+                dtNavMesh_addTile(self.handle, data, data_len, flags, last_ref, &mut tile_ref)
+            }
         };
 
         if add_tile_status.is_failed() {
-            return Err(DivertError::Failure(add_tile_status));
+            return Err(if add_tile_status.contains(DtStatus::ALREADY_OCCUPIED) {
+                DivertError::AlreadyOccupied()
+            } else if add_tile_status.contains(DtStatus::OUT_OF_MEMORY) {
+                DivertError::NavMeshFull()
+            } else {
+                DivertError::Failure(add_tile_status)
+            });
         }
 
-        std::mem::forget(boxed_slice);
+        #[cfg(debug_assertions)]
+        {
+            self.tile_bytes += data_size;
+        }
+        self.dirty_tiles.insert(tile_ref);
         Ok(tile_ref)
     }
-}
-
-/// Handles freeing the inner dtNavMesh
-/// subsequently handles freeing the tile data added to this NavMesh
-impl<'a> Drop for NavMesh<'a> {
-    /// Frees dtNavMesh resources with dtFreeNavMesh
-    fn drop(&mut self) {
-        unsafe { dtNavMesh_free(self.handle) }
-    }
-}
 
-/// Safe bindings to dtQueryFilter
-/// Handles life time of the dtQueryFilter and will release resources when dropped
-pub struct QueryFilter<'a> {
-    handle: *mut DtQueryFilter,
-    _phantom: marker::PhantomData<&'a DtQueryFilter>,
-}
+    /// Removes a tile from the inner dtNavMesh, freeing its tile data
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn remove_tile(&mut self, tile_ref: TileRef) -> DivertResult<()> {
+        #[cfg(debug_assertions)]
+        let data_size = self.tile_data_size(tile_ref);
 
-unsafe impl Send for QueryFilter<'_> {}
+        let remove_tile_status = unsafe {
+            dtNavMesh_removeTile(self.handle, tile_ref, ptr::null_mut(), ptr::null_mut())
+        };
 
-/// Provides functionality to interact with QueryFilter and its underlying dtQueryFilter
-impl<'a> QueryFilter<'a> {
-    /// Allocates a dtQueryFilter
-    /// Errors if the allocation returns a null pointer
-    pub fn new() -> DivertResult<Self> {
-        let dt_query_filter = unsafe { dtQueryFilter_alloc() };
-        if dt_query_filter.is_null() {
-            Err(DivertError::NullPtr())
-        } else {
-            Ok(Self {
-                handle: dt_query_filter,
-                _phantom: marker::PhantomData,
-            })
+        if remove_tile_status.is_failed() {
+            return Err(DivertError::RemoveTileFailure(remove_tile_status));
         }
-    }
 
-    /// Sets the filter's include flags
-    pub fn set_include_flags(&mut self, include_flags: u16) {
-        unsafe {
-            dtQueryFilter_setIncludeFlags(self.handle, include_flags);
+        #[cfg(debug_assertions)]
+        {
+            self.tile_bytes -= data_size;
         }
+        if let Some(data) = self.retained_tiles.remove(&tile_ref) {
+            unsafe { free_retained_tile(data) };
+        }
+        self.dirty_tiles.insert(tile_ref);
+        Ok(())
     }
 
-    /// Retrieves the filter's include flags
-    pub fn get_include_flags(&self) -> u16 {
-        unsafe { dtQueryFilter_getIncludeFlags(self.handle) }
+    /// Size of a loaded tile's data in bytes, 0 if tile_ref doesn't reference a loaded tile
+    #[cfg(debug_assertions)]
+    fn tile_data_size(&self, tile_ref: TileRef) -> usize {
+        self.tile_data(tile_ref).map_or(0, <[u8]>::len)
     }
 
-    /// Sets the filter's exclude flags
-    pub fn set_exclude_flags(&mut self, exclude_flags: u16) {
-        unsafe {
-            dtQueryFilter_setExcludeFlags(self.handle, exclude_flags);
+    /// Serialized data of a loaded tile, as it was passed to add_tile
+    /// The bytes are Detour's internal tile format (dtMeshHeader followed by the tile's arrays),
+    /// Detour stores runtime links in place, so they don't match the added bytes exactly
+    /// The slice borrows the NavMesh, so the tile can't be removed while it is in use
+    /// Errors if tile_ref doesn't reference a loaded tile
+    pub fn tile_data(&self, tile_ref: TileRef) -> DivertResult<&[u8]> {
+        let invalid_tile_ref =
+            DivertError::InvalidParam("tile_ref does not reference a loaded tile");
+
+        let tile = unsafe { dtNavMesh_getTileByRef(self.handle, tile_ref) };
+        if tile.is_null() {
+            return Err(invalid_tile_ref);
         }
-    }
 
-    /// Retrieves the filter's exclude flags
-    pub fn get_exclude_flags(&self) -> u16 {
-        unsafe { dtQueryFilter_getExcludeFlags(self.handle) }
+        let data = unsafe { dtMeshTile_getData(tile) };
+        if data.is_null() {
+            return Err(invalid_tile_ref);
+        }
+
+        Ok(unsafe { std::slice::from_raw_parts(data, dtMeshTile_getDataSize(tile) as usize) })
     }
-}
 
-/// Handles freeing the inner dtQueryFilter
-impl<'a> Drop for QueryFilter<'a> {
-    fn drop(&mut self) {
-        unsafe { dtQueryFilter_free(self.handle) }
+    /// Sum of the data sizes of all loaded tiles, as seen by the dtNavMesh
+    #[cfg(debug_assertions)]
+    fn live_tile_bytes(&self) -> usize {
+        self.tiles()
+            .map(|tile_ref| self.tile_data_size(tile_ref))
+            .sum()
     }
-}
 
-/// Safe bindings to dtNavMeshQuery
-/// Handles life time of the dtNavMeshQuery and will release resources when dropped
-pub struct NavMeshQuery<'a> {
-    handle: *mut DtNavMeshQuery,
-    _phantom: marker::PhantomData<&'a DtNavMeshQuery>,
-}
+    /// Accounts for tiles Detour added or removed itself, as a TileCache does when rebuilding tiles
+    /// Tiles loaded or unloaded since loaded was collected are marked dirty
+    fn sync_loaded_tiles(&mut self, loaded: &BTreeSet<TileRef>) {
+        let now_loaded: BTreeSet<TileRef> = self.tiles().collect();
+        self.dirty_tiles
+            .extend(loaded.symmetric_difference(&now_loaded).copied());
+        self.retained_tiles.retain(|tile_ref, data| {
+            let loaded = now_loaded.contains(tile_ref);
+            if !loaded {
+                unsafe { free_retained_tile(*data) };
+            }
+            loaded
+        });
 
-unsafe impl Send for NavMeshQuery<'_> {}
+        #[cfg(debug_assertions)]
+        {
+            self.tile_bytes = self.live_tile_bytes();
+        }
+    }
 
-/// Provides functionality to interact with NavMeshQuery and its underlying dtNavMeshQuery
-impl<'a> NavMeshQuery<'a> {
-    /// Allocates and initializes a dtNavMeshQuery for NavMeshQuery to handle
-    /// Errors if allocation returns a null pointer, or the dtNavMeshQuery->init function returns a failed status
-    pub fn new(nav_mesh: &NavMesh, max_nodes: i32) -> DivertResult<Self> {
-        let dt_nav_mesh_query = unsafe { dtNavMeshQuery_alloc() };
+    /// Iterates the TileRef of every loaded tile
+    /// Walks each of the dtNavMesh's tile slots, skipping slots without tile data
+    pub fn tiles(&self) -> impl Iterator<Item = TileRef> + '_ {
+        loaded_tiles(self.handle)
+    }
 
-        if dt_nav_mesh_query.is_null() {
-            return Err(DivertError::NullPtr());
+    /// Looks up the tile at the given tile grid location and layer
+    /// Returns None if no tile occupies the location
+    /// Errors if layer is negative
+    pub fn get_tile_at(&self, coord: TileCoord) -> DivertResult<Option<TileRef>> {
+        if coord.layer < 0 {
+            return Err(DivertError::InvalidParam("layer must not be negative"));
         }
 
-        let init_status =
-            unsafe { dtNavMeshQuery_init(dt_nav_mesh_query, nav_mesh.handle, max_nodes) };
-        if init_status.is_failed() {
-            return Err(DivertError::Failure(init_status));
+        let tile = unsafe { dtNavMesh_getTileAt(self.handle, coord.x, coord.y, coord.layer) };
+        if tile.is_null() {
+            return Ok(None);
         }
 
-        Ok(Self {
-            handle: dt_nav_mesh_query,
-            _phantom: marker::PhantomData,
-        })
+        Ok(Some(unsafe { dtNavMesh_getTileRef(self.handle, tile) }))
     }
 
-    /// Queries for polygon height given the reference polygon and position on the polygon
-    /// Errors if ffi function returns a failed DtStatus
-    pub fn get_poly_height(&self, poly_ref: PolyRef, position: &DtVector) -> DivertResult<f64> {
-        let mut height: f64 = 0.0;
+    /// Calculates the tile grid location containing a position, in layer 0
+    pub fn calc_tile_loc(&self, position: &Vector) -> TileCoord {
+        let mut coord = TileCoord::default();
+        unsafe { dtNavMesh_calcTileLoc(self.handle, position, &mut coord.x, &mut coord.y) };
 
-        let get_poly_height_status =
-            unsafe { dtNavMeshQuery_getPolyHeight(self.handle, poly_ref, position, &mut height) };
+        coord
+    }
 
-        if get_poly_height_status.is_failed() {
-            return Err(DivertError::GetPolyHeightFailure(get_poly_height_status));
+    /// Gets the header of a loaded tile
+    /// Returns None if tile_ref doesn't reference a loaded tile
+    pub fn get_tile_header(&self, tile_ref: TileRef) -> Option<&DtMeshHeader> {
+        let tile = unsafe { dtNavMesh_getTileByRef(self.handle, tile_ref) };
+        if tile.is_null() {
+            return None;
         }
 
-        Ok(height)
+        unsafe { dtMeshTile_getHeader(tile).as_ref() }
     }
 
-    /// Queries for nearest polygon given a center point, a search area (extents), and a filter
+    /// AABB covering every loaded tile, as the union of the tile headers' bounds
+    /// Returns None if no tile is loaded
+    pub fn world_bounds(&self) -> Option<(Vector, Vector)> {
+        self.tiles()
+            .filter_map(|tile_ref| self.get_tile_header(tile_ref))
+            .map(|header| (header.bmin(), header.bmax()))
+            .reduce(|(min, max), (b_min, b_max)| (min.min(b_min), max.max(b_max)))
+    }
+
+    /// AABB covering every loaded tile, same as world_bounds
+    /// Returns None if no tile is loaded
+    pub fn compute_bounds(&self) -> Option<(Vector, Vector)> {
+        self.world_bounds()
+    }
+
+    /// Estimated bytes held by the nav mesh
+    /// Sums the data size of every loaded tile with the dtNavMesh's fixed allocations,
+    /// its tile slots and position lookup, sized by max_tiles
+    /// The position lookup size is an estimate mirroring Detour's private sizing,
+    /// and may be off if Detour changes how it sizes the lookup
+    pub fn memory_usage(&self) -> usize {
+        let tile_bytes: usize = self
+            .tiles()
+            .filter_map(|tile_ref| self.tile_data(tile_ref).ok())
+            .map(<[u8]>::len)
+            .sum();
+
+        tile_bytes + unsafe { dtNavMesh_getBaseMemoryUsage(self.handle) }
+    }
+
+    /// Quantizes a point relative to a tile's bounds into the tile's BV tree space, truncating each axis
+    /// Detour's BV tree queries additionally round the query box outward, clearing the low bit of
+    /// its min (& 0xfffe) and setting it on its max (| 1), which this function doesn't do
+    /// The point is clamped to the tile bounds before quantization
+    /// Errors if tile_ref doesn't reference a loaded tile
+    pub fn quantize_point(&self, tile_ref: TileRef, pos: &Vector) -> DivertResult<[u16; 3]> {
+        let header = self
+            .get_tile_header(tile_ref)
+            .ok_or(DivertError::InvalidParam(
+                "tile_ref does not reference a loaded tile",
+            ))?;
+
+        let b_min = header.bmin();
+        let quantized = (pos.clamp(b_min, header.bmax()) - b_min) * header.bv_quant_factor();
+
+        Ok([quantized.x as u16, quantized.y as u16, quantized.z as u16])
+    }
+
+    /// Gets the user defined area id of a poly
     /// Errors if ffi function returns a failed DtStatus
-    pub fn find_nearest_poly(
-        &self,
-        center: &Vector,
-        extents: &Vector,
-        filter: &QueryFilter,
-    ) -> DivertResult<(PolyRef, Vector)> {
-        let mut closest_point = Vector::default();
-        let mut nearest_ref = PolyRef::default();
+    pub fn get_poly_area(&self, poly_ref: PolyRef) -> DivertResult<u8> {
+        get_poly_area(self.handle, poly_ref)
+    }
 
-        let nearest_status = unsafe {
-            dtNavMeshQuery_findNearestPoly(
-                self.handle,
-                center,
-                extents,
-                filter.handle,
-                &mut nearest_ref,
-                &mut closest_point,
-            )
-        };
+    /// Gets the user defined flags of a poly
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn get_poly_flags(&self, poly_ref: PolyRef) -> DivertResult<u16> {
+        get_poly_flags(self.handle, poly_ref)
+    }
 
-        if nearest_status.is_failed() {
-            return Err(DivertError::FindNearestPolyFailure(nearest_status));
+    /// Sets the user defined area id of a poly, marking its tile dirty
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn set_poly_area(&mut self, poly_ref: PolyRef, area: u8) -> DivertResult<()> {
+        let set_poly_area_status = unsafe { dtNavMesh_setPolyArea(self.handle, poly_ref, area) };
+        if set_poly_area_status.is_failed() {
+            return Err(DivertError::SetPolyAreaFailure(set_poly_area_status));
         }
 
-        Ok((nearest_ref, closest_point))
+        self.mark_poly_dirty(poly_ref)
     }
 
-    /// Queries for closest point on poly to a given position
+    /// Sets the user defined flags of a poly, marking its tile dirty
     /// Errors if ffi function returns a failed DtStatus
-    pub fn closest_point_on_poly(
-        &self,
+    pub fn set_poly_flags(&mut self, poly_ref: PolyRef, flags: u16) -> DivertResult<()> {
+        let set_poly_flags_status = unsafe { dtNavMesh_setPolyFlags(self.handle, poly_ref, flags) };
+        if set_poly_flags_status.is_failed() {
+            return Err(DivertError::SetPolyFlagsFailure(set_poly_flags_status));
+        }
+
+        self.mark_poly_dirty(poly_ref)
+    }
+
+    fn mark_poly_dirty(&mut self, poly_ref: PolyRef) -> DivertResult<()> {
+        let (tile, _) = self.get_tile_and_poly_by_ref(poly_ref)?;
+
+        let tile_ref = unsafe { dtNavMesh_getTileRef(self.handle, tile) };
+        self.dirty_tiles.insert(tile_ref);
+        Ok(())
+    }
+
+    /// Tiles added, removed, or with polys edited through set_poly_area and set_poly_flags
+    /// since the NavMesh was created or clear_dirty was last called, in TileRef order
+    /// Removed tiles are included so incremental saves can drop them, their TileRef no longer
+    /// references a loaded tile
+    pub fn dirty_tiles(&self) -> Vec<TileRef> {
+        self.dirty_tiles.iter().copied().collect()
+    }
+
+    /// Forgets all dirty tiles, typically after they've been saved
+    pub fn clear_dirty(&mut self) {
+        self.dirty_tiles.clear();
+    }
+
+    fn get_tile_and_poly_by_ref(
+        &self,
         poly_ref: PolyRef,
-        position: &Vector,
-    ) -> DivertResult<(Vector, bool)> {
-        let mut closest_point = Vector::default();
-        let mut position_over_poly = false;
+    ) -> DivertResult<(*const DtMeshTile, *const DtPoly)> {
+        get_tile_and_poly_by_ref(self.handle, poly_ref)
+    }
+
+    /// Gets the (x, y) tile grid location of the tile containing a poly
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn poly_tile_coords(&self, poly_ref: PolyRef) -> DivertResult<(i32, i32)> {
+        let (tile, _) = self.get_tile_and_poly_by_ref(poly_ref)?;
+
+        let header = unsafe { &*dtMeshTile_getHeader(tile) };
+        Ok((header.x(), header.y()))
+    }
+
+    /// Gets the world space vertices of a poly, in Detour's winding order
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn get_poly_vertices(&self, poly_ref: PolyRef) -> DivertResult<Vec<Vector>> {
+        get_poly_vertices(self.handle, poly_ref)
+    }
+}
+
+fn get_poly_vertices(nav_mesh: *const DtNavMesh, poly_ref: PolyRef) -> DivertResult<Vec<Vector>> {
+    let (tile, poly) = get_tile_and_poly_by_ref(nav_mesh, poly_ref)?;
+
+    let vertices = unsafe {
+        let tile_verts = dtMeshTile_getVerts(tile);
+        let poly_verts =
+            std::slice::from_raw_parts(dtPoly_getVerts(poly), dtPoly_getVertCount(poly).into());
+
+        poly_verts
+            .iter()
+            .map(|&index| *tile_verts.add(index.into()))
+            .collect()
+    };
+
+    Ok(vertices)
+}
+
+fn loaded_tiles(nav_mesh: *const DtNavMesh) -> impl Iterator<Item = TileRef> {
+    let max_tiles = unsafe { dtNavMesh_getMaxTiles(nav_mesh) };
+
+    (0..max_tiles).filter_map(move |index| {
+        let tile = unsafe { dtNavMesh_getTile(nav_mesh, index) };
+
+        if tile.is_null() || unsafe { dtMeshTile_getHeader(tile) }.is_null() {
+            return None;
+        }
+
+        Some(unsafe { dtNavMesh_getTileRef(nav_mesh, tile) })
+    })
+}
+
+fn get_tile_and_poly_by_ref(
+    nav_mesh: *const DtNavMesh,
+    poly_ref: PolyRef,
+) -> DivertResult<(*const DtMeshTile, *const DtPoly)> {
+    let mut tile = ptr::null();
+    let mut poly = ptr::null();
+
+    let get_tile_and_poly_status =
+        unsafe { dtNavMesh_getTileAndPolyByRef(nav_mesh, poly_ref, &mut tile, &mut poly) };
+    if get_tile_and_poly_status.is_failed() {
+        return Err(DivertError::GetTileAndPolyByRefFailure(
+            get_tile_and_poly_status,
+        ));
+    }
+
+    Ok((tile, poly))
+}
+
+/// Polys linked from a poly, in the poly's link order
+fn linked_polys(nav_mesh: *const DtNavMesh, poly_ref: PolyRef) -> DivertResult<Vec<PolyRef>> {
+    let (tile, poly) = get_tile_and_poly_by_ref(nav_mesh, poly_ref)?;
+
+    let mut neighbours = Vec::new();
+    let mut link = unsafe { dtPoly_getFirstLink(poly) };
+    while link != DT_NULL_LINK {
+        neighbours.push(unsafe { dtMeshTile_getLinkRef(tile, link) });
+        link = unsafe { dtMeshTile_getLinkNext(tile, link) };
+    }
+
+    Ok(neighbours)
+}
+
+/// Off-mesh connection polys linked from a poly, in the poly's link order
+fn linked_off_mesh_connections(
+    nav_mesh: *const DtNavMesh,
+    poly_ref: PolyRef,
+) -> DivertResult<Vec<PolyRef>> {
+    let mut off_mesh_connections = Vec::new();
+    for neighbour_ref in linked_polys(nav_mesh, poly_ref)? {
+        let (_, neighbour) = get_tile_and_poly_by_ref(nav_mesh, neighbour_ref)?;
+        if unsafe { dtPoly_getType(neighbour) } == DT_POLYTYPE_OFFMESH_CONNECTION {
+            off_mesh_connections.push(neighbour_ref);
+        }
+    }
+
+    Ok(off_mesh_connections)
+}
+
+fn get_poly_area(nav_mesh: *const DtNavMesh, poly_ref: PolyRef) -> DivertResult<u8> {
+    let mut area = 0;
+
+    let get_poly_area_status = unsafe { dtNavMesh_getPolyArea(nav_mesh, poly_ref, &mut area) };
+    if get_poly_area_status.is_failed() {
+        return Err(DivertError::GetPolyAreaFailure(get_poly_area_status));
+    }
+
+    Ok(area)
+}
+
+fn get_poly_flags(nav_mesh: *const DtNavMesh, poly_ref: PolyRef) -> DivertResult<u16> {
+    let mut flags = 0;
+
+    let get_poly_flags_status = unsafe { dtNavMesh_getPolyFlags(nav_mesh, poly_ref, &mut flags) };
+    if get_poly_flags_status.is_failed() {
+        return Err(DivertError::GetPolyFlagsFailure(get_poly_flags_status));
+    }
+
+    Ok(flags)
+}
+
+/// Handles freeing the inner dtNavMesh
+/// subsequently handles freeing the tile data added to this NavMesh
+impl<'a> Drop for NavMesh<'a> {
+    /// Frees dtNavMesh resources with dtFreeNavMesh
+    /// In debug builds, warns if the tile data added and removed doesn't match the loaded tiles
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        {
+            let live_tile_bytes = self.live_tile_bytes();
+            if live_tile_bytes != self.tile_bytes {
+                log::warn!(
+                    "NavMesh tile data mismatch on drop: {} bytes added but {} bytes loaded",
+                    self.tile_bytes,
+                    live_tile_bytes
+                );
+            }
+        }
+
+        unsafe { dtNavMesh_free(self.handle) }
+        for (_, data) in self.retained_tiles.drain() {
+            unsafe { free_retained_tile(data) };
+        }
+    }
+}
+
+/// Frees tile data retained by NavMesh::add_tile_with
+/// Safety: data must come from a leaked Box, and no longer be referenced by a dtNavMesh
+unsafe fn free_retained_tile(data: NonNull<[u8]>) {
+    drop(Box::from_raw(data.as_ptr()));
+}
+
+/// NavMesh shared between threads, created by NavMesh::into_shared
+/// Cloning is cheap and hands out another reference to the same dtNavMesh
+/// The mesh is immutable while shared: tiles can't be added or removed and poly flags and areas
+/// can't be edited, Detour's read-only queries are safe to run from several threads at once
+/// Use try_unwrap to get the NavMesh back for editing once every clone and query is dropped
+#[derive(Clone)]
+pub struct SharedNavMesh(Arc<NavMesh<'static>>);
+
+// SAFETY: SharedNavMesh only hands out &NavMesh, through Deref, and NavMesh has no interior
+// mutability, so every access to the dtNavMesh from a shared reference goes through a &self method
+// Those only call Detour's const dtNavMesh methods: getTile, getTileAt, getTileByRef, getTileRef,
+// getTileAndPolyByRef, getPolyArea, getPolyFlags, getParams, getMaxTiles and calcTileLoc, plus
+// the dtMeshTile and dtPoly getters and dtNavMesh_getBaseMemoryUsage, which only read
+// The FFI calls that mutate the dtNavMesh (init, addTile, removeTile, setPolyArea, setPolyFlags and
+// dtFreeNavMesh) take a *mut DtNavMesh and are only reachable from &mut self methods, NavMesh's
+// constructors and Drop, none of which a shared reference can reach
+// NavMeshQuery::new_shared attaches queries through dtNavMeshQuery::init, which keeps a
+// const dtNavMesh*, and Drop only runs once the last clone or query releases the Arc
+unsafe impl Send for SharedNavMesh {}
+unsafe impl Sync for SharedNavMesh {}
+
+impl SharedNavMesh {
+    /// Returns the NavMesh if this is the last reference to it, otherwise returns self unchanged
+    pub fn try_unwrap(self) -> Result<NavMesh<'static>, Self> {
+        Arc::try_unwrap(self.0).map_err(Self)
+    }
+}
+
+impl ops::Deref for SharedNavMesh {
+    type Target = NavMesh<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl NavMesh<'static> {
+    /// Moves the NavMesh behind an Arc so queries on several threads can share it
+    /// Only NavMeshes owning their tile data can be shared
+    // SharedNavMesh is Send and Sync itself, see its SAFETY comment, so NavMesh only being Send
+    // doesn't apply to the Arc
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub fn into_shared(self) -> SharedNavMesh {
+        SharedNavMesh(Arc::new(self))
+    }
+}
+
+/// Safe bindings to dtQueryFilter
+/// Handles life time of the dtQueryFilter and will release resources when dropped
+///
+/// QueryFilter is Send but not Sync: a filter can be moved to another thread, but not shared
+/// The dtQueryFilter is mutated through its setters without synchronization, so reading it from
+/// queries on one thread while it is configured or read on another would be a data race (UB)
+/// Give each thread its own filter, configured before it is moved:
+///
+/// ```
+/// use divert_f64::QueryFilter;
+///
+/// let mut filter = QueryFilter::new().unwrap();
+/// filter.set_include_flags(1);
+///
+/// std::thread::spawn(move || assert_eq!(filter.get_include_flags(), 1))
+///     .join()
+///     .unwrap();
+/// ```
+///
+/// Sharing a filter between threads does not compile:
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<divert_f64::QueryFilter>();
+/// ```
+pub struct QueryFilter<'a> {
+    handle: *mut DtQueryFilter,
+    _phantom: marker::PhantomData<&'a DtQueryFilter>,
+}
+
+// The handle is uniquely owned by QueryFilter, so moving it to another thread is sound
+// QueryFilter is intentionally not Sync, see the type level documentation
+unsafe impl Send for QueryFilter<'_> {}
+
+/// Provides functionality to interact with QueryFilter and its underlying dtQueryFilter
+impl<'a> QueryFilter<'a> {
+    /// Allocates a dtQueryFilter
+    /// Errors if the allocation returns a null pointer
+    pub fn new() -> DivertResult<Self> {
+        let dt_query_filter = unsafe { dtQueryFilter_alloc() };
+        if dt_query_filter.is_null() {
+            Err(DivertError::NullPtr())
+        } else {
+            Ok(Self {
+                handle: dt_query_filter,
+                _phantom: marker::PhantomData,
+            })
+        }
+    }
+
+    /// Sets the filter's include flags
+    pub fn set_include_flags(&mut self, include_flags: u16) {
+        unsafe {
+            dtQueryFilter_setIncludeFlags(self.handle, include_flags);
+        }
+    }
+
+    /// Retrieves the filter's include flags
+    pub fn get_include_flags(&self) -> u16 {
+        unsafe { dtQueryFilter_getIncludeFlags(self.handle) }
+    }
+
+    /// Sets the filter's exclude flags
+    pub fn set_exclude_flags(&mut self, exclude_flags: u16) {
+        unsafe {
+            dtQueryFilter_setExcludeFlags(self.handle, exclude_flags);
+        }
+    }
+
+    /// Retrieves the filter's exclude flags
+    pub fn get_exclude_flags(&self) -> u16 {
+        unsafe { dtQueryFilter_getExcludeFlags(self.handle) }
+    }
+
+    /// Sets the traversal cost multiplier of an area id
+    /// Errors if area is not below DT_MAX_AREAS
+    pub fn set_area_cost(&mut self, area: u8, cost: f64) -> DivertResult<()> {
+        if area as usize >= DT_MAX_AREAS {
+            return Err(DivertError::InvalidParam("area must be below DT_MAX_AREAS"));
+        }
+
+        unsafe { dtQueryFilter_setAreaCost(self.handle, area as i32, cost) };
+        Ok(())
+    }
+
+    /// Retrieves the traversal cost multiplier of an area id
+    /// Errors if area is not below DT_MAX_AREAS
+    pub fn get_area_cost(&self, area: u8) -> DivertResult<f64> {
+        if area as usize >= DT_MAX_AREAS {
+            return Err(DivertError::InvalidParam("area must be below DT_MAX_AREAS"));
+        }
+
+        Ok(unsafe { dtQueryFilter_getAreaCost(self.handle, area as i32) })
+    }
+
+    /// Sets the traversal cost multipliers of every area id at once, indexed by area id
+    pub fn set_area_costs(&mut self, costs: &[f64; DT_MAX_AREAS]) {
+        for (area, cost) in costs.iter().enumerate() {
+            unsafe { dtQueryFilter_setAreaCost(self.handle, area as i32, *cost) };
+        }
+    }
+
+    /// Retrieves the traversal cost multipliers of every area id at once, indexed by area id
+    pub fn get_area_costs(&self) -> [f64; DT_MAX_AREAS] {
+        std::array::from_fn(|area| unsafe { dtQueryFilter_getAreaCost(self.handle, area as i32) })
+    }
+
+    /// Adds flag to the filter's include flags, keeping the existing flags
+    pub fn add_include_flag(&mut self, flag: u16) {
+        self.set_include_flags(self.get_include_flags() | flag);
+    }
+
+    /// Removes flag from the filter's include flags, keeping the remaining flags
+    pub fn remove_include_flag(&mut self, flag: u16) {
+        self.set_include_flags(self.get_include_flags() & !flag);
+    }
+
+    /// Adds flag to the filter's exclude flags, keeping the existing flags
+    pub fn add_exclude_flag(&mut self, flag: u16) {
+        self.set_exclude_flags(self.get_exclude_flags() | flag);
+    }
+
+    /// Removes flag from the filter's exclude flags, keeping the remaining flags
+    pub fn remove_exclude_flag(&mut self, flag: u16) {
+        self.set_exclude_flags(self.get_exclude_flags() & !flag);
+    }
+}
+
+/// Handles freeing the inner dtQueryFilter
+impl<'a> Drop for QueryFilter<'a> {
+    fn drop(&mut self) {
+        unsafe { dtQueryFilter_free(self.handle) }
+    }
+}
+
+/// Controls how NavMeshQuery::snap retries when no poly is found within the initial extents
+/// Each retry multiplies the vertical (y) extent by growth, leaving the horizontal extents as is,
+/// so agents on slopes or uneven terrain still snap without widening the search sideways
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VerticalSnapPolicy {
+    /// Factor the vertical extent is multiplied by on each retry, must be greater than 1
+    pub growth: f64,
+    /// Number of retries with expanded vertical extents, 0 disables retrying
+    pub max_retries: u32,
+}
+
+impl Default for VerticalSnapPolicy {
+    fn default() -> Self {
+        Self {
+            growth: 4.0,
+            max_retries: 2,
+        }
+    }
+}
+
+/// Plain data configuration of a NavMeshQuery, for creating many queries from one template
+/// Filters are passed to each query call, so they aren't part of the configuration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NavMeshQueryConfig {
+    /// Maximum number of search nodes
+    pub max_nodes: i32,
+    /// Search area (half extents) used by the _default query methods
+    pub default_extents: Vector,
+    /// Heuristic scale used by the find_path methods
+    pub heuristic_scale: f64,
+}
+
+/// Safe bindings to dtNavMeshQuery
+/// Handles life time of the dtNavMeshQuery and will release resources when dropped
+pub struct NavMeshQuery<'a> {
+    handle: *mut DtNavMeshQuery,
+    default_extents: Vector,
+    straight_path_buffers: StraightPathBuffers,
+    heuristic_scale: f64,
+    /// Filter the find_path methods copy their filter into while the heuristic scale isn't 1,
+    /// allocated when the scale is set, so weighting the filter doesn't allocate per query
+    heuristic_filter: RefCell<Option<QueryFilter<'static>>>,
+    /// Keeps a shared nav mesh alive for queries created by new_shared
+    _shared_nav_mesh: Option<SharedNavMesh>,
+    _phantom: marker::PhantomData<&'a DtNavMeshQuery>,
+}
+
+/// Search area (half extents) used by NavMeshQuery's default extent queries until overridden
+pub const DEFAULT_EXTENTS: Vector = Vector::new(2.0, 4.0, 2.0);
+
+unsafe impl Send for NavMeshQuery<'_> {}
+
+/// Provides functionality to interact with NavMeshQuery and its underlying dtNavMeshQuery
+impl<'a> NavMeshQuery<'a> {
+    /// Allocates and initializes a dtNavMeshQuery for NavMeshQuery to handle
+    /// Errors if allocation returns a null pointer, or the dtNavMeshQuery->init function returns a failed status
+    pub fn new(nav_mesh: &NavMesh, max_nodes: i32) -> DivertResult<Self> {
+        let dt_nav_mesh_query = unsafe { dtNavMeshQuery_alloc() };
+
+        if dt_nav_mesh_query.is_null() {
+            return Err(DivertError::NullPtr());
+        }
+
+        let init_status =
+            unsafe { dtNavMeshQuery_init(dt_nav_mesh_query, nav_mesh.handle, max_nodes) };
+        if init_status.is_failed() {
+            return Err(DivertError::Failure(init_status));
+        }
+
+        Ok(Self {
+            handle: dt_nav_mesh_query,
+            default_extents: DEFAULT_EXTENTS,
+            straight_path_buffers: StraightPathBuffers::default(),
+            heuristic_scale: 1.0,
+            heuristic_filter: RefCell::new(None),
+            _shared_nav_mesh: None,
+            _phantom: marker::PhantomData,
+        })
+    }
+
+    /// Allocates and initializes a dtNavMeshQuery like new, on a shared nav mesh
+    /// The query holds a reference to nav_mesh, keeping it alive until the query is dropped
+    /// Errors if allocation returns a null pointer, or the dtNavMeshQuery->init function returns a failed status
+    pub fn new_shared(nav_mesh: &SharedNavMesh, max_nodes: i32) -> DivertResult<Self> {
+        let mut nav_mesh_query = Self::new(nav_mesh, max_nodes)?;
+        nav_mesh_query._shared_nav_mesh = Some(nav_mesh.clone());
+
+        Ok(nav_mesh_query)
+    }
+
+    /// Allocates and initializes a dtNavMeshQuery configured by config
+    /// Errors if allocation returns a null pointer, the dtNavMeshQuery->init function returns a
+    /// failed status, or the heuristic scale isn't finite and positive
+    pub fn with_config(nav_mesh: &NavMesh, config: &NavMeshQueryConfig) -> DivertResult<Self> {
+        let mut nav_mesh_query = Self::new(nav_mesh, config.max_nodes)?;
+        nav_mesh_query.set_default_extents(config.default_extents);
+        nav_mesh_query.set_heuristic_scale(config.heuristic_scale)?;
+
+        Ok(nav_mesh_query)
+    }
+
+    /// Retrieves the query's configuration, to create other queries configured the same way
+    pub fn config(&self) -> NavMeshQueryConfig {
+        NavMeshQueryConfig {
+            max_nodes: self.get_node_pool_size(),
+            default_extents: self.default_extents,
+            heuristic_scale: self.heuristic_scale,
+        }
+    }
+
+    /// Maps a failed findPath status, reporting EmptyNavMesh if the nav mesh has no loaded tiles
+    fn find_path_failure(&self, find_path_status: DtStatus) -> DivertError {
+        if self.nav_mesh_is_empty() {
+            DivertError::EmptyNavMesh()
+        } else {
+            DivertError::FindPathFailure(find_path_status)
+        }
+    }
+
+    /// Whether the attached nav mesh has no loaded tiles
+    /// Stops at the first loaded tile, but walks every tile slot of an empty nav mesh,
+    /// so it is only checked once a query came up empty
+    fn nav_mesh_is_empty(&self) -> bool {
+        let nav_mesh = unsafe { dtNavMeshQuery_getAttachedNavMesh(self.handle) };
+        loaded_tiles(nav_mesh).next().is_none()
+    }
+
+    /// Sets the search area (extents) used by the _default query methods
+    pub fn set_default_extents(&mut self, extents: Vector) {
+        self.default_extents = extents;
+    }
+
+    /// Retrieves the search area (extents) used by the _default query methods
+    pub fn get_default_extents(&self) -> Vector {
+        self.default_extents
+    }
+
+    /// Sets how much the find_path methods weight the A* heuristic against the path cost
+    /// Detour's heuristic scale is fixed, so the filter's area costs are divided by scale instead,
+    /// which orders the search the same way as multiplying the heuristic
+    /// Scales above 1 expand fewer nodes and find paths faster, at the cost of paths up to scale
+    /// times longer than the optimal one, 1 keeps Detour's near optimal search
+    /// Sliced path queries are not affected
+    /// A scale other than 1 allocates a filter once, which the find_path methods copy their
+    /// filter into without allocating
+    /// Errors if scale isn't finite and positive, or allocating the filter returns a null pointer
+    pub fn set_heuristic_scale(&mut self, scale: f64) -> DivertResult<()> {
+        if !scale.is_finite() || scale <= 0.0 {
+            return Err(DivertError::InvalidParam(
+                "heuristic scale must be finite and positive",
+            ));
+        }
+
+        let heuristic_filter = self.heuristic_filter.get_mut();
+        if scale == 1.0 {
+            *heuristic_filter = None;
+        } else if heuristic_filter.is_none() {
+            *heuristic_filter = Some(QueryFilter::new()?);
+        }
+
+        self.heuristic_scale = scale;
+        Ok(())
+    }
+
+    /// Retrieves the heuristic scale used by the find_path methods
+    pub fn get_heuristic_scale(&self) -> f64 {
+        self.heuristic_scale
+    }
+
+    /// Copies filter into the query's heuristic filter, with its area costs divided by the heuristic scale
+    /// Returns None while the heuristic scale is 1, so the filter can be used as is
+    fn weighted_filter(&self, filter: &QueryFilter) -> Option<RefMut<'_, QueryFilter<'static>>> {
+        let mut weighted =
+            RefMut::filter_map(self.heuristic_filter.borrow_mut(), Option::as_mut).ok()?;
+        weighted.set_include_flags(filter.get_include_flags());
+        weighted.set_exclude_flags(filter.get_exclude_flags());
+        weighted.set_area_costs(
+            &filter
+                .get_area_costs()
+                .map(|cost| cost / self.heuristic_scale),
+        );
+
+        Some(weighted)
+    }
+
+    /// Reinitializes the query against its nav mesh with room for max_nodes search nodes
+    /// Detour keeps the existing node pool when it already holds max_nodes, so the pool never shrinks
+    /// Errors if the dtNavMeshQuery->init function returns a failed status
+    pub fn reinit(&mut self, max_nodes: i32) -> DivertResult<()> {
+        let nav_mesh = unsafe { dtNavMeshQuery_getAttachedNavMesh(self.handle) };
+        let init_status = unsafe { dtNavMeshQuery_init(self.handle, nav_mesh, max_nodes) };
+        if init_status.is_failed() {
+            return Err(DivertError::Failure(init_status));
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the dtNavMeshQuery with a new one on the same nav mesh, holding max_nodes search nodes
+    /// Unlike reinit, this can shrink the node pool
+    /// Errors if allocation returns a null pointer, or the dtNavMeshQuery->init function returns a failed status
+    fn rebuild(&mut self, max_nodes: i32) -> DivertResult<()> {
+        let dt_nav_mesh_query = unsafe { dtNavMeshQuery_alloc() };
+        if dt_nav_mesh_query.is_null() {
+            return Err(DivertError::NullPtr());
+        }
+
+        let nav_mesh = unsafe { dtNavMeshQuery_getAttachedNavMesh(self.handle) };
+        let init_status = unsafe { dtNavMeshQuery_init(dt_nav_mesh_query, nav_mesh, max_nodes) };
+        if init_status.is_failed() {
+            unsafe { dtNavMeshQuery_free(dt_nav_mesh_query) };
+            return Err(DivertError::Failure(init_status));
+        }
+
+        unsafe { dtNavMeshQuery_free(self.handle) };
+        self.handle = dt_nav_mesh_query;
+        Ok(())
+    }
+
+    /// Retrieves the maximum number of search nodes, as set by max_nodes on initialization
+    pub fn get_node_pool_size(&self) -> i32 {
+        unsafe { dtNavMeshQuery_getNodePoolSize(self.handle) }
+    }
+
+    /// Retrieves the number of search nodes used by the most recent query
+    /// Compare against get_node_pool_size to tune max_nodes when queries run OUT_OF_NODES
+    pub fn get_nodes_used(&self) -> i32 {
+        unsafe { dtNavMeshQuery_getNodesUsed(self.handle) }
+    }
+
+    /// Checks whether a poly passes the filter, which queries rely on to decide whether a poly can be visited
+    /// A poly passes when its flags share a bit with the include flags and none with the exclude flags
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn filter_passes(&self, poly_ref: PolyRef, filter: &QueryFilter) -> DivertResult<bool> {
+        let nav_mesh = unsafe { dtNavMeshQuery_getAttachedNavMesh(self.handle) };
+        let flags = get_poly_flags(nav_mesh, poly_ref)?;
+
+        Ok(flags & filter.get_include_flags() != 0 && flags & filter.get_exclude_flags() == 0)
+    }
+
+    /// Queries for polygon height given the reference polygon and position on the polygon
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn get_poly_height(&self, poly_ref: PolyRef, position: &DtVector) -> DivertResult<f64> {
+        check_finite(position, "position must be finite")?;
+
+        let mut height: f64 = 0.0;
+
+        let get_poly_height_status =
+            unsafe { dtNavMeshQuery_getPolyHeight(self.handle, poly_ref, position, &mut height) };
+
+        if get_poly_height_status.is_failed() {
+            return Err(DivertError::GetPolyHeightFailure(get_poly_height_status));
+        }
+
+        Ok(height)
+    }
+
+    /// Queries polygon heights for a batch of (poly, position) samples, such as a smoothed path
+    /// Samples where the query fails, for example positions outside their poly, are None
+    pub fn sample_heights(&self, path: &[(PolyRef, Vector)]) -> Vec<Option<f64>> {
+        path.iter()
+            .map(|(poly_ref, position)| self.get_poly_height(*poly_ref, position).ok())
+            .collect()
+    }
+
+    /// Queries for nearest polygon given a center point, a search area (extents), and a filter
+    /// Errors with EmptyNavMesh if nothing was found because the nav mesh has no loaded tiles
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn find_nearest_poly(
+        &self,
+        center: &Vector,
+        extents: &Vector,
+        filter: &QueryFilter,
+    ) -> DivertResult<(PolyRef, Vector)> {
+        let (nearest_ref, closest_point, _) = self.find_nearest_poly_ex(center, extents, filter)?;
+
+        Ok((nearest_ref, closest_point))
+    }
+
+    /// Queries for nearest polygon like find_nearest_poly, also reporting whether center lies
+    /// over the polygon on the xz-plane, rather than being snapped to it from the side
+    /// The flag is false when no polygon was found within the search area
+    /// Errors with EmptyNavMesh if nothing was found because the nav mesh has no loaded tiles
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn find_nearest_poly_ex(
+        &self,
+        center: &Vector,
+        extents: &Vector,
+        filter: &QueryFilter,
+    ) -> DivertResult<(PolyRef, Vector, bool)> {
+        let nearest = self.query_nearest_poly(center, extents, filter)?;
+        if nearest.0 == PolyRef::default() && self.nav_mesh_is_empty() {
+            return Err(DivertError::EmptyNavMesh());
+        }
+
+        Ok(nearest)
+    }
+
+    /// Runs findNearestPoly without telling an empty nav mesh apart from a miss
+    fn query_nearest_poly(
+        &self,
+        center: &Vector,
+        extents: &Vector,
+        filter: &QueryFilter,
+    ) -> DivertResult<(PolyRef, Vector, bool)> {
+        check_finite(center, "center must be finite")?;
+        check_finite(extents, "extents must be finite")?;
+
+        let mut closest_point = Vector::default();
+        let mut nearest_ref = PolyRef::default();
+        let mut is_over_poly = false;
+
+        let nearest_status = unsafe {
+            dtNavMeshQuery_findNearestPolyEx(
+                self.handle,
+                center,
+                extents,
+                filter.handle,
+                &mut nearest_ref,
+                &mut closest_point,
+                &mut is_over_poly,
+            )
+        };
+
+        if nearest_status.is_failed() {
+            return Err(DivertError::FindNearestPolyFailure(nearest_status));
+        }
+
+        Ok((nearest_ref, closest_point, is_over_poly))
+    }
+
+    /// Queries for nearest polygon given a center point and a filter, using the default extents
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn find_nearest_poly_default(
+        &self,
+        center: &Vector,
+        filter: &QueryFilter,
+    ) -> DivertResult<(PolyRef, Vector)> {
+        self.find_nearest_poly(center, &self.default_extents, filter)
+    }
+
+    /// Checks whether a and b snap to the same polygon within the search area (extents)
+    /// Returns false if either position has no polygon within the search area
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn same_poly(
+        &self,
+        a: &Vector,
+        b: &Vector,
+        extents: &Vector,
+        filter: &QueryFilter,
+    ) -> DivertResult<bool> {
+        let (a_ref, _) = self.find_nearest_poly(a, extents, filter)?;
+        if a_ref == PolyRef::default() {
+            return Ok(false);
+        }
+
+        let (b_ref, _) = self.find_nearest_poly(b, extents, filter)?;
+        Ok(a_ref == b_ref)
+    }
+
+    /// Finds the nearest polygon within the search area (extents) and the closest point on it
+    /// Returns None if no polygon was found within the search area
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn closest_navigable_point(
+        &self,
+        pos: &Vector,
+        extents: &Vector,
+        filter: &QueryFilter,
+    ) -> DivertResult<Option<Vector>> {
+        let (nearest_ref, _) = self.find_nearest_poly(pos, extents, filter)?;
+        if nearest_ref == PolyRef::default() {
+            return Ok(None);
+        }
+
+        let (closest_point, _) = self.closest_point_on_poly(nearest_ref, pos)?;
+        Ok(Some(closest_point))
+    }
+
+    /// Snaps a position to the ground of the nearest polygon within the search area (extents)
+    /// The closest point's height is replaced by the poly surface height, keeping the closest
+    /// point's height if the surface height can not be sampled
+    /// Returns None if no polygon was found within the search area
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn snap_to_navmesh(
+        &self,
+        position: &Vector,
+        extents: &Vector,
+        filter: &QueryFilter,
+    ) -> DivertResult<Option<(PolyRef, Vector)>> {
+        let (nearest_ref, mut closest_point) = self.find_nearest_poly(position, extents, filter)?;
+        if nearest_ref == PolyRef::default() {
+            return Ok(None);
+        }
+
+        if let Ok(height) = self.get_poly_height(nearest_ref, &closest_point) {
+            closest_point.y = height;
+        }
+
+        Ok(Some((nearest_ref, closest_point)))
+    }
+
+    /// Snaps a position to the ground of the nearest polygon like snap_to_navmesh, using the default extents
+    /// Returns None if no polygon was found within the search area
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn snap_to_navmesh_default(
+        &self,
+        position: &Vector,
+        filter: &QueryFilter,
+    ) -> DivertResult<Option<(PolyRef, Vector)>> {
+        self.snap_to_navmesh(position, &self.default_extents, filter)
+    }
+
+    /// Snaps a position onto the nav mesh like snap_to_navmesh, retrying with expanded vertical
+    /// extents according to policy while no poly is found
+    /// Returns None if no polygon was found after the last retry
+    /// Errors if the policy's growth is not greater than 1, or ffi function returns a failed DtStatus
+    pub fn snap(
+        &self,
+        position: &Vector,
+        extents: &Vector,
+        filter: &QueryFilter,
+        policy: &VerticalSnapPolicy,
+    ) -> DivertResult<Option<(PolyRef, Vector)>> {
+        if policy.growth.is_nan() || policy.growth <= 1.0 {
+            return Err(DivertError::InvalidParam("growth must be greater than 1"));
+        }
+        let mut extents = *extents;
+
+        for _ in 0..policy.max_retries {
+            if let Some(snapped) = self.snap_to_navmesh(position, &extents, filter)? {
+                return Ok(Some(snapped));
+            }
+            extents.y *= policy.growth;
+        }
+
+        self.snap_to_navmesh(position, &extents, filter)
+    }
+
+    /// Queries for the nearest polygon like find_nearest_poly, growing the search area (extents)
+    /// by growth, up to max, while no polygon is found
+    /// Returns None if no polygon was found within max
+    /// Errors if growth is not greater than 1, initial isn't finite and positive on every axis,
+    /// max isn't finite or smaller than initial on some axis, or ffi function returns a failed DtStatus
+    /// Errors with EmptyNavMesh if the nav mesh has no loaded tiles
+    pub fn find_nearest_poly_expanding(
+        &self,
+        center: &Vector,
+        initial: &Vector,
+        max: &Vector,
+        growth: f64,
+        filter: &QueryFilter,
+    ) -> DivertResult<Option<(PolyRef, Vector)>> {
+        if growth.is_nan() || growth <= 1.0 {
+            return Err(DivertError::InvalidParam("growth must be greater than 1"));
+        }
+        // A zero axis would never grow to max, searching forever
+        if !initial.is_finite() || !initial.cmpgt(Vector::ZERO).all() {
+            return Err(DivertError::InvalidParam(
+                "initial must be finite and positive on every axis",
+            ));
+        }
+        if !max.is_finite() || !max.cmpge(*initial).all() {
+            return Err(DivertError::InvalidParam(
+                "max must be finite and at least initial on every axis",
+            ));
+        }
+
+        // Checked once up front rather than after every miss
+        if self.nav_mesh_is_empty() {
+            return Err(DivertError::EmptyNavMesh());
+        }
+
+        let mut extents = *initial;
+        loop {
+            let (nearest_ref, closest_point, _) =
+                self.query_nearest_poly(center, &extents, filter)?;
+            if nearest_ref != PolyRef::default() {
+                return Ok(Some((nearest_ref, closest_point)));
+            }
+
+            if extents.cmpge(*max).all() {
+                return Ok(None);
+            }
+            extents = (extents * growth).min(*max);
+        }
+    }
+
+    /// Queries for closest point on poly to a given position
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn closest_point_on_poly(
+        &self,
+        poly_ref: PolyRef,
+        position: &Vector,
+    ) -> DivertResult<(Vector, bool)> {
+        check_finite(position, "position must be finite")?;
+
+        let mut closest_point = Vector::default();
+        let mut position_over_poly = false;
+
+        let nearest_status = unsafe {
+            dtNavMeshQuery_closestPointOnPoly(
+                self.handle,
+                poly_ref,
+                position,
+                &mut closest_point,
+                &mut position_over_poly,
+            )
+        };
+
+        if nearest_status.is_failed() {
+            return Err(DivertError::ClosestPointOnPolyFailure(nearest_status));
+        }
+
+        Ok((closest_point, position_over_poly))
+    }
+
+    /// Queries for closest point on poly to a given position, if the position is over the poly
+    /// Returns None when the position is not over the poly and was clamped to its boundary
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn closest_point_over_poly(
+        &self,
+        poly_ref: PolyRef,
+        position: &Vector,
+    ) -> DivertResult<Option<Vector>> {
+        let (closest_point, position_over_poly) = self.closest_point_on_poly(poly_ref, position)?;
+
+        Ok(position_over_poly.then_some(closest_point))
+    }
+
+    /// Queries for closest point on poly boundary to a given position
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn closest_point_on_poly_boundary(
+        &self,
+        poly_ref: PolyRef,
+        position: &Vector,
+    ) -> DivertResult<Vector> {
+        check_finite(position, "position must be finite")?;
+
+        let mut closest_point = Vector::default();
+
+        let dt_result = unsafe {
+            dtNavMeshQuery_closestPointOnPolyBoundary(
+                self.handle,
+                poly_ref,
+                position,
+                &mut closest_point,
+            )
+        };
+
+        if dt_result.is_failed() {
+            return Err(DivertError::ClosestPointOnPolyBoundaryFailure(dt_result));
+        }
+
+        Ok(closest_point)
+    }
+
+    /// Queries for the closest point on the edges of a poly's detail mesh to a given position
+    /// Returns the squared distance on the xz-plane along with the closest point, whose height
+    /// follows the detail mesh, unlike closest_point_on_poly_boundary on uneven terrain
+    /// With only_boundary, the detail triangles' inner edges are skipped
+    /// Errors if poly_ref is invalid or an off-mesh connection, or ffi function returns a failed DtStatus
+    pub fn closest_point_on_detail_edges(
+        &self,
+        poly_ref: PolyRef,
+        position: &Vector,
+        only_boundary: bool,
+    ) -> DivertResult<(f64, Vector)> {
+        check_finite(position, "position must be finite")?;
+
+        let mut dist_sqr = 0.0;
+        let mut closest_point = Vector::default();
+
+        let dt_result = unsafe {
+            dtNavMeshQuery_closestPointOnDetailEdges(
+                self.handle,
+                poly_ref,
+                position,
+                only_boundary,
+                &mut dist_sqr,
+                &mut closest_point,
+            )
+        };
+
+        if dt_result.is_failed() {
+            return Err(DivertError::ClosestPointOnDetailEdgesFailure(dt_result));
+        }
+
+        Ok((dist_sqr, closest_point))
+    }
+
+    /// Gets the (left, right) end points of the portal edge shared by two adjacent polys
+    /// Off-mesh connections portal through a single point, returned as both left and right
+    /// Errors if the polys aren't linked, or ffi function returns a failed DtStatus
+    pub fn get_portal_points(&self, from: PolyRef, to: PolyRef) -> DivertResult<(Vector, Vector)> {
+        let mut left = Vector::default();
+        let mut right = Vector::default();
+
+        let get_portal_points_status =
+            unsafe { dtNavMeshQuery_getPortalPoints(self.handle, from, to, &mut left, &mut right) };
+        if get_portal_points_status.is_failed() {
+            return Err(DivertError::GetPortalPointsFailure(
+                get_portal_points_status,
+            ));
+        }
+
+        Ok((left, right))
+    }
+
+    /// Gets the mid point of the portal edge shared by two adjacent polys
+    /// Errors if the polys aren't linked, or ffi function returns a failed DtStatus
+    pub fn get_edge_mid_point(&self, from: PolyRef, to: PolyRef) -> DivertResult<Vector> {
+        let (left, right) = self.get_portal_points(from, to)?;
+        Ok(left.midpoint(&right))
+    }
+
+    /// Finds the nearest poly within the search area, and the closest point on its boundary
+    /// Positions over the poly are returned as is, like closest_point_on_poly_boundary
+    /// Returns None if no polygon was found within the search area
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn nearest_boundary_point(
+        &self,
+        center: &Vector,
+        extents: &Vector,
+        filter: &QueryFilter,
+    ) -> DivertResult<Option<Vector>> {
+        let (nearest_ref, _) = self.find_nearest_poly(center, extents, filter)?;
+        if nearest_ref == PolyRef::default() {
+            return Ok(None);
+        }
+
+        self.closest_point_on_poly_boundary(nearest_ref, center)
+            .map(Some)
+    }
+
+    /// Clamps a start and end position onto the boundaries of a corridor's first and last polys
+    /// Returns the clamped (start, end), as used before smoothing along a polygon path
+    /// Errors if path is empty, or ffi function returns a failed DtStatus
+    pub fn clamp_to_corridor(
+        &self,
+        path: &[PolyRef],
+        start: &Vector,
+        end: &Vector,
+    ) -> DivertResult<(Vector, Vector)> {
+        let (first, last) = match (path.first(), path.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Err(DivertError::InvalidParam("path must not be empty")),
+        };
+
+        Ok((
+            self.closest_point_on_poly_boundary(first, start)?,
+            self.closest_point_on_poly_boundary(last, end)?,
+        ))
+    }
+
+    /// Generates a polygon path from one (poly, position) to another (poly, position)
+    /// Uses a user provided PolyRef Vector
+    /// Max Path length is derived from the user provided PolyRef Vec's capacity
+    /// Errors if ffi function returns a failed DtStatus
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self, start_pos, end_pos, filter, path))
+    )]
+    pub fn find_path_inplace(
+        &self,
+        start_ref: PolyRef,
+        end_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+        path: &mut Vec<PolyRef>,
+    ) -> DivertResult<DtStatus> {
+        check_finite(start_pos, "start_pos must be finite")?;
+        check_finite(end_pos, "end_pos must be finite")?;
+
+        let weighted_filter = self.weighted_filter(filter);
+        let filter = weighted_filter.as_deref().unwrap_or(filter);
+
+        let mut path_count = 0;
+        let max_path = buffer_size(path.capacity());
+
+        let find_path_status = unsafe {
+            dtNavMeshQuery_findPath(
+                self.handle,
+                start_ref,
+                end_ref,
+                start_pos,
+                end_pos,
+                filter.handle,
+                path.as_mut_ptr(),
+                &mut path_count,
+                max_path,
+            )
+        };
+
+        log::trace!("FindPathStatus: {:#?}", find_path_status);
+
+        unsafe {
+            path.set_len(result_len(path_count, path.capacity()));
+        }
+
+        if find_path_status.is_failed() {
+            return Err(self.find_path_failure(find_path_status));
+        }
+
+        Ok(find_path_status)
+    }
+
+    /// Flood fills the polys reachable from start_ref through polys passing the filter
+    /// Follows every poly link, including off-mesh connections, breadth first from start_ref,
+    /// which is always included, stopping once max_polys polys were found
+    /// Visits each reachable poly and its links once, so the cost grows with the size of the
+    /// reachable area rather than its distance, like a search without a goal
+    /// Errors if max_polys is not within 1..=1048576, or ffi function returns a failed DtStatus
+    pub fn reachable_polys(
+        &self,
+        start_ref: PolyRef,
+        filter: &QueryFilter,
+        max_polys: i32,
+    ) -> DivertResult<Vec<PolyRef>> {
+        if !(1..=MAX_BUFFER_SIZE).contains(&max_polys) {
+            return Err(DivertError::InvalidParam(
+                "max_polys must be within 1..=1048576",
+            ));
+        }
+        let max_polys = max_polys as usize;
+        let nav_mesh = unsafe { dtNavMeshQuery_getAttachedNavMesh(self.handle) };
+        get_tile_and_poly_by_ref(nav_mesh, start_ref)?;
+
+        let mut reachable = Vec::new();
+        let mut visited = HashSet::from([start_ref]);
+        let mut open = VecDeque::from([start_ref]);
+        while let Some(poly_ref) = open.pop_front() {
+            if reachable.len() >= max_polys {
+                break;
+            }
+            reachable.push(poly_ref);
+
+            for neighbour_ref in linked_polys(nav_mesh, poly_ref)? {
+                if !visited.contains(&neighbour_ref) && self.filter_passes(neighbour_ref, filter)? {
+                    visited.insert(neighbour_ref);
+                    open.push_back(neighbour_ref);
+                }
+            }
+        }
+
+        Ok(reachable)
+    }
+
+    /// Generates a polygon path from one (poly, position) to another (poly, position)
+    /// Writes into a user provided PolyRef slice without allocating, returning the path length
+    /// Max Path length is derived from the slice's length
+    /// Errors if ffi function returns a failed DtStatus
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self, start_pos, end_pos, filter, out))
+    )]
+    pub fn find_path_into(
+        &self,
+        start_ref: PolyRef,
+        end_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+        out: &mut [PolyRef],
+    ) -> DivertResult<usize> {
+        check_finite(start_pos, "start_pos must be finite")?;
+        check_finite(end_pos, "end_pos must be finite")?;
+
+        let weighted_filter = self.weighted_filter(filter);
+        let filter = weighted_filter.as_deref().unwrap_or(filter);
+
+        let mut path_count = 0;
+
+        let find_path_status = unsafe {
+            dtNavMeshQuery_findPath(
+                self.handle,
+                start_ref,
+                end_ref,
+                start_pos,
+                end_pos,
+                filter.handle,
+                out.as_mut_ptr(),
+                &mut path_count,
+                buffer_size(out.len()),
+            )
+        };
+
+        log::trace!("FindPathStatus: {:#?}", find_path_status);
+
+        if find_path_status.is_failed() {
+            return Err(self.find_path_failure(find_path_status));
+        }
+
+        Ok(result_len(path_count, out.len()))
+    }
+
+    /// Generates a polygon path from one (poly, position) to another (poly, position)
+    /// Errors if ffi function returns a failed DtStatus
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self, start_pos, end_pos, filter))
+    )]
+    pub fn find_path(
+        &self,
+        start_ref: PolyRef,
+        end_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+        max_path: i32,
+    ) -> DivertResult<Vec<PolyRef>> {
+        let (path, _) =
+            self.find_path_with_status(start_ref, end_ref, start_pos, end_pos, filter, max_path)?;
+
+        Ok(path)
+    }
+
+    /// Generates a polygon path like find_path, growing the path buffer while it is too small
+    /// Starts with initial_max_path polys and doubles it each time Detour reports
+    /// BUFFER_TOO_SMALL, up to the 1048576 polys buffers are limited to
+    /// Errors if initial_max_path is not positive, or ffi function returns a failed DtStatus
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self, start_pos, end_pos, filter))
+    )]
+    pub fn find_path_growing(
+        &self,
+        start_ref: PolyRef,
+        end_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+        initial_max_path: i32,
+    ) -> DivertResult<Vec<PolyRef>> {
+        grow_while_too_small(initial_max_path, |max_path| {
+            self.find_path_with_status(start_ref, end_ref, start_pos, end_pos, filter, max_path)
+        })
+    }
+
+    /// Generates a polygon path like find_path, reporting partial paths without erroring
+    /// The result is incomplete when Detour reports PARTIAL_RESULT or BUFFER_TOO_SMALL
+    /// Errors if ffi function returns a failed DtStatus
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self, start_pos, end_pos, filter))
+    )]
+    pub fn find_path_detailed(
+        &self,
+        start_ref: PolyRef,
+        end_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+        max_path: i32,
+    ) -> DivertResult<PathResult> {
+        let (path, status) =
+            self.find_path_with_status(start_ref, end_ref, start_pos, end_pos, filter, max_path)?;
+
+        Ok(PathResult {
+            path,
+            complete: !status.intersects(DtStatus::PARTIAL_RESULT | DtStatus::BUFFER_TOO_SMALL),
+            status,
+        })
+    }
+
+    /// Generates a polygon path like find_path, growing the node pool while the search runs out of nodes
+    /// The query is reinitialized with initial_max_nodes, then doubles its node pool up to
+    /// max_nodes_cap each time the search reports OUT_OF_NODES
+    /// A pool already larger than initial_max_nodes is searched as is, as reinit never shrinks it
+    /// Returns the partial path found with the capped node pool if it still runs out of nodes
+    /// The node pool is restored to its size before the call afterwards, rebuilding the
+    /// dtNavMeshQuery if it grew, so config keeps reporting the same max_nodes
+    /// Errors if initial_max_nodes is not within 1..=max_nodes_cap, or ffi function returns a failed DtStatus
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self, start_pos, end_pos, filter))
+    )]
+    #[allow(clippy::too_many_arguments)]
+    pub fn find_path_retry(
+        &mut self,
+        start_ref: PolyRef,
+        end_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+        max_path: i32,
+        initial_max_nodes: i32,
+        max_nodes_cap: i32,
+    ) -> DivertResult<Vec<PolyRef>> {
+        if initial_max_nodes < 1 || initial_max_nodes > max_nodes_cap {
+            return Err(DivertError::InvalidParam(
+                "initial_max_nodes must be within 1..=max_nodes_cap",
+            ));
+        }
+
+        let original_max_nodes = self.get_node_pool_size();
+        let path = self.find_path_growing_node_pool(
+            start_ref,
+            end_ref,
+            start_pos,
+            end_pos,
+            filter,
+            max_path,
+            initial_max_nodes,
+            max_nodes_cap,
+        );
+
+        if self.get_node_pool_size() > original_max_nodes {
+            self.rebuild(original_max_nodes)?;
+        }
+
+        path
+    }
+
+    /// Runs find_path_retry's search, leaving the node pool at the size the path was found with
+    #[allow(clippy::too_many_arguments)]
+    fn find_path_growing_node_pool(
+        &mut self,
+        start_ref: PolyRef,
+        end_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+        max_path: i32,
+        initial_max_nodes: i32,
+        max_nodes_cap: i32,
+    ) -> DivertResult<Vec<PolyRef>> {
+        self.reinit(initial_max_nodes)?;
+        let mut max_nodes = self.get_node_pool_size();
+
+        loop {
+            let (path, find_path_status) = self
+                .find_path_with_status(start_ref, end_ref, start_pos, end_pos, filter, max_path)?;
+
+            if !find_path_status.contains(DtStatus::OUT_OF_NODES) || max_nodes >= max_nodes_cap {
+                return Ok(path);
+            }
+
+            max_nodes = max_nodes.saturating_mul(2).min(max_nodes_cap);
+            log::debug!(
+                "findPath ran out of nodes, retrying with {} nodes",
+                max_nodes
+            );
+            self.reinit(max_nodes)?;
+        }
+    }
+
+    /// Generates a polygon path like find_path, along with the successful status and its detail bits
+    /// PARTIAL_RESULT is set when end_ref can't be reached and the path leads to the closest poly,
+    /// BUFFER_TOO_SMALL when the path was truncated to max_path
+    /// Errors if ffi function returns a failed DtStatus
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self, start_pos, end_pos, filter))
+    )]
+    pub fn find_path_with_status(
+        &self,
+        start_ref: PolyRef,
+        end_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+        max_path: i32,
+    ) -> DivertResult<(Vec<PolyRef>, DtStatus)> {
+        check_finite(start_pos, "start_pos must be finite")?;
+        check_finite(end_pos, "end_pos must be finite")?;
+
+        let weighted_filter = self.weighted_filter(filter);
+        let filter = weighted_filter.as_deref().unwrap_or(filter);
+
+        let mut path_count = 0;
+        let mut path: Vec<PolyRef> = Vec::with_capacity(buffer_capacity(max_path)?);
+
+        let find_path_status = unsafe {
+            dtNavMeshQuery_findPath(
+                self.handle,
+                start_ref,
+                end_ref,
+                start_pos,
+                end_pos,
+                filter.handle,
+                path.as_mut_ptr(),
+                &mut path_count,
+                max_path,
+            )
+        };
+
+        log::trace!("FindPathStatus: {:#?}", find_path_status);
+
+        unsafe {
+            path.set_len(result_len(path_count, path.capacity()));
+        }
+
+        if find_path_status.is_failed() {
+            return Err(self.find_path_failure(find_path_status));
+        }
+
+        Ok((path, find_path_status))
+    }
+
+    /// Checks whether end_ref can be reached from start_ref
+    /// Runs find_path and only reports reachable when the path ends at end_ref,
+    /// a partial path ending elsewhere means the end poly could not be reached
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn is_reachable(
+        &self,
+        start_ref: PolyRef,
+        end_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+        max_path: i32,
+    ) -> DivertResult<bool> {
+        let path = self.find_path(start_ref, end_ref, start_pos, end_pos, filter, max_path)?;
+
+        Ok(path.last() == Some(&end_ref))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    /// Generates a (poly, position) path from on (poly, position) to another (poly, position)
+    /// Uses a user provided DtVector Vec, DtStraightPathFlags Vec, and PolyRef Vec
+    /// Max Path length is derived from the user provided DtVector Vec's capacity
+    /// Errors if ffi function returns a failed DtStatus
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip_all,
+            fields(
+                start_ref = poly_path.first().map(|poly_ref| poly_ref.as_raw()),
+                end_ref = poly_path.last().map(|poly_ref| poly_ref.as_raw()),
+                options = options.bits()
+            )
+        )
+    )]
+    pub fn find_straight_path_inplace(
+        &self,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        poly_path: &[PolyRef],
+        straight_path_points: &mut Vec<DtVector>,
+        straight_path_flags: &mut Vec<DtStraightPathFlags>,
+        straight_path_polys: &mut Vec<PolyRef>,
+        options: StraightPathOptions,
+    ) -> DivertResult<DtStatus> {
+        check_finite(start_pos, "start_pos must be finite")?;
+        check_finite(end_pos, "end_pos must be finite")?;
+
+        let mut straight_path_count = 0;
+        let poly_path_size = input_len(poly_path.len())?;
+        let max_straight_path = buffer_size(
+            straight_path_points
+                .capacity()
+                .min(straight_path_flags.capacity())
+                .min(straight_path_polys.capacity()),
+        );
+
+        let find_path_status = unsafe {
+            dtNavMeshQuery_findStraightPath(
+                self.handle,
+                start_pos,
+                end_pos,
+                poly_path.as_ptr(),
+                poly_path_size,
+                straight_path_points.as_mut_ptr(),
+                straight_path_flags.as_mut_ptr(),
+                straight_path_polys.as_mut_ptr(),
+                &mut straight_path_count,
+                max_straight_path,
+                options,
+            )
+        };
+
+        let path_count = result_len(
+            straight_path_count,
+            straight_path_points
+                .capacity()
+                .min(straight_path_flags.capacity())
+                .min(straight_path_polys.capacity()),
+        );
+        unsafe {
+            straight_path_points.set_len(path_count);
+            straight_path_flags.set_len(path_count);
+            straight_path_polys.set_len(path_count);
+        }
+        flag_coincident_start(straight_path_flags, straight_path_polys, poly_path);
+
+        log::trace!("FindStraightPathStatus: {:#?}", find_path_status);
+        if find_path_status.is_failed() {
+            return Err(DivertError::FindStraightPathFailure(find_path_status));
+        }
+
+        Ok(find_path_status)
+    }
+
+    /// Generates a (poly, position) path from on (poly, position) to another (poly, position)
+    /// Coinciding start and end positions yield a single point flagged both START and END
+    /// Errors if ffi function returns a failed DtStatus
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip_all,
+            fields(
+                start_ref = poly_path.first().map(|poly_ref| poly_ref.as_raw()),
+                end_ref = poly_path.last().map(|poly_ref| poly_ref.as_raw()),
+                options = options.bits()
+            )
+        )
+    )]
+    pub fn find_straight_path(
+        &self,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        poly_path: &[PolyRef],
+        max_path: i32,
+        options: StraightPathOptions,
+    ) -> DivertResult<Vec<(Vector, DtStraightPathFlags, PolyRef)>> {
+        let (path, _) =
+            self.find_straight_path_with_status(start_pos, end_pos, poly_path, max_path, options)?;
+
+        Ok(path)
+    }
+
+    /// Generates a straight path like find_straight_path, growing the buffer while it is too small
+    /// Starts with initial_max_path points and doubles it each time Detour reports
+    /// BUFFER_TOO_SMALL, up to the 1048576 points buffers are limited to
+    /// Errors if initial_max_path is not positive, or ffi function returns a failed DtStatus
+    pub fn find_straight_path_growing(
+        &self,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        poly_path: &[PolyRef],
+        initial_max_path: i32,
+        options: StraightPathOptions,
+    ) -> DivertResult<Vec<(Vector, DtStraightPathFlags, PolyRef)>> {
+        grow_while_too_small(initial_max_path, |max_path| {
+            self.find_straight_path_with_status(start_pos, end_pos, poly_path, max_path, options)
+        })
+    }
+
+    /// Runs findStraightPath, returning the path along with the successful status and its detail bits
+    #[allow(clippy::type_complexity)]
+    fn find_straight_path_with_status(
+        &self,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        poly_path: &[PolyRef],
+        max_path: i32,
+        options: StraightPathOptions,
+    ) -> DivertResult<(Vec<(Vector, DtStraightPathFlags, PolyRef)>, DtStatus)> {
+        check_finite(start_pos, "start_pos must be finite")?;
+        check_finite(end_pos, "end_pos must be finite")?;
+
+        let mut straight_path_count = 0;
+        let capacity = buffer_capacity(max_path)?;
+        let poly_path_size = input_len(poly_path.len())?;
+        let mut straight_path_points: Vec<DtVector> = Vec::with_capacity(capacity);
+        let mut straight_path_flags: Vec<DtStraightPathFlags> = Vec::with_capacity(capacity);
+        let mut straight_path_polys: Vec<PolyRef> = Vec::with_capacity(capacity);
+
+        let find_path_status = unsafe {
+            dtNavMeshQuery_findStraightPath(
+                self.handle,
+                start_pos,
+                end_pos,
+                poly_path.as_ptr(),
+                poly_path_size,
+                straight_path_points.as_mut_ptr(),
+                straight_path_flags.as_mut_ptr(),
+                straight_path_polys.as_mut_ptr(),
+                &mut straight_path_count,
+                max_path,
+                options,
+            )
+        };
+
+        log::trace!("FindStraightPathStatus: {:#?}", find_path_status);
+
+        let path_count = result_len(straight_path_count, capacity);
+
+        unsafe {
+            straight_path_points.set_len(path_count);
+            straight_path_flags.set_len(path_count);
+            straight_path_polys.set_len(path_count);
+        }
+        flag_coincident_start(
+            &mut straight_path_flags,
+            &mut straight_path_polys,
+            poly_path,
+        );
+
+        if find_path_status.is_failed() {
+            return Err(DivertError::FindStraightPathFailure(find_path_status));
+        }
+
+        let path_result = straight_path_points
+            .into_iter()
+            .zip(straight_path_flags)
+            .zip(straight_path_polys)
+            .map(|((pos, flags), poly_ref)| (pos, flags, poly_ref))
+            .collect();
+
+        Ok((path_result, find_path_status))
+    }
+
+    /// Generates a poly path while moving from (poly, position) to a (poly)
+    /// Uses a user provided PolyRef Vec
+    /// Max Path length is derived from the user provided PolyRef Vec's capacity
+    /// Errors if ffi function returns a failed DtStatus
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip(self, start_pos, end_pos, filter, result_pos, visited)
+        )
+    )]
+    pub fn move_along_surface_inplace(
+        &self,
+        start_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+        result_pos: &mut Vector,
+        visited: &mut Vec<PolyRef>,
+    ) -> DivertResult<DtStatus> {
+        check_finite(start_pos, "start_pos must be finite")?;
+        check_finite(end_pos, "end_pos must be finite")?;
+
+        let mut visited_count = 0;
+        let max_visited_size = buffer_size(visited.capacity());
+
+        let move_along_surface_result = unsafe {
+            dtNavMeshQuery_moveAlongSurface(
+                self.handle,
+                start_ref,
+                start_pos,
+                end_pos,
+                filter.handle,
+                result_pos,
+                visited.as_mut_ptr(),
+                &mut visited_count,
+                max_visited_size,
+            )
+        };
+
+        unsafe {
+            visited.set_len(result_len(visited_count, visited.capacity()));
+        }
+
+        if move_along_surface_result.is_failed() {
+            return Err(DivertError::MoveAlongSurfaceFailure(
+                move_along_surface_result,
+            ));
+        }
+
+        Ok(move_along_surface_result)
+    }
+
+    /// Generates a poly path while moving from (poly, position) to a (poly)
+    /// Errors if ffi function returns a failed DtStatus
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self, start_pos, end_pos, filter))
+    )]
+    pub fn move_along_surface(
+        &self,
+        start_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+        max_visit: i32,
+    ) -> DivertResult<(Vector, Vec<PolyRef>)> {
+        check_finite(start_pos, "start_pos must be finite")?;
+        check_finite(end_pos, "end_pos must be finite")?;
+
+        let mut visited_count = 0;
+        let mut visited: Vec<PolyRef> = Vec::with_capacity(buffer_capacity(max_visit)?);
+        let mut result_pos = Vector::default();
+
+        let move_along_surface_result = unsafe {
+            dtNavMeshQuery_moveAlongSurface(
+                self.handle,
+                start_ref,
+                start_pos,
+                end_pos,
+                filter.handle,
+                &mut result_pos,
+                visited.as_mut_ptr(),
+                &mut visited_count,
+                max_visit,
+            )
+        };
+
+        unsafe {
+            visited.set_len(result_len(visited_count, visited.capacity()));
+        }
+
+        if move_along_surface_result.is_failed() {
+            return Err(DivertError::MoveAlongSurfaceFailure(
+                move_along_surface_result,
+            ));
+        }
+
+        Ok((result_pos, visited))
+    }
+
+    /// Moves along the surface like move_along_surface, halting at the first visited poly an
+    /// off-mesh connection passing the filter can be entered from
+    /// start_ref is skipped, so an agent that just landed from a connection can move on
+    /// When halted, returns the connection's start position, the visited polys up to the halting
+    /// poly, and the connection's poly, so the caller can play its jump or ladder animation
+    /// Otherwise returns move_along_surface's result with no connection
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn move_along_surface_until_offmesh(
+        &self,
+        start_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+        max_visit: i32,
+    ) -> DivertResult<(Vector, Vec<PolyRef>, Option<PolyRef>)> {
+        let (result_pos, mut visited) =
+            self.move_along_surface(start_ref, start_pos, end_pos, filter, max_visit)?;
+
+        let nav_mesh = unsafe { dtNavMeshQuery_getAttachedNavMesh(self.handle) };
+        for index in 1..visited.len() {
+            let poly_ref = visited[index];
+
+            for off_mesh_ref in linked_off_mesh_connections(nav_mesh, poly_ref)? {
+                if !self.filter_passes(off_mesh_ref, filter)? {
+                    continue;
+                }
+
+                let mut connection_start = Vector::default();
+                let mut connection_end = Vector::default();
+                let end_points_status = unsafe {
+                    dtNavMesh_getOffMeshConnectionPolyEndPoints(
+                        nav_mesh,
+                        poly_ref,
+                        off_mesh_ref,
+                        &mut connection_start,
+                        &mut connection_end,
+                    )
+                };
+                if end_points_status.is_failed() {
+                    return Err(DivertError::GetOffMeshConnectionPolyEndPointsFailure(
+                        end_points_status,
+                    ));
+                }
+
+                visited.truncate(index + 1);
+                return Ok((connection_start, visited, Some(off_mesh_ref)));
+            }
+        }
+
+        Ok((result_pos, visited, None))
+    }
+
+    /// Casts a walkability ray along the surface from (poly, position) toward end_pos
+    /// Returns the hit parameter t along the ray, the normal of the wall hit, and the visited polys
+    /// t is Detour's FLT_MAX (f32::MAX) with a zero normal if the ray reached end_pos without hitting a wall
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn raycast(
+        &self,
+        start_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+        max_path: i32,
+    ) -> DivertResult<(f64, Vector, Vec<PolyRef>)> {
+        check_finite(start_pos, "start_pos must be finite")?;
+        check_finite(end_pos, "end_pos must be finite")?;
+
+        let mut t = 0.0;
+        let mut hit_normal = Vector::default();
+        let mut path_count = 0;
+        let mut path: Vec<PolyRef> = Vec::with_capacity(buffer_capacity(max_path)?);
+
+        let raycast_status = unsafe {
+            dtNavMeshQuery_raycast(
+                self.handle,
+                start_ref,
+                start_pos,
+                end_pos,
+                filter.handle,
+                &mut t,
+                &mut hit_normal,
+                path.as_mut_ptr(),
+                &mut path_count,
+                max_path,
+            )
+        };
+
+        unsafe {
+            path.set_len(result_len(path_count, path.capacity()));
+        }
+
+        if raycast_status.is_failed() {
+            return Err(DivertError::RaycastFailure(raycast_status));
+        }
+
+        Ok((t, hit_normal, path))
+    }
+
+    /// Moves from (poly, position) toward end_pos in a straight line, stopping at the first wall
+    /// Cheaper than move_along_surface, but doesn't slide along walls
+    /// Returns the furthest reachable position, at the hit parameter clamped to 1.0, and the visited polys
+    /// The returned height is interpolated along the ray rather than sampled from the surface
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn raycast_move(
+        &self,
+        start_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+        max_path: i32,
+    ) -> DivertResult<(Vector, Vec<PolyRef>)> {
+        let (t, _, path) = self.raycast(start_ref, start_pos, end_pos, filter, max_path)?;
+
+        Ok((start_pos.lerp(*end_pos, t.min(1.0)), path))
+    }
+
+    /// Checks whether end_pos is visible from (poly, position) across the nav mesh surface
+    /// True only if a raycast toward end_pos hits no wall before reaching it
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn has_line_of_sight(
+        &self,
+        start_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &QueryFilter,
+    ) -> DivertResult<bool> {
+        let (t, _, _) = self.raycast(start_ref, start_pos, end_pos, filter, 0)?;
+
+        Ok(t >= 1.0)
+    }
+}
+
+/// Handles freeing the inner dtNavMeshQuery
+impl<'a> Drop for NavMeshQuery<'a> {
+    /// Frees dtNavMeshQuery resources with dtFreeNavMeshQuery
+    fn drop(&mut self) {
+        unsafe { dtNavMeshQuery_free(self.handle) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        create_nav_mesh_data, fixtures, DivertError, DtStatus, DtStraightPathFlags, NavMesh,
+        NavMeshCreateParams, NavMeshParams, NavMeshQuery, NavMeshQueryConfig,
+        OffMeshConnectionDirection, PolyRef, QueryFilter, StraightPathOptions, TileCoord,
+        TileDataOwnership, TileFlags, TileRef, Vector, VerticalSnapPolicy, DEFAULT_EXTENTS,
+        DT_MAX_AREAS,
+    };
+
+    #[test]
+    fn test_nav_mesh() {
+        let nav_mesh_params = NavMeshParams {
+            origin: [0.0, 0.0, 0.0],
+            tile_width: 32.0,
+            tile_height: 32.0,
+            max_polys: 1000,
+            max_tiles: 1,
+        };
+
+        let nav_mesh = NavMesh::new(&nav_mesh_params);
+        assert!(nav_mesh.is_ok());
+
+        let _nav_mesh = nav_mesh.unwrap();
+    }
+
+    #[test]
+    fn test_nav_mesh_tiles() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(2, 2, 4)).unwrap();
+        assert_eq!(nav_mesh.tiles().count(), 0);
+
+        let first_tile = nav_mesh
+            .add_tile(fixtures::create_tile_data(0, 0, &["..", ".."]))
+            .unwrap();
+        let second_tile = nav_mesh
+            .add_tile(fixtures::create_tile_data(1, 0, &["..", ".."]))
+            .unwrap();
+
+        let tiles: Vec<_> = nav_mesh.tiles().collect();
+        assert_eq!(tiles.len(), 2);
+        assert!(tiles.contains(&first_tile));
+        assert!(tiles.contains(&second_tile));
+    }
+
+    #[test]
+    fn test_nav_mesh_get_tile_at() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(2, 2, 4)).unwrap();
+        let tile_ref = nav_mesh
+            .add_tile(fixtures::create_tile_data(1, 0, &["..", ".."]))
+            .unwrap();
+
+        assert_eq!(
+            nav_mesh.get_tile_at(TileCoord::new(1, 0, 0)).unwrap(),
+            Some(tile_ref)
+        );
+        assert_eq!(nav_mesh.get_tile_at(TileCoord::new(0, 0, 0)).unwrap(), None);
+        assert_eq!(nav_mesh.get_tile_at(TileCoord::new(1, 0, 1)).unwrap(), None);
+        assert!(nav_mesh.get_tile_at(TileCoord::new(1, 0, -1)).is_err());
+
+        let coord = nav_mesh.calc_tile_loc(&fixtures::cell_center(3, 1));
+        assert_eq!(coord, TileCoord::new(1, 0, 0));
+        assert_eq!(nav_mesh.get_tile_at(coord).unwrap(), Some(tile_ref));
+    }
+
+    #[test]
+    fn test_tile_coord_pack() {
+        for coord in [
+            TileCoord::new(0, 0, 0),
+            TileCoord::new(3, 7, 1),
+            TileCoord::new(-1, -2, 0),
+            TileCoord::new(-8388608, 8388607, 65535),
+        ] {
+            assert_eq!(TileCoord::unpack(coord.pack().unwrap()), coord);
+        }
+
+        assert_ne!(
+            TileCoord::new(1, 2, 0).pack().unwrap(),
+            TileCoord::new(2, 1, 0).pack().unwrap()
+        );
+        assert!(TileCoord::new(8388608, 0, 0).pack().is_err());
+        assert!(TileCoord::new(0, 0, -1).pack().is_err());
+    }
+
+    #[test]
+    fn test_nav_mesh_add_tile_borrowed() {
+        let mut tile_data = fixtures::create_tile_data(0, 0, &["..."]);
+        let original_data = tile_data.clone();
+
+        {
+            let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(3, 1, 1)).unwrap();
+            let tile_ref = nav_mesh
+                .add_tile_with_ownership(TileDataOwnership::BorrowedByCaller(&mut tile_data))
+                .unwrap();
+            assert_eq!(nav_mesh.tiles().collect::<Vec<_>>(), vec![tile_ref]);
+
+            let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+            let filter = QueryFilter::new().unwrap();
+            let (poly_ref, _) = nav_mesh_query
+                .find_nearest_poly(
+                    &fixtures::cell_center(1, 0),
+                    &Vector::new(1.0, 2.0, 1.0),
+                    &filter,
+                )
+                .unwrap();
+            assert_ne!(poly_ref, PolyRef::default());
+        }
+
+        // The data survives the NavMesh and can be handed to another one
+        assert_eq!(tile_data.len(), original_data.len());
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(3, 1, 1)).unwrap();
+        let tile_ref = nav_mesh
+            .add_tile_with_ownership(TileDataOwnership::BorrowedByCaller(&mut tile_data))
+            .unwrap();
+        nav_mesh.remove_tile(tile_ref).unwrap();
+    }
+
+    #[test]
+    fn test_nav_mesh_add_tile_with_flags() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(2, 1, 2)).unwrap();
+        let freed_ref = nav_mesh
+            .add_tile_with(
+                fixtures::create_tile_data(0, 0, &[".."]),
+                TileFlags::FREE_DATA,
+                TileRef::default(),
+            )
+            .unwrap();
+        let retained_ref = nav_mesh
+            .add_tile_with(
+                fixtures::create_tile_data(1, 0, &[".."]),
+                TileFlags::empty(),
+                TileRef::default(),
+            )
+            .unwrap();
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(3, 0);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+        let path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 8)
+            .unwrap();
+        assert_eq!(path.len(), 4);
+        drop(nav_mesh_query);
+
+        nav_mesh.remove_tile(freed_ref).unwrap();
+        nav_mesh.remove_tile(retained_ref).unwrap();
+
+        // last_ref restores the tile under the same ref
+        let restored_ref = nav_mesh
+            .add_tile_with(
+                fixtures::create_tile_data(1, 0, &[".."]),
+                TileFlags::empty(),
+                retained_ref,
+            )
+            .unwrap();
+        assert_eq!(restored_ref, retained_ref);
+
+        // Failed adds don't retain the data
+        assert!(matches!(
+            nav_mesh.add_tile_with(
+                fixtures::create_tile_data(1, 0, &[".."]),
+                TileFlags::empty(),
+                TileRef::default(),
+            ),
+            Err(DivertError::AlreadyOccupied())
+        ));
+        assert_eq!(nav_mesh.tiles().collect::<Vec<_>>(), vec![restored_ref]);
+    }
+
+    #[test]
+    fn test_nav_mesh_add_tile_transferred() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(3, 1, 1)).unwrap();
+        let tile_ref = nav_mesh
+            .add_tile_with_ownership(TileDataOwnership::TransferToDetour(
+                fixtures::create_tile_data(0, 0, &["..."]),
+            ))
+            .unwrap();
+        assert_eq!(nav_mesh.tiles().collect::<Vec<_>>(), vec![tile_ref]);
+        nav_mesh.remove_tile(tile_ref).unwrap();
+    }
+
+    #[test]
+    fn test_find_path_with_status() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["....#."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+        let find_poly = |x| {
+            let position = fixtures::cell_center(x, 0);
+            let (poly_ref, _) = nav_mesh_query
+                .find_nearest_poly(&position, &extents, &filter)
+                .unwrap();
+            (poly_ref, position)
+        };
+        let (start_ref, start_pos) = find_poly(0);
+
+        let (end_ref, end_pos) = find_poly(3);
+        let (path, status) = nav_mesh_query
+            .find_path_with_status(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+            .unwrap();
+        assert_eq!(path.len(), 4);
+        assert!(status.is_success());
+        assert!(!status.intersects(DtStatus::PARTIAL_RESULT | DtStatus::BUFFER_TOO_SMALL));
+
+        let (path, status) = nav_mesh_query
+            .find_path_with_status(start_ref, end_ref, &start_pos, &end_pos, &filter, 2)
+            .unwrap();
+        assert_eq!(path.len(), 2);
+        assert!(status.contains(DtStatus::BUFFER_TOO_SMALL));
+
+        // The cell past the blocked one is unreachable, the path leads to the closest poly
+        let (island_ref, island_pos) = find_poly(5);
+        let (path, status) = nav_mesh_query
+            .find_path_with_status(start_ref, island_ref, &start_pos, &island_pos, &filter, 16)
+            .unwrap();
+        assert_eq!(path.last(), Some(&end_ref));
+        assert!(status.contains(DtStatus::PARTIAL_RESULT));
+    }
+
+    #[test]
+    fn test_nav_mesh_new_single_tile() {
+        let nav_mesh =
+            NavMesh::new_single_tile(fixtures::create_tile_data(0, 0, &["...", ".w.", "..."]))
+                .unwrap();
+        assert_eq!(nav_mesh.tiles().count(), 1);
+
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let start_pos = fixtures::cell_center(0, 2);
+        let end_pos = fixtures::cell_center(2, 0);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+        let path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+            .unwrap();
+        assert_eq!(path.first(), Some(&start_ref));
+        assert_eq!(path.last(), Some(&end_ref));
+
+        assert!(matches!(
+            NavMesh::new_single_tile(vec![0; 16]),
+            Err(DivertError::Failure(_))
+        ));
+    }
+
+    #[test]
+    fn test_nav_mesh_remove_tile() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(1, 1, 2)).unwrap();
+        let tile_ref = nav_mesh
+            .add_tile(fixtures::create_tile_data(0, 0, &["."]))
+            .unwrap();
+
+        nav_mesh.remove_tile(tile_ref).unwrap();
+        assert_eq!(nav_mesh.tiles().count(), 0);
+        assert!(matches!(
+            nav_mesh.remove_tile(tile_ref),
+            Err(DivertError::RemoveTileFailure(_))
+        ));
+
+        nav_mesh
+            .add_tile(fixtures::create_tile_data(0, 0, &["."]))
+            .unwrap();
+        assert_eq!(nav_mesh.tiles().count(), 1);
+    }
+
+    #[test]
+    fn test_nav_mesh_tile_data() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(2, 2, 2)).unwrap();
+        let tile_data = fixtures::create_tile_data(0, 0, &["..", ".w"]);
+        let tile_ref = nav_mesh.add_tile(tile_data.clone()).unwrap();
+
+        let data = nav_mesh.tile_data(tile_ref).unwrap();
+        assert_eq!(data.len(), tile_data.len());
+
+        nav_mesh.remove_tile(tile_ref).unwrap();
+        assert!(matches!(
+            nav_mesh.tile_data(tile_ref),
+            Err(DivertError::InvalidParam(_))
+        ));
+    }
+
+    #[test]
+    fn test_nav_mesh_cross_tile_path_in_either_order() {
+        for tile_order in [[0, 1], [1, 0]] {
+            let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(2, 2, 4)).unwrap();
+            for tile_x in tile_order {
+                nav_mesh
+                    .add_tile(fixtures::create_tile_data(tile_x, 0, &["..", ".."]))
+                    .unwrap();
+            }
+
+            let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+            let filter = QueryFilter::new().unwrap();
+
+            let start_pos = fixtures::cell_center(0, 1);
+            let end_pos = fixtures::cell_center(3, 1);
+            let (start_ref, end_ref) =
+                fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+
+            let path = nav_mesh_query
+                .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+                .unwrap();
+            assert_eq!(path.first(), Some(&start_ref));
+            assert_eq!(path.last(), Some(&end_ref));
+        }
+    }
+
+    #[test]
+    fn test_nav_mesh_world_bounds() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(2, 2, 4)).unwrap();
+        assert_eq!(nav_mesh.world_bounds(), None);
+
+        let first = nav_mesh
+            .add_tile(fixtures::create_tile_data(0, 0, &["..", ".."]))
+            .unwrap();
+        let second = nav_mesh
+            .add_tile(fixtures::create_tile_data(1, 0, &["..", ".."]))
+            .unwrap();
+
+        let first_header = nav_mesh.get_tile_header(first).unwrap();
+        let second_header = nav_mesh.get_tile_header(second).unwrap();
+        assert_eq!(
+            nav_mesh.world_bounds(),
+            Some((first_header.bmin(), second_header.bmax()))
+        );
+
+        let tile_width = 2.0 * fixtures::CELL_SIZE as f64;
+        let (min, max) = nav_mesh.world_bounds().unwrap();
+        assert_eq!(max.x - min.x, 2.0 * tile_width);
+        assert_eq!(max.z - min.z, tile_width);
+    }
+
+    #[test]
+    fn test_nav_mesh_world_bounds_single_tile() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(3, 2, 1)).unwrap();
+        nav_mesh
+            .add_tile(fixtures::create_tile_data(0, 0, &["...", "..."]))
+            .unwrap();
+
+        let cell_size = fixtures::CELL_SIZE as f64;
+        assert_eq!(
+            nav_mesh.world_bounds(),
+            Some((
+                Vector::new(0.0, -1.0, 0.0),
+                Vector::new(3.0 * cell_size, 1.0, 2.0 * cell_size)
+            ))
+        );
+        assert_eq!(nav_mesh.compute_bounds(), nav_mesh.world_bounds());
+    }
+
+    #[test]
+    fn test_tile_header_accessors() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(2, 2, 4)).unwrap();
+        let tile_ref = nav_mesh
+            .add_tile(fixtures::create_tile_data(1, 0, &["..", ".#"]))
+            .unwrap();
+
+        let header = nav_mesh.get_tile_header(tile_ref).unwrap();
+        assert_eq!((header.x(), header.y(), header.layer()), (1, 0, 0));
+        assert_eq!(header.user_id(), 0);
+        assert_eq!(header.poly_count(), 3);
+        assert_eq!(header.vert_count(), 9);
+        assert_eq!(header.detail_mesh_count(), 3);
+        assert!(header.bv_node_count() > 0);
+        assert_eq!(header.off_mesh_con_count(), 0);
+        assert_eq!(header.off_mesh_base(), header.poly_count());
+        assert_eq!(header.walkable_height(), 2.0);
+        assert_eq!(header.walkable_radius(), 0.5);
+        assert_eq!(header.walkable_climb(), 1.0);
+
+        let tile_width = 2.0 * fixtures::CELL_SIZE as f64;
+        assert_eq!(header.bmin().x, tile_width);
+        assert_eq!(header.bmax().x, 2.0 * tile_width);
+    }
+
+    #[test]
+    fn test_nav_mesh_memory_usage() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(2, 2, 4)).unwrap();
+        let empty_usage = nav_mesh.memory_usage();
+        assert!(empty_usage > 0);
+
+        let tile_data = fixtures::create_tile_data(0, 0, &["..", ".w"]);
+        let tile_len = tile_data.len();
+        let tile_ref = nav_mesh.add_tile(tile_data).unwrap();
+        assert!(nav_mesh.memory_usage() >= empty_usage + tile_len);
+
+        nav_mesh.remove_tile(tile_ref).unwrap();
+        assert_eq!(nav_mesh.memory_usage(), empty_usage);
+    }
+
+    #[test]
+    fn test_nav_mesh_replace_tile() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(3, 1, 4)).unwrap();
+        let coord = TileCoord::new(0, 0, 0);
+        let tile_ref = nav_mesh
+            .replace_tile(0, 0, 0, fixtures::create_tile_data(0, 0, &["..w"]), false)
+            .unwrap();
+        let neighbour_ref = nav_mesh
+            .add_tile(fixtures::create_tile_data(1, 0, &["..."]))
+            .unwrap();
+
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(5, 0);
+        let (start_ref, end_ref, path) = {
+            let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+            let filter = QueryFilter::new().unwrap();
+            let (start_ref, end_ref) =
+                fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+            let path = nav_mesh_query
+                .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+                .unwrap();
+            (start_ref, end_ref, path)
+        };
+        assert_eq!(path.len(), 6);
+
+        let replaced_ref = nav_mesh
+            .replace_tile(0, 0, 0, fixtures::create_tile_data(0, 0, &["..."]), false)
+            .unwrap();
+        assert_eq!(replaced_ref, tile_ref);
+        assert_eq!(nav_mesh.tiles().count(), 2);
+        assert_eq!(
+            nav_mesh.get_poly_area(path[2]).unwrap(),
+            fixtures::GROUND_AREA
+        );
+
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        assert_eq!(
+            nav_mesh_query
+                .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+                .unwrap(),
+            path
+        );
+        drop(nav_mesh_query);
+
+        assert!(matches!(
+            nav_mesh.replace_tile(0, 0, 0, fixtures::create_tile_data(2, 0, &["..."]), true),
+            Err(DivertError::InvalidParam(_))
+        ));
+
+        let mut corrupt_data = fixtures::create_tile_data(0, 0, &["..."]);
+        corrupt_data[..4].copy_from_slice(&[0; 4]);
+        assert!(nav_mesh
+            .replace_tile(0, 0, 0, corrupt_data.clone(), true)
+            .is_err());
+        assert_eq!(nav_mesh.get_tile_at(coord).unwrap(), Some(tile_ref));
+        assert_eq!(
+            nav_mesh.get_tile_at(TileCoord::new(1, 0, 0)).unwrap(),
+            Some(neighbour_ref)
+        );
+
+        assert!(nav_mesh.replace_tile(0, 0, 0, corrupt_data, false).is_err());
+        assert_eq!(nav_mesh.get_tile_at(coord).unwrap(), None);
+        assert_eq!(nav_mesh.tiles().count(), 1);
+    }
+
+    #[test]
+    fn test_nav_mesh_dirty_tiles() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(2, 2, 4)).unwrap();
+        let first = nav_mesh
+            .add_tile(fixtures::create_tile_data(0, 0, &["..", ".."]))
+            .unwrap();
+        let second = nav_mesh
+            .add_tile(fixtures::create_tile_data(1, 0, &["..", ".."]))
+            .unwrap();
+        assert_eq!(nav_mesh.dirty_tiles().len(), 2);
+
+        nav_mesh.clear_dirty();
+        assert!(nav_mesh.dirty_tiles().is_empty());
 
-        let nearest_status = unsafe {
-            dtNavMeshQuery_closestPointOnPoly(
-                self.handle,
-                poly_ref,
-                position,
-                &mut closest_point,
-                &mut position_over_poly,
-            )
+        let poly_ref = {
+            let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+            let filter = QueryFilter::new().unwrap();
+            let (poly_ref, _) = nav_mesh_query
+                .find_nearest_poly(
+                    &fixtures::cell_center(3, 1),
+                    &Vector::new(1.0, 2.0, 1.0),
+                    &filter,
+                )
+                .unwrap();
+            poly_ref
         };
+        nav_mesh
+            .set_poly_flags(poly_ref, fixtures::WATER_FLAG)
+            .unwrap();
+        nav_mesh.set_poly_area(poly_ref, 3).unwrap();
+        assert_eq!(
+            nav_mesh.get_poly_flags(poly_ref).unwrap(),
+            fixtures::WATER_FLAG
+        );
+        assert_eq!(nav_mesh.get_poly_area(poly_ref).unwrap(), 3);
+        assert_eq!(nav_mesh.dirty_tiles(), vec![second]);
 
-        if nearest_status.is_failed() {
-            return Err(DivertError::ClosestPointOnPolyFailure(nearest_status));
-        }
+        nav_mesh.clear_dirty();
+        nav_mesh.remove_tile(first).unwrap();
+        assert_eq!(nav_mesh.dirty_tiles(), vec![first]);
 
-        Ok((closest_point, position_over_poly))
+        assert!(matches!(
+            nav_mesh.set_poly_flags(PolyRef::default(), 0),
+            Err(DivertError::SetPolyFlagsFailure(_))
+        ));
+        assert_eq!(nav_mesh.dirty_tiles(), vec![first]);
     }
 
-    /// Queries for closest point on poly boundary to a given position
-    /// Errors if ffi function returns a failed DtStatus
-    pub fn closest_point_on_poly_boundary(
-        &self,
-        poly_ref: PolyRef,
-        position: &Vector,
-    ) -> DivertResult<Vector> {
-        let mut closest_point = Vector::default();
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_nav_mesh_tile_bytes_accounting() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(1, 1, 4)).unwrap();
+        let first = nav_mesh
+            .add_tile(fixtures::create_tile_data(0, 0, &["."]))
+            .unwrap();
+        nav_mesh
+            .add_tile(fixtures::create_tile_data(1, 0, &["."]))
+            .unwrap();
+        assert!(nav_mesh
+            .add_tile(fixtures::create_tile_data(1, 0, &["."]))
+            .is_err());
+        assert!(nav_mesh.tile_bytes > 0);
+        assert_eq!(nav_mesh.tile_bytes, nav_mesh.live_tile_bytes());
 
-        let dt_result = unsafe {
-            dtNavMeshQuery_closestPointOnPolyBoundary(
-                self.handle,
-                poly_ref,
-                position,
-                &mut closest_point,
+        nav_mesh.remove_tile(first).unwrap();
+        assert_eq!(nav_mesh.tile_bytes, nav_mesh.live_tile_bytes());
+
+        nav_mesh
+            .add_tile(fixtures::create_tile_data(2, 0, &["."]))
+            .unwrap();
+        assert_eq!(nav_mesh.tile_bytes, nav_mesh.live_tile_bytes());
+    }
+
+    #[test]
+    fn test_nav_mesh_quantize_point() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...", "..."]);
+        let tile_ref = nav_mesh
+            .get_tile_at(TileCoord::new(0, 0, 0))
+            .unwrap()
+            .unwrap();
+        let header = nav_mesh.get_tile_header(tile_ref).unwrap();
+
+        let position = Vector::new(5.3, 0.2, 2.7);
+        let quantized = nav_mesh.quantize_point(tile_ref, &position).unwrap();
+
+        let quant_factor = header.bv_quant_factor();
+        let dequantized =
+            Vector::from_array(quantized.map(f64::from)) / quant_factor + header.bmin();
+        let error = position - dequantized;
+        assert!(error.min_element() >= 0.0);
+        assert!(error.max_element() < 1.0 / quant_factor);
+
+        assert!(matches!(
+            nav_mesh.quantize_point(TileRef::default(), &position),
+            Err(DivertError::InvalidParam(_))
+        ));
+    }
+
+    #[test]
+    fn test_nav_mesh_add_tile_full_or_occupied() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(1, 1, 2)).unwrap();
+        nav_mesh
+            .add_tile(fixtures::create_tile_data(0, 0, &["."]))
+            .unwrap();
+
+        assert!(matches!(
+            nav_mesh.add_tile(fixtures::create_tile_data(0, 0, &["."])),
+            Err(DivertError::AlreadyOccupied())
+        ));
+
+        nav_mesh
+            .add_tile(fixtures::create_tile_data(1, 0, &["."]))
+            .unwrap();
+
+        assert!(matches!(
+            nav_mesh.add_tile(fixtures::create_tile_data(2, 0, &["."])),
+            Err(DivertError::NavMeshFull())
+        ));
+    }
+
+    #[test]
+    fn test_nav_mesh_poly_tile_coords() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(2, 2, 4)).unwrap();
+        nav_mesh
+            .add_tile(fixtures::create_tile_data(0, 0, &["..", ".."]))
+            .unwrap();
+        nav_mesh
+            .add_tile(fixtures::create_tile_data(1, 1, &["..", ".."]))
+            .unwrap();
+
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let (poly_ref, _) = nav_mesh_query
+            .find_nearest_poly(
+                &fixtures::cell_center(3, 2),
+                &Vector::new(1.0, 2.0, 1.0),
+                &filter,
+            )
+            .unwrap();
+
+        assert_eq!(nav_mesh.poly_tile_coords(poly_ref).unwrap(), (1, 1));
+        assert!(matches!(
+            nav_mesh.poly_tile_coords(PolyRef::default()),
+            Err(DivertError::GetTileAndPolyByRefFailure(_))
+        ));
+    }
+
+    #[test]
+    fn test_nav_mesh_get_poly_vertices() {
+        let tile_data = create_nav_mesh_data(&NavMeshCreateParams {
+            verts: &[[0, 1, 0], [0, 1, 4], [4, 1, 0]],
+            polys: &[0, 1, 2, 0xffff, 0xffff, 0xffff],
+            poly_flags: &[fixtures::GROUND_FLAG],
+            poly_areas: &[fixtures::GROUND_AREA],
+            nvp: 3,
+            b_min: [0.0, -1.0, 0.0],
+            b_max: [4.0, 1.0, 4.0],
+            walkable_height: 2.0,
+            walkable_radius: 0.5,
+            walkable_climb: 1.0,
+            cs: 1.0,
+            ch: 1.0,
+            build_bv_tree: true,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(1, 1, 1)).unwrap();
+        nav_mesh.add_tile(tile_data).unwrap();
+
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let (poly_ref, _) = nav_mesh_query
+            .find_nearest_poly(
+                &Vector::new(1.0, 0.0, 1.0),
+                &Vector::new(1.0, 2.0, 1.0),
+                &filter,
             )
+            .unwrap();
+
+        assert_eq!(
+            nav_mesh.get_poly_vertices(poly_ref).unwrap(),
+            vec![
+                Vector::new(0.0, 0.0, 0.0),
+                Vector::new(0.0, 0.0, 4.0),
+                Vector::new(4.0, 0.0, 0.0),
+            ]
+        );
+        assert!(matches!(
+            nav_mesh.get_poly_vertices(PolyRef::default()),
+            Err(DivertError::GetTileAndPolyByRefFailure(_))
+        ));
+    }
+
+    #[test]
+    fn test_query_empty_nav_mesh() {
+        let nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(1, 1, 4)).unwrap();
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let position = fixtures::cell_center(0, 0);
+
+        assert!(matches!(
+            nav_mesh_query.find_nearest_poly(&position, &Vector::new(1.0, 2.0, 1.0), &filter),
+            Err(DivertError::EmptyNavMesh())
+        ));
+        assert!(matches!(
+            nav_mesh_query.find_path(
+                PolyRef::from_raw(1),
+                PolyRef::from_raw(1),
+                &position,
+                &position,
+                &filter,
+                16
+            ),
+            Err(DivertError::EmptyNavMesh())
+        ));
+    }
+
+    #[test]
+    fn test_nav_mesh_query() {
+        let nav_mesh_params = NavMeshParams {
+            origin: [0.0, 0.0, 0.0],
+            tile_width: 32.0,
+            tile_height: 32.0,
+            max_polys: 1000,
+            max_tiles: 1,
         };
 
-        if dt_result.is_failed() {
-            return Err(DivertError::ClosestPointOnPolyBoundaryFailure(dt_result));
-        }
+        let nav_mesh = NavMesh::new(&nav_mesh_params).unwrap();
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100);
+        assert!(nav_mesh_query.is_ok());
+    }
 
-        Ok(closest_point)
+    #[test]
+    fn test_query_filter() {
+        let filter = QueryFilter::new();
+        assert!(filter.is_ok());
+
+        let mut filter = filter.unwrap();
+
+        filter.set_include_flags(1);
+        assert_eq!(filter.get_include_flags(), 1);
+
+        filter.set_exclude_flags(1);
+        assert_eq!(filter.get_exclude_flags(), 1);
     }
 
-    /// Generates a polygon path from one (poly, position) to another (poly, position)
-    /// Uses a user provided PolyRef Vector
-    /// Max Path length is derived from the user provided PolyRef Vec's capacity
-    /// Errors if ffi function returns a failed DtStatus
-    pub fn find_path_inplace(
-        &self,
-        start_ref: PolyRef,
-        end_ref: PolyRef,
-        start_pos: &Vector,
-        end_pos: &Vector,
-        filter: &QueryFilter,
-        path: &mut Vec<PolyRef>,
-    ) -> DivertResult<DtStatus> {
-        let mut path_count = 0;
+    #[test]
+    fn test_query_filter_add_remove_flags() {
+        let mut filter = QueryFilter::new().unwrap();
 
-        let find_path_status = unsafe {
-            dtNavMeshQuery_findPath(
-                self.handle,
-                start_ref,
-                end_ref,
-                start_pos,
-                end_pos,
-                filter.handle,
-                path.as_mut_ptr(),
-                &mut path_count,
-                path.capacity().try_into().unwrap(),
+        filter.set_include_flags(0);
+        filter.add_include_flag(1);
+        filter.add_include_flag(8);
+        assert_eq!(filter.get_include_flags(), 1 | 8);
+        filter.remove_include_flag(1);
+        assert_eq!(filter.get_include_flags(), 8);
+
+        filter.add_exclude_flag(2);
+        filter.add_exclude_flag(4);
+        assert_eq!(filter.get_exclude_flags(), 2 | 4);
+        filter.remove_exclude_flag(2);
+        filter.remove_exclude_flag(4);
+        assert_eq!(filter.get_exclude_flags(), 0);
+    }
+
+    #[test]
+    fn test_query_filter_area_costs() {
+        let mut filter = QueryFilter::new().unwrap();
+        assert_eq!(filter.get_area_costs(), [1.0; DT_MAX_AREAS]);
+
+        let costs: [f64; DT_MAX_AREAS] = std::array::from_fn(|area| 1.0 + area as f64 * 0.5);
+        filter.set_area_costs(&costs);
+        assert_eq!(filter.get_area_costs(), costs);
+        assert_eq!(filter.get_area_cost(3).unwrap(), 2.5);
+
+        filter.set_area_cost(3, 10.0).unwrap();
+        assert_eq!(filter.get_area_costs()[3], 10.0);
+        assert!(matches!(
+            filter.set_area_cost(DT_MAX_AREAS as u8, 1.0),
+            Err(DivertError::InvalidParam(_))
+        ));
+    }
+
+    #[test]
+    fn test_find_nearest_poly_ex() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...", "..."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(4.0, 2.0, 4.0);
+
+        let on_mesh = fixtures::cell_center(1, 1);
+        let (poly_ref, closest_point, is_over_poly) = nav_mesh_query
+            .find_nearest_poly_ex(&on_mesh, &extents, &filter)
+            .unwrap();
+        assert!(is_over_poly);
+        assert_eq!(
+            (poly_ref, closest_point),
+            nav_mesh_query
+                .find_nearest_poly(&on_mesh, &extents, &filter)
+                .unwrap()
+        );
+
+        // Past the mesh's +x edge, the point is snapped onto the edge from the side
+        let off_mesh = on_mesh + Vector::new(2.0 * fixtures::CELL_SIZE as f64, 0.0, 0.0);
+        let (poly_ref, closest_point, is_over_poly) = nav_mesh_query
+            .find_nearest_poly_ex(&off_mesh, &extents, &filter)
+            .unwrap();
+        assert!(!is_over_poly);
+        assert_ne!(poly_ref, PolyRef::default());
+        assert_eq!(closest_point.x, 3.0 * fixtures::CELL_SIZE as f64);
+
+        let far_away = Vector::new(100.0, 0.0, 100.0);
+        let (poly_ref, _, is_over_poly) = nav_mesh_query
+            .find_nearest_poly_ex(&far_away, &extents, &filter)
+            .unwrap();
+        assert_eq!(poly_ref, PolyRef::default());
+        assert!(!is_over_poly);
+    }
+
+    #[test]
+    fn test_find_nearest_poly_default() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...", "...", "..."]);
+        let mut nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let extents = Vector::new(1.5, 3.0, 1.5);
+        nav_mesh_query.set_default_extents(extents);
+        assert_eq!(nav_mesh_query.get_default_extents(), extents);
+
+        let center = fixtures::cell_center(2, 1);
+        let explicit = nav_mesh_query
+            .find_nearest_poly(&center, &extents, &filter)
+            .unwrap();
+        let default = nav_mesh_query
+            .find_nearest_poly_default(&center, &filter)
+            .unwrap();
+
+        assert_ne!(default.0, PolyRef::default());
+        assert_eq!(default, explicit);
+    }
+
+    #[test]
+    fn test_snap_to_navmesh_default_extents() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...", "...", "..."]);
+        let mut nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let position = fixtures::cell_center(2, 1) + Vector::new(0.0, 3.0, 0.0);
+        assert_eq!(nav_mesh_query.get_default_extents(), DEFAULT_EXTENTS);
+        let (poly_ref, snapped) = nav_mesh_query
+            .snap_to_navmesh_default(&position, &filter)
+            .unwrap()
+            .unwrap();
+        assert_ne!(poly_ref, PolyRef::default());
+        assert!(snapped.y.abs() < 1e-9);
+
+        nav_mesh_query.set_default_extents(Vector::new(1.0, 1.0, 1.0));
+        assert_eq!(
+            nav_mesh_query
+                .snap_to_navmesh_default(&position, &filter)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_node_pool_usage() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["......"]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(5, 0);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+        nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+            .unwrap();
+
+        let pool_size = nav_mesh_query.get_node_pool_size();
+        let nodes_used = nav_mesh_query.get_nodes_used();
+        assert!(pool_size >= 100);
+        assert!(nodes_used > 0);
+        assert!(nodes_used < pool_size);
+    }
+
+    #[test]
+    fn test_find_path_retry_grows_node_pool() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["............"]);
+        let mut nav_mesh_query = NavMeshQuery::new(&nav_mesh, 4).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let start_pos = fixtures::cell_center(1, 0);
+        let end_pos = fixtures::cell_center(11, 0);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+
+        let partial = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 32)
+            .unwrap();
+        assert_ne!(partial.last(), Some(&end_ref));
+
+        let path = nav_mesh_query
+            .find_path_retry(start_ref, end_ref, &start_pos, &end_pos, &filter, 32, 4, 64)
+            .unwrap();
+        assert_eq!(path.last(), Some(&end_ref));
+        assert_eq!(nav_mesh_query.config().max_nodes, 4);
+
+        // A pool grown before the call is searched as is, and keeps its size afterwards
+        nav_mesh_query.reinit(64).unwrap();
+        let path = nav_mesh_query
+            .find_path_retry(start_ref, end_ref, &start_pos, &end_pos, &filter, 32, 4, 8)
+            .unwrap();
+        assert_eq!(path.last(), Some(&end_ref));
+        assert_eq!(nav_mesh_query.config().max_nodes, 64);
+
+        assert!(matches!(
+            nav_mesh_query
+                .find_path_retry(start_ref, end_ref, &start_pos, &end_pos, &filter, 32, 64, 4),
+            Err(DivertError::InvalidParam(_))
+        ));
+    }
+
+    #[test]
+    fn test_find_path_growing() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["....", "###.", "....", ".###", "...."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(3, 4);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+
+        let truncated = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 2)
+            .unwrap();
+        assert_eq!(truncated.len(), 2);
+
+        let path = nav_mesh_query
+            .find_path_growing(start_ref, end_ref, &start_pos, &end_pos, &filter, 2)
+            .unwrap();
+        assert_eq!(path.len(), 14);
+        assert_eq!(path.last(), Some(&end_ref));
+
+        let straight_path = nav_mesh_query
+            .find_straight_path(
+                &start_pos,
+                &end_pos,
+                &path,
+                64,
+                StraightPathOptions::empty(),
+            )
+            .unwrap();
+        assert!(straight_path.len() > 2);
+        assert_eq!(
+            nav_mesh_query
+                .find_straight_path_growing(
+                    &start_pos,
+                    &end_pos,
+                    &path,
+                    1,
+                    StraightPathOptions::empty()
+                )
+                .unwrap(),
+            straight_path
+        );
+
+        assert!(matches!(
+            nav_mesh_query.find_path_growing(start_ref, end_ref, &start_pos, &end_pos, &filter, 0),
+            Err(DivertError::InvalidParam(_))
+        ));
+    }
+
+    #[test]
+    fn test_filter_passes() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["..w"]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let mut filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let (ground_ref, _) = nav_mesh_query
+            .find_nearest_poly(&fixtures::cell_center(1, 0), &extents, &filter)
+            .unwrap();
+        let (water_ref, _) = nav_mesh_query
+            .find_nearest_poly(&fixtures::cell_center(2, 0), &extents, &filter)
+            .unwrap();
+        assert_eq!(
+            nav_mesh.get_poly_flags(water_ref).unwrap(),
+            fixtures::WATER_FLAG
+        );
+        assert!(nav_mesh_query.filter_passes(water_ref, &filter).unwrap());
+
+        filter.add_exclude_flag(fixtures::WATER_FLAG);
+        assert!(!nav_mesh_query.filter_passes(water_ref, &filter).unwrap());
+        assert!(nav_mesh_query.filter_passes(ground_ref, &filter).unwrap());
+
+        assert!(matches!(
+            nav_mesh_query.filter_passes(PolyRef::default(), &filter),
+            Err(DivertError::GetPolyFlagsFailure(_))
+        ));
+    }
+
+    #[test]
+    fn test_raycast_move() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["....", "....", "...."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let start_pos = fixtures::cell_center(1, 1);
+        let (start_ref, _) = nav_mesh_query
+            .find_nearest_poly(&start_pos, &extents, &filter)
+            .unwrap();
+
+        let open_end = fixtures::cell_center(3, 2);
+        let (raycast_pos, raycast_path) = nav_mesh_query
+            .raycast_move(start_ref, &start_pos, &open_end, &filter, 16)
+            .unwrap();
+        let (surface_pos, surface_path) = nav_mesh_query
+            .move_along_surface(start_ref, &start_pos, &open_end, &filter, 16)
+            .unwrap();
+        assert!(raycast_pos.abs_diff_eq(open_end, 1e-9));
+        assert!(raycast_pos.abs_diff_eq(surface_pos, 1e-9));
+        assert_eq!(raycast_path.first(), Some(&start_ref));
+        assert_eq!(raycast_path.last(), surface_path.last());
+
+        let (t, hit_normal, _) = nav_mesh_query
+            .raycast(start_ref, &start_pos, &open_end, &filter, 16)
+            .unwrap();
+        assert_eq!(t, f32::MAX as f64);
+        assert_eq!(hit_normal, Vector::default());
+
+        let blocked_end = fixtures::cell_center(6, 1);
+        let (blocked_pos, _) = nav_mesh_query
+            .raycast_move(start_ref, &start_pos, &blocked_end, &filter, 16)
+            .unwrap();
+        assert!((blocked_pos.x - 4.0 * fixtures::CELL_SIZE as f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_non_finite_positions() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["..."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let start_pos = fixtures::cell_center(1, 0);
+        let (start_ref, _) = nav_mesh_query
+            .find_nearest_poly(&start_pos, &extents, &filter)
+            .unwrap();
+
+        let nan = Vector::new(f64::NAN, 0.0, 0.0);
+        assert!(matches!(
+            nav_mesh_query.find_nearest_poly(&nan, &extents, &filter),
+            Err(DivertError::InvalidParam("center must be finite"))
+        ));
+        assert!(matches!(
+            nav_mesh_query.find_nearest_poly(&start_pos, &Vector::splat(f64::INFINITY), &filter),
+            Err(DivertError::InvalidParam("extents must be finite"))
+        ));
+        assert!(matches!(
+            nav_mesh_query.find_path(start_ref, start_ref, &start_pos, &nan, &filter, 16),
+            Err(DivertError::InvalidParam("end_pos must be finite"))
+        ));
+        assert!(matches!(
+            nav_mesh_query.move_along_surface(start_ref, &nan, &start_pos, &filter, 16),
+            Err(DivertError::InvalidParam("start_pos must be finite"))
+        ));
+    }
+
+    #[test]
+    fn test_find_straight_path_same_position() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["..."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let position = fixtures::cell_center(1, 0);
+        let (poly_ref, _) = nav_mesh_query
+            .find_nearest_poly(&position, &extents, &filter)
+            .unwrap();
+        let poly_path = nav_mesh_query
+            .find_path(poly_ref, poly_ref, &position, &position, &filter, 16)
+            .unwrap();
+        assert_eq!(poly_path, vec![poly_ref]);
+
+        let straight_path = nav_mesh_query
+            .find_straight_path(
+                &position,
+                &position,
+                &poly_path,
+                16,
+                StraightPathOptions::empty(),
             )
+            .unwrap();
+        assert_eq!(
+            straight_path,
+            vec![(
+                position,
+                DtStraightPathFlags::START | DtStraightPathFlags::END,
+                poly_ref
+            )]
+        );
+    }
+
+    #[test]
+    fn test_has_line_of_sight() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["....", "..#.", "...."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let start_pos = fixtures::cell_center(0, 1);
+        let (start_ref, _) = nav_mesh_query
+            .find_nearest_poly(&start_pos, &extents, &filter)
+            .unwrap();
+
+        assert!(nav_mesh_query
+            .has_line_of_sight(start_ref, &start_pos, &fixtures::cell_center(1, 1), &filter)
+            .unwrap());
+        assert!(nav_mesh_query
+            .has_line_of_sight(start_ref, &start_pos, &fixtures::cell_center(1, 0), &filter)
+            .unwrap());
+        assert!(!nav_mesh_query
+            .has_line_of_sight(start_ref, &start_pos, &fixtures::cell_center(3, 1), &filter)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_move_along_surface_until_offmesh() {
+        let start = fixtures::cell_center(2, 0);
+        let end = fixtures::cell_center(2, 2);
+        let tile_data = fixtures::create_tile_data_with(
+            0,
+            0,
+            &["....", "####", "...."],
+            &NavMeshCreateParams {
+                off_mesh_con_verts: &[[start.x, start.y, start.z, end.x, end.y, end.z]],
+                off_mesh_con_rad: &[1.0],
+                off_mesh_con_flags: &[fixtures::GROUND_FLAG],
+                off_mesh_con_areas: &[fixtures::GROUND_AREA],
+                off_mesh_con_dir: &[OffMeshConnectionDirection::OneWay],
+                off_mesh_con_user_id: &[1],
+                ..Default::default()
+            },
+        );
+
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(4, 3, 1)).unwrap();
+        nav_mesh.add_tile(tile_data).unwrap();
+
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(3, 0);
+        let (start_ref, _) = nav_mesh_query
+            .find_nearest_poly(&start_pos, &extents, &filter)
+            .unwrap();
+        let (connection_ref, _) = nav_mesh_query
+            .find_nearest_poly(&start, &extents, &filter)
+            .unwrap();
+
+        let (result_pos, visited, off_mesh_ref) = nav_mesh_query
+            .move_along_surface_until_offmesh(start_ref, &start_pos, &end_pos, &filter, 16)
+            .unwrap();
+        assert!(result_pos.abs_diff_eq(start, 1e-9));
+        assert_eq!(visited.first(), Some(&start_ref));
+        assert_eq!(visited.last(), Some(&connection_ref));
+        assert!(off_mesh_ref.is_some());
+
+        let (result_pos, _, off_mesh_ref) = nav_mesh_query
+            .move_along_surface_until_offmesh(connection_ref, &start, &end_pos, &filter, 16)
+            .unwrap();
+        assert!(result_pos.abs_diff_eq(end_pos, 1e-9));
+        assert_eq!(off_mesh_ref, None);
+    }
+
+    #[test]
+    fn test_find_nearest_poly_expanding() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...", "...", "..."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let center = fixtures::cell_center(2, 1) + Vector::new(0.0, 6.0, 0.0);
+        let initial = Vector::new(1.0, 1.0, 1.0);
+        let (missed_ref, _) = nav_mesh_query
+            .find_nearest_poly(&center, &initial, &filter)
+            .unwrap();
+        assert_eq!(missed_ref, PolyRef::default());
+
+        let (nearest_ref, closest_point) = nav_mesh_query
+            .find_nearest_poly_expanding(&center, &initial, &Vector::splat(8.0), 2.0, &filter)
+            .unwrap()
+            .unwrap();
+        assert_ne!(nearest_ref, PolyRef::default());
+        assert!(closest_point.y.abs() < 1e-9);
+
+        assert_eq!(
+            nav_mesh_query
+                .find_nearest_poly_expanding(&center, &initial, &Vector::splat(4.0), 2.0, &filter)
+                .unwrap(),
+            None
+        );
+        assert!(matches!(
+            nav_mesh_query.find_nearest_poly_expanding(&center, &initial, &initial, 1.0, &filter),
+            Err(DivertError::InvalidParam(_))
+        ));
+
+        let max = Vector::splat(8.0);
+        for invalid_initial in [
+            Vector::ZERO,
+            Vector::new(0.0, 2.0, 0.0),
+            Vector::new(1.0, -1.0, 1.0),
+            Vector::new(1.0, f64::NAN, 1.0),
+        ] {
+            assert!(matches!(
+                nav_mesh_query.find_nearest_poly_expanding(
+                    &center,
+                    &invalid_initial,
+                    &max,
+                    2.0,
+                    &filter
+                ),
+                Err(DivertError::InvalidParam(_))
+            ));
+        }
+        let empty_nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(3, 3, 1)).unwrap();
+        let empty_query = NavMeshQuery::new(&empty_nav_mesh, 100).unwrap();
+        assert!(matches!(
+            empty_query.find_nearest_poly_expanding(&center, &initial, &max, 2.0, &filter),
+            Err(DivertError::EmptyNavMesh())
+        ));
+
+        for invalid_max in [Vector::splat(0.5), Vector::splat(f64::INFINITY)] {
+            assert!(matches!(
+                nav_mesh_query.find_nearest_poly_expanding(
+                    &center,
+                    &initial,
+                    &invalid_max,
+                    2.0,
+                    &filter
+                ),
+                Err(DivertError::InvalidParam(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_snap_to_navmesh() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...", "...", "..."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let position = fixtures::cell_center(1, 1) + Vector::new(0.0, 0.75, 0.0);
+        let (poly_ref, snapped) = nav_mesh_query
+            .snap_to_navmesh(&position, &extents, &filter)
+            .unwrap()
+            .unwrap();
+
+        let surface_height = nav_mesh_query.get_poly_height(poly_ref, &snapped).unwrap();
+        assert_eq!(snapped.y, surface_height);
+        assert_eq!(snapped.x, position.x);
+        assert_eq!(snapped.z, position.z);
+
+        let far_away = Vector::new(100.0, 0.0, 100.0);
+        let snapped = nav_mesh_query
+            .snap_to_navmesh(&far_away, &extents, &filter)
+            .unwrap();
+        assert!(snapped.is_none());
+    }
+
+    #[test]
+    fn test_snap_vertical_retry() {
+        // A single steep quad rising from y = 6 at x = 0 to y = 14 at x = 4
+        let tile_data = create_nav_mesh_data(&NavMeshCreateParams {
+            verts: &[[0, 7, 0], [0, 7, 4], [4, 15, 4], [4, 15, 0]],
+            polys: &[
+                0, 1, 2, 3, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff,
+            ],
+            poly_flags: &[fixtures::GROUND_FLAG],
+            poly_areas: &[fixtures::GROUND_AREA],
+            nvp: 6,
+            b_min: [0.0, -1.0, 0.0],
+            b_max: [4.0, 15.0, 4.0],
+            walkable_height: 2.0,
+            walkable_radius: 0.5,
+            walkable_climb: 1.0,
+            cs: 1.0,
+            ch: 1.0,
+            build_bv_tree: true,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(1, 1, 1)).unwrap();
+        nav_mesh.add_tile(tile_data).unwrap();
+
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+        let position = Vector::new(3.0, 0.0, 3.0);
+
+        let no_retry = VerticalSnapPolicy {
+            max_retries: 0,
+            ..Default::default()
         };
+        assert!(nav_mesh_query
+            .snap(&position, &extents, &filter, &no_retry)
+            .unwrap()
+            .is_none());
 
-        log::trace!("FindPathStatus: {:#?}", find_path_status);
+        let (poly_ref, snapped) = nav_mesh_query
+            .snap(&position, &extents, &filter, &VerticalSnapPolicy::default())
+            .unwrap()
+            .unwrap();
+        assert_ne!(poly_ref, PolyRef::default());
+        assert_eq!((snapped.x, snapped.z), (position.x, position.z));
+        assert!((snapped.y - 12.0).abs() < 1e-9);
+
+        for growth in [1.0, 0.5, f64::NAN] {
+            let policy = VerticalSnapPolicy {
+                growth,
+                ..Default::default()
+            };
+            assert!(matches!(
+                nav_mesh_query.snap(&position, &extents, &filter, &policy),
+                Err(DivertError::InvalidParam(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_is_reachable() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["..#.."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let positions: Vec<_> = [0, 1, 4]
+            .iter()
+            .map(|&x| fixtures::cell_center(x, 0))
+            .collect();
+        let refs: Vec<_> = positions
+            .iter()
+            .map(|position| {
+                nav_mesh_query
+                    .find_nearest_poly(position, &extents, &filter)
+                    .unwrap()
+                    .0
+            })
+            .collect();
+
+        assert!(nav_mesh_query
+            .is_reachable(refs[0], refs[1], &positions[0], &positions[1], &filter, 16)
+            .unwrap());
+        assert!(!nav_mesh_query
+            .is_reachable(refs[0], refs[2], &positions[0], &positions[2], &filter, 16)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_find_path_detailed() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...#.."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let positions: Vec<_> = [0, 2, 5]
+            .iter()
+            .map(|&x| fixtures::cell_center(x, 0))
+            .collect();
+        let refs: Vec<_> = positions
+            .iter()
+            .map(|position| {
+                nav_mesh_query
+                    .find_nearest_poly(position, &extents, &filter)
+                    .unwrap()
+                    .0
+            })
+            .collect();
+
+        let reachable = nav_mesh_query
+            .find_path_detailed(refs[0], refs[1], &positions[0], &positions[1], &filter, 16)
+            .unwrap();
+        assert!(reachable.complete);
+        assert_eq!(reachable.path.len(), 3);
+        assert!(reachable.status.is_success());
+
+        let unreachable = nav_mesh_query
+            .find_path_detailed(refs[0], refs[2], &positions[0], &positions[2], &filter, 16)
+            .unwrap();
+        assert!(!unreachable.complete);
+        assert!(!unreachable.path.is_empty());
+        assert!(unreachable.status.contains(DtStatus::PARTIAL_RESULT));
+
+        let truncated = nav_mesh_query
+            .find_path_detailed(refs[0], refs[1], &positions[0], &positions[1], &filter, 2)
+            .unwrap();
+        assert!(!truncated.complete);
+        assert_eq!(truncated.path.len(), 2);
+        assert!(truncated.status.contains(DtStatus::BUFFER_TOO_SMALL));
+    }
+
+    #[test]
+    fn test_heuristic_scale() {
+        let mut layout = vec!["................"; 16];
+        layout[8] = "..............##";
+        let nav_mesh = fixtures::nav_mesh_with_tile(&layout);
+        let mut nav_mesh_query = NavMeshQuery::new(&nav_mesh, 1024).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let start_pos = fixtures::cell_center(15, 1);
+        let end_pos = fixtures::cell_center(15, 14);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+
+        let optimal_path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 256)
+            .unwrap();
+        let optimal_nodes = nav_mesh_query.get_nodes_used();
+
+        nav_mesh_query.set_heuristic_scale(4.0).unwrap();
+        let weighted_path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 256)
+            .unwrap();
+        assert!(nav_mesh_query.get_nodes_used() < optimal_nodes);
+        assert_eq!(weighted_path.first(), Some(&start_ref));
+        assert_eq!(weighted_path.last(), Some(&end_ref));
+        assert!(weighted_path.len() >= optimal_path.len());
+
+        let mut out = [PolyRef::default(); 256];
+        let path_count = nav_mesh_query
+            .find_path_into(start_ref, end_ref, &start_pos, &end_pos, &filter, &mut out)
+            .unwrap();
+        assert_eq!(&out[..path_count], weighted_path.as_slice());
+
+        assert!(nav_mesh_query.set_heuristic_scale(0.0).is_err());
+        assert!(nav_mesh_query.set_heuristic_scale(f64::NAN).is_err());
+        assert_eq!(nav_mesh_query.get_heuristic_scale(), 4.0);
+
+        nav_mesh_query.set_heuristic_scale(1.0).unwrap();
+        assert_eq!(
+            nav_mesh_query
+                .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 256)
+                .unwrap(),
+            optimal_path
+        );
+    }
+
+    #[test]
+    fn test_same_poly() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...", "..."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let center = fixtures::cell_center(1, 1);
+        let offset = Vector::new(1.0, 0.0, -1.0);
+        assert!(nav_mesh_query
+            .same_poly(&(center - offset), &(center + offset), &extents, &filter)
+            .unwrap());
+        assert!(!nav_mesh_query
+            .same_poly(&center, &fixtures::cell_center(2, 1), &extents, &filter)
+            .unwrap());
+
+        let off_mesh = Vector::new(-10.0, 0.0, -10.0);
+        assert!(!nav_mesh_query
+            .same_poly(&off_mesh, &off_mesh, &extents, &filter)
+            .unwrap());
+    }
 
-        unsafe {
-            path.set_len(path_count as usize);
-        }
+    #[test]
+    fn test_closest_navigable_point() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...", ".#."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(2.0, 4.0, 2.0);
 
-        if find_path_status.is_failed() {
-            return Err(DivertError::FindPathFailure(find_path_status));
+        let blocked = fixtures::cell_center(1, 1) + Vector::new(0.5, 3.0, 0.5);
+        for pos in [fixtures::cell_center(0, 0) + Vector::Y, blocked] {
+            let (nearest_ref, _) = nav_mesh_query
+                .find_nearest_poly(&pos, &extents, &filter)
+                .unwrap();
+            let (expected, _) = nav_mesh_query
+                .closest_point_on_poly(nearest_ref, &pos)
+                .unwrap();
+            assert_eq!(
+                nav_mesh_query
+                    .closest_navigable_point(&pos, &extents, &filter)
+                    .unwrap(),
+                Some(expected)
+            );
         }
 
-        Ok(find_path_status)
+        let off_mesh = Vector::new(-10.0, 0.0, -10.0);
+        assert_eq!(
+            nav_mesh_query
+                .closest_navigable_point(&off_mesh, &extents, &filter)
+                .unwrap(),
+            None
+        );
     }
 
-    /// Generates a polygon path from one (poly, position) to another (poly, position)
-    /// Errors if ffi function returns a failed DtStatus
-    pub fn find_path(
-        &self,
-        start_ref: PolyRef,
-        end_ref: PolyRef,
-        start_pos: &Vector,
-        end_pos: &Vector,
-        filter: &QueryFilter,
-        max_path: i32,
-    ) -> DivertResult<Vec<PolyRef>> {
-        let mut path_count = 0;
-        let mut path: Vec<PolyRef> = Vec::with_capacity(max_path.try_into().unwrap());
-
-        let find_path_status = unsafe {
-            dtNavMeshQuery_findPath(
-                self.handle,
-                start_ref,
-                end_ref,
-                start_pos,
-                end_pos,
-                filter.handle,
-                path.as_mut_ptr(),
-                &mut path_count,
-                max_path,
-            )
+    #[test]
+    fn test_nav_mesh_query_config() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["....", ".##.", "...."]);
+        let config = NavMeshQueryConfig {
+            max_nodes: 64,
+            default_extents: Vector::new(1.0, 2.0, 1.0),
+            heuristic_scale: 2.0,
         };
+        let first = NavMeshQuery::with_config(&nav_mesh, &config).unwrap();
+        let second = NavMeshQuery::with_config(&nav_mesh, &first.config()).unwrap();
+        assert_eq!(first.config(), config);
+        assert_eq!(second.config(), config);
 
-        log::trace!("FindPathStatus: {:#?}", find_path_status);
-
-        unsafe {
-            path.set_len(path_count as usize);
-        }
+        let filter = QueryFilter::new().unwrap();
+        let find_path = |nav_mesh_query: &NavMeshQuery| {
+            let start_pos = fixtures::cell_center(0, 1);
+            let end_pos = fixtures::cell_center(3, 1);
+            let (start_ref, _) = nav_mesh_query
+                .find_nearest_poly_default(&start_pos, &filter)
+                .unwrap();
+            let (end_ref, _) = nav_mesh_query
+                .find_nearest_poly_default(&end_pos, &filter)
+                .unwrap();
 
-        if find_path_status.is_failed() {
-            return Err(DivertError::FindPathFailure(find_path_status));
-        }
+            nav_mesh_query
+                .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+                .unwrap()
+        };
+        assert_eq!(find_path(&first), find_path(&second));
 
-        Ok(path)
+        let invalid = NavMeshQueryConfig {
+            heuristic_scale: 0.0,
+            ..config
+        };
+        assert!(NavMeshQuery::with_config(&nav_mesh, &invalid).is_err());
     }
 
-    #[allow(clippy::too_many_arguments)]
-    /// Generates a (poly, position) path from on (poly, position) to another (poly, position)
-    /// Uses a user provided DtVector Vec, DtStraightPathFlags Vec, and PolyRef Vec
-    /// Max Path length is derived from the user provided DtVector Vec's capacity
-    /// Errors if ffi function returns a failed DtStatus
-    pub fn find_straight_path_inplace(
-        &self,
-        start_pos: &Vector,
-        end_pos: &Vector,
-        poly_path: &[PolyRef],
-        straight_path_points: &mut Vec<DtVector>,
-        straight_path_flags: &mut Vec<DtStraightPathFlags>,
-        straight_path_polys: &mut Vec<PolyRef>,
-        options: i32,
-    ) -> DivertResult<DtStatus> {
-        let mut straight_path_count = 0;
+    #[test]
+    fn test_reachable_polys() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["..#...", "..#..."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
 
-        let find_path_status = unsafe {
-            dtNavMeshQuery_findStraightPath(
-                self.handle,
-                start_pos,
-                end_pos,
-                poly_path.as_ptr(),
-                poly_path.len().try_into().unwrap(),
-                straight_path_points.as_mut_ptr(),
-                straight_path_flags.as_mut_ptr(),
-                straight_path_polys.as_mut_ptr(),
-                &mut straight_path_count,
-                straight_path_points.capacity().try_into().unwrap(),
-                options,
-            )
+        let poly_at = |x, z| {
+            nav_mesh_query
+                .find_nearest_poly(&fixtures::cell_center(x, z), &extents, &filter)
+                .unwrap()
+                .0
         };
+        let start_ref = poly_at(0, 0);
 
-        let path_count = straight_path_count as usize;
-        unsafe {
-            straight_path_points.set_len(path_count);
-            straight_path_flags.set_len(path_count);
-            straight_path_polys.set_len(path_count);
-        }
+        let mut reachable = nav_mesh_query
+            .reachable_polys(start_ref, &filter, 64)
+            .unwrap();
+        assert_eq!(reachable.first(), Some(&start_ref));
+        reachable.sort();
+        let mut island = vec![poly_at(0, 0), poly_at(1, 0), poly_at(0, 1), poly_at(1, 1)];
+        island.sort();
+        assert_eq!(reachable, island);
 
-        log::trace!("FindStraightPathStatus: {:#?}", find_path_status);
-        if find_path_status.is_failed() {
-            return Err(DivertError::FindStraightPathFailure(find_path_status));
+        let right_island = nav_mesh_query
+            .reachable_polys(poly_at(5, 1), &filter, 64)
+            .unwrap();
+        assert_eq!(right_island.len(), 6);
+
+        assert_eq!(
+            nav_mesh_query
+                .reachable_polys(start_ref, &filter, 2)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(matches!(
+            nav_mesh_query.reachable_polys(PolyRef::default(), &filter, 64),
+            Err(DivertError::GetTileAndPolyByRefFailure(_))
+        ));
+        for max_polys in [0, -1, i32::MAX] {
+            assert!(matches!(
+                nav_mesh_query.reachable_polys(start_ref, &filter, max_polys),
+                Err(DivertError::InvalidParam(_))
+            ));
         }
+    }
 
-        Ok(find_path_status)
+    #[test]
+    fn test_nearest_boundary_point() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["..", ".."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(2.0, 2.0, 2.0);
+
+        let past_edge = Vector::new(-1.0, 0.0, 2.5);
+        let boundary_point = nav_mesh_query
+            .nearest_boundary_point(&past_edge, &extents, &filter)
+            .unwrap()
+            .unwrap();
+        assert_eq!(boundary_point, Vector::new(0.0, 0.0, 2.5));
+
+        let far_away = Vector::new(-10.0, 0.0, 2.5);
+        assert!(nav_mesh_query
+            .nearest_boundary_point(&far_away, &extents, &filter)
+            .unwrap()
+            .is_none());
     }
 
-    /// Generates a (poly, position) path from on (poly, position) to another (poly, position)
-    /// Errors if ffi function returns a failed DtStatus
-    pub fn find_straight_path(
-        &self,
-        start_pos: &Vector,
-        end_pos: &Vector,
-        poly_path: &[PolyRef],
-        max_path: i32,
-        options: i32,
-    ) -> DivertResult<Vec<(Vector, DtStraightPathFlags, PolyRef)>> {
-        let mut straight_path_count = 0;
-        let mut straight_path_points: Vec<DtVector> =
-            Vec::with_capacity(max_path.try_into().unwrap());
-        let mut straight_path_flags: Vec<DtStraightPathFlags> =
-            Vec::with_capacity(max_path.try_into().unwrap());
-        let mut straight_path_polys: Vec<PolyRef> =
-            Vec::with_capacity(max_path.try_into().unwrap());
+    #[test]
+    fn test_clamp_to_corridor() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["....", "#..#"]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
 
-        let find_path_status = unsafe {
-            dtNavMeshQuery_findStraightPath(
-                self.handle,
-                start_pos,
-                end_pos,
-                poly_path.as_ptr(),
-                poly_path.len().try_into().unwrap(),
-                straight_path_points.as_mut_ptr(),
-                straight_path_flags.as_mut_ptr(),
-                straight_path_polys.as_mut_ptr(),
-                &mut straight_path_count,
-                max_path,
-                options,
-            )
-        };
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(2, 1);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+        let path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+            .unwrap();
 
-        log::trace!("FindStraightPathStatus: {:#?}", find_path_status);
+        // Both positions lie outside their polys and need clamping
+        let start = fixtures::cell_center(0, 1);
+        let end = fixtures::cell_center(3, 1);
+        let (clamped_start, clamped_end) = nav_mesh_query
+            .clamp_to_corridor(&path, &start, &end)
+            .unwrap();
 
-        let path_count = straight_path_count as usize;
+        assert_eq!(
+            clamped_start,
+            nav_mesh_query
+                .closest_point_on_poly_boundary(start_ref, &start)
+                .unwrap()
+        );
+        assert_eq!(
+            clamped_end,
+            nav_mesh_query
+                .closest_point_on_poly_boundary(end_ref, &end)
+                .unwrap()
+        );
+        assert_ne!(clamped_start, start);
+        assert_ne!(clamped_end, end);
 
-        unsafe {
-            straight_path_points.set_len(path_count);
-            straight_path_flags.set_len(path_count);
-            straight_path_polys.set_len(path_count);
-        }
+        assert!(matches!(
+            nav_mesh_query.clamp_to_corridor(&[], &start, &end),
+            Err(DivertError::InvalidParam(_))
+        ));
+    }
 
-        if find_path_status.is_failed() {
-            return Err(DivertError::FindStraightPathFailure(find_path_status));
-        }
+    #[test]
+    fn test_find_straight_path_fills_max_path() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["..", "#."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
 
-        let path_result = straight_path_points
-            .into_iter()
-            .zip(straight_path_flags.into_iter())
-            .zip(straight_path_polys.into_iter())
-            .map(|((pos, flags), poly_ref)| (pos, flags, poly_ref))
-            .collect();
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(1, 1);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+        let path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+            .unwrap();
+
+        // start, the corner around the blocked cell, and end
+        let straight_path = nav_mesh_query
+            .find_straight_path(&start_pos, &end_pos, &path, 3, StraightPathOptions::empty())
+            .unwrap();
+        assert_eq!(straight_path.len(), 3);
+        assert!(straight_path[2].1.contains(DtStraightPathFlags::END));
 
-        Ok(path_result)
+        let truncated = nav_mesh_query
+            .find_straight_path(&start_pos, &end_pos, &path, 2, StraightPathOptions::empty())
+            .unwrap();
+        assert_eq!(truncated.len(), 2);
+        assert!(!truncated[1].1.contains(DtStraightPathFlags::END));
     }
 
-    /// Generates a poly path while moving from (poly, position) to a (poly)
-    /// Uses a user provided PolyRef Vec
-    /// Max Path length is derived from the user provided PolyRef Vec's capacity
-    /// Errors if ffi function returns a failed DtStatus
-    pub fn move_along_surface_inplace(
-        &self,
-        start_ref: PolyRef,
-        start_pos: &Vector,
-        end_pos: &Vector,
-        filter: &QueryFilter,
-        result_pos: &mut Vector,
-        visited: &mut Vec<PolyRef>,
-    ) -> DivertResult<DtStatus> {
-        let mut visited_count = 0;
+    #[test]
+    fn test_find_path_into() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["....", "...."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
 
-        let move_along_surface_result = unsafe {
-            dtNavMeshQuery_moveAlongSurface(
-                self.handle,
-                start_ref,
-                start_pos,
-                end_pos,
-                filter.handle,
-                result_pos,
-                visited.as_mut_ptr(),
-                &mut visited_count,
-                visited.capacity().try_into().unwrap(),
-            )
-        };
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(3, 1);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
 
-        unsafe {
-            visited.set_len(visited_count as usize);
-        }
+        let expected_path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+            .unwrap();
 
-        if move_along_surface_result.is_failed() {
-            return Err(DivertError::MoveAlongSurfaceFailure(
-                move_along_surface_result,
-            ));
-        }
+        let mut out = [PolyRef::default(); 16];
+        let path_count = nav_mesh_query
+            .find_path_into(start_ref, end_ref, &start_pos, &end_pos, &filter, &mut out)
+            .unwrap();
+        assert_eq!(&out[..path_count], expected_path.as_slice());
+    }
 
-        Ok(move_along_surface_result)
+    #[test]
+    fn test_sample_heights() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["..."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let position = fixtures::cell_center(1, 0);
+        let (poly_ref, _) = nav_mesh_query
+            .find_nearest_poly(&position, &extents, &filter)
+            .unwrap();
+
+        let heights = nav_mesh_query.sample_heights(&[
+            (poly_ref, position),
+            (poly_ref, fixtures::cell_center(2, 0)),
+            (PolyRef::default(), position),
+        ]);
+        assert_eq!(heights, vec![Some(0.0), None, None]);
     }
 
-    /// Generates a poly path while moving from (poly, position) to a (poly)
-    /// Errors if ffi function returns a failed DtStatus
-    pub fn move_along_surface(
-        &self,
-        start_ref: PolyRef,
-        start_pos: &Vector,
-        end_pos: &Vector,
-        filter: &QueryFilter,
-        max_visit: i32,
-    ) -> DivertResult<(Vector, Vec<PolyRef>)> {
-        let mut visited_count = 0;
-        let mut visited: Vec<PolyRef> = Vec::with_capacity(max_visit.try_into().unwrap());
-        let mut result_pos = Vector::default();
+    #[test]
+    fn test_negative_buffer_sizes() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["..."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
 
-        let move_along_surface_result = unsafe {
-            dtNavMeshQuery_moveAlongSurface(
-                self.handle,
-                start_ref,
-                start_pos,
-                end_pos,
-                filter.handle,
-                &mut result_pos,
-                visited.as_mut_ptr(),
-                &mut visited_count,
-                max_visit,
-            )
-        };
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(2, 0);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
 
-        unsafe {
-            visited.set_len(visited_count as usize);
-        }
+        let find_path =
+            nav_mesh_query.find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, -1);
+        assert!(matches!(find_path, Err(DivertError::InvalidParam(_))));
 
-        if move_along_surface_result.is_failed() {
-            return Err(DivertError::MoveAlongSurfaceFailure(
-                move_along_surface_result,
-            ));
-        }
+        let find_straight_path = nav_mesh_query.find_straight_path(
+            &start_pos,
+            &end_pos,
+            &[start_ref],
+            -1,
+            StraightPathOptions::empty(),
+        );
+        assert!(matches!(
+            find_straight_path,
+            Err(DivertError::InvalidParam(_))
+        ));
 
-        Ok((result_pos, visited))
+        let move_along_surface =
+            nav_mesh_query.move_along_surface(start_ref, &start_pos, &end_pos, &filter, i32::MAX);
+        assert!(matches!(
+            move_along_surface,
+            Err(DivertError::InvalidParam(_))
+        ));
     }
-}
 
-/// Handles freeing the inner dtNavMeshQuery
-impl<'a> Drop for NavMeshQuery<'a> {
-    /// Frees dtNavMeshQuery resources with dtFreeNavMeshQuery
-    fn drop(&mut self) {
-        unsafe { dtNavMeshQuery_free(self.handle) }
-    }
-}
+    #[test]
+    fn test_closest_point_on_detail_edges() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["..."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let position = fixtures::cell_center(1, 0) + Vector::new(0.5, 0.0, 0.0);
+        let (poly_ref, _) = nav_mesh_query
+            .find_nearest_poly(&position, &Vector::new(1.0, 2.0, 1.0), &filter)
+            .unwrap();
 
-#[cfg(test)]
-mod tests {
+        // The cell's +x edge is the closest boundary edge
+        let (dist_sqr, closest_point) = nav_mesh_query
+            .closest_point_on_detail_edges(poly_ref, &position, true)
+            .unwrap();
+        assert!((dist_sqr - 2.25).abs() < 1e-9);
+        assert_eq!(
+            (closest_point.x, closest_point.z),
+            (2.0 * fixtures::CELL_SIZE as f64, position.z)
+        );
 
-    use crate::{NavMesh, NavMeshParams, NavMeshQuery, QueryFilter};
+        // The diagonal splitting the cell into two detail triangles is closer
+        let (inner_dist_sqr, _) = nav_mesh_query
+            .closest_point_on_detail_edges(poly_ref, &position, false)
+            .unwrap();
+        assert!((inner_dist_sqr - 0.125).abs() < 1e-9);
+
+        assert!(matches!(
+            nav_mesh_query.closest_point_on_detail_edges(PolyRef::from_raw(1), &position, false),
+            Err(DivertError::ClosestPointOnDetailEdgesFailure(_))
+        ));
+    }
 
     #[test]
-    fn test_nav_mesh() {
-        let nav_mesh_params = NavMeshParams {
-            origin: [0.0, 0.0, 0.0],
-            tile_width: 32.0,
-            tile_height: 32.0,
-            max_polys: 1000,
-            max_tiles: 1,
-        };
+    fn test_closest_point_over_poly() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["..#"]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let (poly_ref, _) = nav_mesh_query
+            .find_nearest_poly(
+                &fixtures::cell_center(1, 0),
+                &Vector::new(1.0, 2.0, 1.0),
+                &filter,
+            )
+            .unwrap();
 
-        let nav_mesh = NavMesh::new(&nav_mesh_params);
-        assert!(nav_mesh.is_ok());
+        // Over the poly, the position is dropped onto the poly's surface
+        let above = fixtures::cell_center(1, 0) + Vector::new(0.5, 3.0, -0.5);
+        let closest_point = nav_mesh_query
+            .closest_point_over_poly(poly_ref, &above)
+            .unwrap()
+            .unwrap();
+        assert_eq!((closest_point.x, closest_point.z), (above.x, above.z));
+        assert!(closest_point.y.abs() < 1e-9);
 
-        let _nav_mesh = nav_mesh.unwrap();
+        // Over the blocked cell next to it, the position would be clamped to the poly's edge
+        let beside = fixtures::cell_center(2, 0);
+        assert_eq!(
+            nav_mesh_query
+                .closest_point_over_poly(poly_ref, &beside)
+                .unwrap(),
+            None
+        );
+        let (clamped, over_poly) = nav_mesh_query
+            .closest_point_on_poly(poly_ref, &beside)
+            .unwrap();
+        assert!(!over_poly);
+        assert_eq!(clamped.x, 2.0 * fixtures::CELL_SIZE as f64);
     }
 
     #[test]
-    fn test_nav_mesh_query() {
+    fn test_closest_point_over_poly_invalid_ref() {
         let nav_mesh_params = NavMeshParams {
             origin: [0.0, 0.0, 0.0],
             tile_width: 32.0,
@@ -675,21 +4454,138 @@ mod tests {
         };
 
         let nav_mesh = NavMesh::new(&nav_mesh_params).unwrap();
-        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100);
-        assert!(nav_mesh_query.is_ok());
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+
+        let result =
+            nav_mesh_query.closest_point_over_poly(PolyRef::from_raw(1), &Vector::default());
+        assert!(matches!(
+            result,
+            Err(DivertError::ClosestPointOnPolyFailure(_))
+        ));
     }
 
+    #[cfg(feature = "tracing")]
     #[test]
-    fn test_query_filter() {
-        let filter = QueryFilter::new();
-        assert!(filter.is_ok());
+    fn test_find_path_tracing_span() {
+        use std::sync::{Arc, Mutex};
+        use tracing::{span, subscriber, Event, Metadata, Subscriber};
 
-        let mut filter = filter.unwrap();
+        /// Records the name of every span created while it is the default subscriber
+        struct SpanRecorder(Arc<Mutex<Vec<&'static str>>>);
 
-        filter.set_include_flags(1);
-        assert_eq!(filter.get_include_flags(), 1);
+        impl Subscriber for SpanRecorder {
+            fn enabled(&self, _: &Metadata) -> bool {
+                true
+            }
 
-        filter.set_exclude_flags(1);
-        assert_eq!(filter.get_exclude_flags(), 1);
+            fn new_span(&self, span: &span::Attributes) -> span::Id {
+                let mut spans = self.0.lock().unwrap();
+                spans.push(span.metadata().name());
+                span::Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, _: &span::Id, _: &span::Record) {}
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+            fn event(&self, _: &Event) {}
+            fn enter(&self, _: &span::Id) {}
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["..."]);
+        let mut nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(2, 0);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+
+        let spans = Arc::new(Mutex::new(Vec::new()));
+        subscriber::with_default(SpanRecorder(spans.clone()), || {
+            nav_mesh_query
+                .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+                .unwrap();
+            nav_mesh_query
+                .find_path_detailed(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+                .unwrap();
+            nav_mesh_query
+                .find_path_growing(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+                .unwrap();
+            nav_mesh_query
+                .find_path_retry(
+                    start_ref, end_ref, &start_pos, &end_pos, &filter, 16, 100, 100,
+                )
+                .unwrap();
+        });
+
+        // Every entry point gets its own span, around the find_path_with_status span it runs
+        assert_eq!(
+            *spans.lock().unwrap(),
+            vec![
+                "find_path",
+                "find_path_with_status",
+                "find_path_detailed",
+                "find_path_with_status",
+                "find_path_growing",
+                "find_path_with_status",
+                "find_path_retry",
+                "find_path_with_status",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shared_nav_mesh_across_threads() {
+        let shared = fixtures::nav_mesh_with_tile(&["....", ".w..", "...."]).into_shared();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let handles: Vec<_> = (0..4)
+            .map(|x| {
+                let shared = shared.clone();
+                std::thread::spawn(move || {
+                    let nav_mesh_query = NavMeshQuery::new_shared(&shared, 100).unwrap();
+                    let filter = QueryFilter::new().unwrap();
+                    let (poly_ref, _) = nav_mesh_query
+                        .find_nearest_poly(&fixtures::cell_center(x, 1), &extents, &filter)
+                        .unwrap();
+
+                    shared.get_poly_area(poly_ref).unwrap()
+                })
+            })
+            .collect();
+
+        let areas: Vec<u8> = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+        assert_eq!(
+            areas,
+            vec![
+                fixtures::GROUND_AREA,
+                fixtures::WATER_AREA,
+                fixtures::GROUND_AREA,
+                fixtures::GROUND_AREA
+            ]
+        );
+
+        let nav_mesh_query = NavMeshQuery::new_shared(&shared, 100).unwrap();
+        let shared = shared.try_unwrap().err().unwrap();
+        drop(nav_mesh_query);
+        let nav_mesh = shared.try_unwrap().ok().unwrap();
+        assert_eq!(nav_mesh.tiles().count(), 1);
+    }
+
+    #[cfg(feature = "mint")]
+    #[test]
+    fn test_vector_mint_round_trip() {
+        let vector = Vector::new(1.0, 2.0, 3.0);
+
+        let mint_vector: mint::Vector3<f64> = vector.into();
+        assert_eq!(mint_vector, mint::Vector3::from([1.0, 2.0, 3.0]));
+        assert_eq!(Vector::from(mint_vector), vector);
+
+        let mint_point: mint::Point3<f64> = vector.into();
+        assert_eq!(mint_point, mint::Point3::from([1.0, 2.0, 3.0]));
+        assert_eq!(Vector::from(mint_point), vector);
     }
 }
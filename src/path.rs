@@ -0,0 +1,718 @@
+use crate::{
+    binding::*, buffer_capacity, check_finite, flag_coincident_start, get_poly_area,
+    get_poly_vertices, get_tile_and_poly_by_ref, input_len, result_len, DivertError, DivertResult,
+    DtStraightPathFlags, NavMeshQuery, PolyRef, QueryFilter, Vector,
+};
+
+/// Straight path results a NavMeshQuery keeps across find_straight_path_buffered calls
+/// The buffers grow to the largest max_path requested and are then reused, so steering loops
+/// querying every iteration don't allocate
+#[derive(Debug, Default)]
+pub struct StraightPathBuffers {
+    points: Vec<Vector>,
+    flags: Vec<DtStraightPathFlags>,
+    polys: Vec<PolyRef>,
+}
+
+impl StraightPathBuffers {
+    /// Waypoint positions of the last query
+    pub fn points(&self) -> &[Vector] {
+        &self.points
+    }
+
+    /// Waypoint flags of the last query
+    pub fn flags(&self) -> &[DtStraightPathFlags] {
+        &self.flags
+    }
+
+    /// Polys entered at each waypoint of the last query
+    pub fn polys(&self) -> &[PolyRef] {
+        &self.polys
+    }
+
+    /// Number of waypoints of the last query
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Whether the last query produced no waypoints
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Number of waypoints the buffers can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        self.points
+            .capacity()
+            .min(self.flags.capacity())
+            .min(self.polys.capacity())
+    }
+
+    /// Iterates the waypoints of the last query as find_straight_path's (position, flags, poly)
+    pub fn iter(&self) -> impl Iterator<Item = (Vector, DtStraightPathFlags, PolyRef)> + '_ {
+        self.points
+            .iter()
+            .zip(&self.flags)
+            .zip(&self.polys)
+            .map(|((position, flags), poly_ref)| (*position, *flags, *poly_ref))
+    }
+
+    fn clear_with_capacity(&mut self, capacity: usize) {
+        self.points.clear();
+        self.flags.clear();
+        self.polys.clear();
+        self.points.reserve(capacity);
+        self.flags.reserve(capacity);
+        self.polys.reserve(capacity);
+    }
+}
+
+/// Polygon path found by find_path_detailed, along with whether it reaches the end poly
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathResult {
+    /// Polys from the start poly towards the end poly
+    pub path: Vec<PolyRef>,
+    /// False when the path is a best effort, stopping short of the end poly or truncated to max_path
+    pub complete: bool,
+    /// Successful status of the query, carrying its detail bits
+    pub status: DtStatus,
+}
+
+/// Sums the distance between consecutive straight path waypoints
+/// Empty and single point paths have a length of 0.0
+pub fn straight_path_length(path: &[(Vector, DtStraightPathFlags, PolyRef)]) -> f64 {
+    path.windows(2)
+        .map(|segment| segment[0].0.distance(segment[1].0))
+        .sum()
+}
+
+/// Flattens straight path waypoints into an x, y, z vertex buffer for rendering
+/// Positions keep Detour's y-up order and are narrowed to f32, as vertex buffers expect
+pub fn straight_path_to_vertices(path: &[(Vector, DtStraightPathFlags, PolyRef)]) -> Vec<f32> {
+    path.iter()
+        .flat_map(|(position, _, _)| position.as_vec3().to_array())
+        .collect()
+}
+
+/// Removes consecutive duplicate poly refs from a corridor in place
+/// Merging visited polys into a corridor can repeat a poly, which would otherwise show up as
+/// a zero length portal in straight path and portal queries
+pub fn dedup_corridor(path: &mut Vec<PolyRef>) {
+    path.dedup();
+}
+
+impl<'a> NavMeshQuery<'a> {
+    /// Generates the waypoints from start to end in one call
+    /// Snaps both positions to their nearest polys within extents, finds the poly path between
+    /// them, and extracts its straight path, each step limited to max_path entries
+    /// Returns an empty Vec if either position has no poly within extents
+    /// Errors if any ffi function returns a failed DtStatus
+    pub fn find_waypoints(
+        &self,
+        start: &Vector,
+        end: &Vector,
+        extents: &Vector,
+        filter: &QueryFilter,
+        max_path: i32,
+    ) -> DivertResult<Vec<Vector>> {
+        let (start_ref, start_pos) = self.find_nearest_poly(start, extents, filter)?;
+        let (end_ref, end_pos) = self.find_nearest_poly(end, extents, filter)?;
+        if start_ref == PolyRef::default() || end_ref == PolyRef::default() {
+            return Ok(Vec::new());
+        }
+
+        let poly_path =
+            self.find_path(start_ref, end_ref, &start_pos, &end_pos, filter, max_path)?;
+        let straight_path = self.find_straight_path(
+            &start_pos,
+            &end_pos,
+            &poly_path,
+            max_path,
+            StraightPathOptions::empty(),
+        )?;
+
+        Ok(straight_path
+            .into_iter()
+            .map(|(position, _, _)| position)
+            .collect())
+    }
+
+    /// Generates a (poly, position) path like find_straight_path, into the query's reused buffers
+    /// The results are borrowed from the NavMeshQuery until the next call
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn find_straight_path_buffered(
+        &mut self,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        poly_path: &[PolyRef],
+        max_path: i32,
+        options: StraightPathOptions,
+    ) -> DivertResult<&StraightPathBuffers> {
+        check_finite(start_pos, "start_pos must be finite")?;
+        check_finite(end_pos, "end_pos must be finite")?;
+
+        let mut straight_path_count = 0;
+        let capacity = buffer_capacity(max_path)?;
+        let poly_path_size = input_len(poly_path.len())?;
+        let buffers = &mut self.straight_path_buffers;
+        buffers.clear_with_capacity(capacity);
+
+        let find_path_status = unsafe {
+            dtNavMeshQuery_findStraightPath(
+                self.handle,
+                start_pos,
+                end_pos,
+                poly_path.as_ptr(),
+                poly_path_size,
+                buffers.points.as_mut_ptr(),
+                buffers.flags.as_mut_ptr(),
+                buffers.polys.as_mut_ptr(),
+                &mut straight_path_count,
+                max_path,
+                options,
+            )
+        };
+
+        let path_count = result_len(straight_path_count, capacity);
+        unsafe {
+            buffers.points.set_len(path_count);
+            buffers.flags.set_len(path_count);
+            buffers.polys.set_len(path_count);
+        }
+        flag_coincident_start(&mut buffers.flags, &mut buffers.polys, poly_path);
+
+        if find_path_status.is_failed() {
+            return Err(DivertError::FindStraightPathFailure(find_path_status));
+        }
+
+        Ok(&self.straight_path_buffers)
+    }
+
+    /// Attaches the (start, end) endpoints of the off-mesh connection each OFFMESH_CONNECTION
+    /// flagged waypoint of a straight path enters, so the caller can animate the traversal
+    /// Bidirectional connections are oriented so start is the endpoint the waypoint is at
+    /// Other waypoints get None
+    /// Errors if a flagged waypoint's PolyRef isn't an off-mesh connection,
+    /// or ffi function returns a failed DtStatus
+    #[allow(clippy::type_complexity)]
+    pub fn expand_offmesh_connections(
+        &self,
+        path: &[(Vector, DtStraightPathFlags, PolyRef)],
+    ) -> DivertResult<
+        Vec<(
+            Vector,
+            DtStraightPathFlags,
+            PolyRef,
+            Option<(Vector, Vector)>,
+        )>,
+    > {
+        let nav_mesh = unsafe { dtNavMeshQuery_getAttachedNavMesh(self.handle) };
+
+        path.iter()
+            .map(|&(position, flags, poly_ref)| {
+                if !flags.contains(DtStraightPathFlags::OFFMESH_CONNECTION) {
+                    return Ok((position, flags, poly_ref, None));
+                }
+
+                let not_off_mesh_connection = DivertError::InvalidParam(
+                    "flagged waypoint does not reference an off-mesh connection",
+                );
+                let (_, poly) = get_tile_and_poly_by_ref(nav_mesh, poly_ref)?;
+                if unsafe { dtPoly_getType(poly) } != DT_POLYTYPE_OFFMESH_CONNECTION {
+                    return Err(not_off_mesh_connection);
+                }
+
+                let (start, end) = match get_poly_vertices(nav_mesh, poly_ref)?[..] {
+                    [start, end] => (start, end),
+                    _ => return Err(not_off_mesh_connection),
+                };
+                let end_points =
+                    if position.distance_squared(end) < position.distance_squared(start) {
+                        (end, start)
+                    } else {
+                        (start, end)
+                    };
+
+                Ok((position, flags, poly_ref, Some(end_points)))
+            })
+            .collect()
+    }
+
+    /// Expands a poly path into the points where it enters each poly
+    /// The first poly is entered at start, each following poly at the mid point of the portal
+    /// edge it is entered through, and a final (last poly, end) pair closes the corridor
+    /// Errors if poly_path is empty, consecutive polys aren't linked,
+    /// or ffi function returns a failed DtStatus
+    pub fn corridor_crossings(
+        &self,
+        poly_path: &[PolyRef],
+        start: &Vector,
+        end: &Vector,
+    ) -> DivertResult<Vec<(PolyRef, Vector)>> {
+        let (first, last) = match (poly_path.first(), poly_path.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Err(DivertError::InvalidParam("poly_path must not be empty")),
+        };
+
+        let mut crossings = Vec::with_capacity(poly_path.len() + 1);
+        crossings.push((first, *start));
+        for pair in poly_path.windows(2) {
+            crossings.push((pair[1], self.get_edge_mid_point(pair[0], pair[1])?));
+        }
+        crossings.push((last, *end));
+
+        Ok(crossings)
+    }
+
+    /// Sums the traversal cost of a poly path the way Detour's default filter scores it
+    /// Each poly contributes the distance between the points it is entered and left at, as in
+    /// corridor_crossings, times the filter's cost of the poly's area
+    /// Errors if path is empty, consecutive polys aren't linked,
+    /// or ffi function returns a failed DtStatus
+    pub fn path_cost(
+        &self,
+        path: &[PolyRef],
+        start: &Vector,
+        end: &Vector,
+        filter: &QueryFilter,
+    ) -> DivertResult<f64> {
+        let nav_mesh = unsafe { dtNavMeshQuery_getAttachedNavMesh(self.handle) };
+
+        let mut cost = 0.0;
+        for segment in self.corridor_crossings(path, start, end)?.windows(2) {
+            let (poly_ref, entry) = segment[0];
+            let area_cost = filter.get_area_cost(get_poly_area(nav_mesh, poly_ref)?)?;
+            cost += entry.distance(segment[1].1) * area_cost;
+        }
+
+        Ok(cost)
+    }
+
+    /// Picks the next point to steer towards along a polygon path, as in Detour's sample getSteerTarget
+    /// Returns the first straight path point outside the cylinder of radius and height around start,
+    /// or the first off-mesh connection start, whichever comes first
+    /// Returns None if every point within max_steer_points lies within the cylinder
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn steer_target(
+        &self,
+        poly_path: &[PolyRef],
+        start: &Vector,
+        end: &Vector,
+        radius: f64,
+        height: f64,
+        max_steer_points: i32,
+    ) -> DivertResult<Option<(Vector, DtStraightPathFlags, PolyRef)>> {
+        let steer_points = self.find_straight_path(
+            start,
+            end,
+            poly_path,
+            max_steer_points,
+            StraightPathOptions::empty(),
+        )?;
+
+        Ok(steer_points.into_iter().find(|(point, flags, _)| {
+            let delta = *point - *start;
+            flags.contains(DtStraightPathFlags::OFFMESH_CONNECTION)
+                || delta.x * delta.x + delta.z * delta.z >= radius * radius
+                || delta.y.abs() >= height
+        }))
+    }
+
+    /// Generates a straight path with an extra waypoint wherever the path crosses into another area
+    /// Unlike DT_STRAIGHTPATH_ALL_CROSSINGS, edges between polys of the same area add no waypoint
+    /// Each waypoint is paired with the area id of the poly it enters, so per-segment area costs
+    /// can be integrated, the end waypoint keeps the area of the last poly
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn find_area_crossing_path(
+        &self,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        poly_path: &[PolyRef],
+        max_path: i32,
+    ) -> DivertResult<Vec<(Vector, u8)>> {
+        let straight_path = self.find_straight_path(
+            start_pos,
+            end_pos,
+            poly_path,
+            max_path,
+            StraightPathOptions::AREA_CROSSINGS,
+        )?;
+
+        let nav_mesh = unsafe { dtNavMeshQuery_getAttachedNavMesh(self.handle) };
+        let mut area = 0;
+        let mut path = Vec::with_capacity(straight_path.len());
+        for (position, _, poly_ref) in straight_path {
+            // The end waypoint doesn't enter a poly and reports a null PolyRef
+            if poly_ref != PolyRef::default() {
+                area = get_poly_area(nav_mesh, poly_ref)?;
+            }
+            path.push((position, area));
+        }
+
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dedup_corridor, straight_path_length, straight_path_to_vertices};
+    use crate::{
+        fixtures, DivertError, DtStraightPathFlags, NavMesh, NavMeshCreateParams, NavMeshQuery,
+        OffMeshConnectionDirection, PolyRef, QueryFilter, StraightPathOptions, Vector,
+        DT_STRAIGHTPATH_ALL_CROSSINGS, DT_STRAIGHTPATH_AREA_CROSSINGS,
+    };
+
+    #[test]
+    fn test_straight_path_length() {
+        assert_eq!(straight_path_length(&[]), 0.0);
+
+        let start = (
+            Vector::new(0.0, 0.0, 0.0),
+            DtStraightPathFlags::START,
+            PolyRef::from_raw(1),
+        );
+        assert_eq!(straight_path_length(&[start]), 0.0);
+
+        let corner = (
+            Vector::new(3.0, 0.0, 0.0),
+            DtStraightPathFlags::empty(),
+            PolyRef::from_raw(2),
+        );
+        let end = (
+            Vector::new(3.0, 0.0, 4.0),
+            DtStraightPathFlags::END,
+            PolyRef::from_raw(3),
+        );
+        assert_eq!(straight_path_length(&[start, corner, end]), 7.0);
+    }
+
+    #[test]
+    fn test_straight_path_to_vertices() {
+        assert!(straight_path_to_vertices(&[]).is_empty());
+
+        let path = [
+            (
+                Vector::new(1.0, 2.0, 3.0),
+                DtStraightPathFlags::START,
+                PolyRef::from_raw(1),
+            ),
+            (
+                Vector::new(4.5, 0.0, -6.0),
+                DtStraightPathFlags::END,
+                PolyRef::from_raw(2),
+            ),
+        ];
+        let vertices = straight_path_to_vertices(&path);
+        assert_eq!(vertices.len(), path.len() * 3);
+        assert_eq!(vertices, vec![1.0, 2.0, 3.0, 4.5, 0.0, -6.0]);
+    }
+
+    #[test]
+    fn test_dedup_corridor() {
+        let [a, b, c] = [1, 2, 3].map(PolyRef::from_raw);
+        let mut path = vec![a, a, b, c, c, c, b];
+
+        dedup_corridor(&mut path);
+        assert_eq!(path, vec![a, b, c, b]);
+    }
+
+    #[test]
+    fn test_corridor_crossings() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["..."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(2, 0);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+        let poly_path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+            .unwrap();
+        assert_eq!(poly_path.len(), 3);
+
+        let crossings = nav_mesh_query
+            .corridor_crossings(&poly_path, &start_pos, &end_pos)
+            .unwrap();
+        assert_eq!(
+            crossings,
+            vec![
+                (poly_path[0], start_pos),
+                (poly_path[1], Vector::new(4.0, 0.0, 2.0)),
+                (poly_path[2], Vector::new(8.0, 0.0, 2.0)),
+                (poly_path[2], end_pos),
+            ]
+        );
+
+        assert!(nav_mesh_query
+            .corridor_crossings(&[poly_path[0], poly_path[2]], &start_pos, &end_pos)
+            .is_err());
+    }
+
+    #[test]
+    fn test_path_cost() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...", "www", "..."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let mut filter = QueryFilter::new().unwrap();
+        filter.set_area_cost(fixtures::WATER_AREA, 10.0).unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let poly_at = |x, z| {
+            nav_mesh_query
+                .find_nearest_poly(&fixtures::cell_center(x, z), &extents, &filter)
+                .unwrap()
+                .0
+        };
+        let start = fixtures::cell_center(0, 0);
+        let end = fixtures::cell_center(2, 0);
+
+        let direct = [poly_at(0, 0), poly_at(1, 0), poly_at(2, 0)];
+        let direct_cost = nav_mesh_query
+            .path_cost(&direct, &start, &end, &filter)
+            .unwrap();
+        assert!((direct_cost - start.distance(end)).abs() < 1e-9);
+
+        let through_water = [
+            poly_at(0, 0),
+            poly_at(0, 1),
+            poly_at(1, 1),
+            poly_at(2, 1),
+            poly_at(2, 0),
+        ];
+        let water_cost = nav_mesh_query
+            .path_cost(&through_water, &start, &end, &filter)
+            .unwrap();
+        assert!(water_cost > direct_cost);
+
+        assert!(matches!(
+            nav_mesh_query.path_cost(&[], &start, &end, &filter),
+            Err(DivertError::InvalidParam(_))
+        ));
+    }
+
+    #[test]
+    fn test_find_waypoints() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...", "#..", "..."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let start = fixtures::cell_center(0, 0);
+        let end = fixtures::cell_center(0, 2);
+        let waypoints = nav_mesh_query
+            .find_waypoints(&start, &end, &extents, &filter, 16)
+            .unwrap();
+        assert!(waypoints.len() > 2);
+        assert_eq!(waypoints.first(), Some(&start));
+        assert_eq!(waypoints.last(), Some(&end));
+
+        let off_mesh = Vector::new(-10.0, 0.0, -10.0);
+        assert!(nav_mesh_query
+            .find_waypoints(&off_mesh, &end, &extents, &filter, 16)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_find_straight_path_buffered() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...", "#..", "..."]);
+        let mut nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let start = fixtures::cell_center(0, 0);
+        let end = fixtures::cell_center(0, 2);
+        let (start_ref, end_ref) = fixtures::endpoints(&nav_mesh_query, &start, &end, &filter);
+        let poly_path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start, &end, &filter, 16)
+            .unwrap();
+        let expected = nav_mesh_query
+            .find_straight_path(&start, &end, &poly_path, 16, StraightPathOptions::empty())
+            .unwrap();
+
+        let buffers = nav_mesh_query
+            .find_straight_path_buffered(&start, &end, &poly_path, 16, StraightPathOptions::empty())
+            .unwrap();
+        assert_eq!(buffers.iter().collect::<Vec<_>>(), expected);
+        let capacity = buffers.capacity();
+        let points = buffers.points().as_ptr();
+
+        let reversed_path: Vec<_> = poly_path.iter().rev().copied().collect();
+        let buffers = nav_mesh_query
+            .find_straight_path_buffered(
+                &end,
+                &start,
+                &reversed_path,
+                16,
+                StraightPathOptions::empty(),
+            )
+            .unwrap();
+        assert_eq!(buffers.capacity(), capacity);
+        assert_eq!(buffers.points().as_ptr(), points);
+        assert_eq!(buffers.points().first(), Some(&end));
+    }
+
+    #[test]
+    fn test_expand_offmesh_connections() {
+        let start = fixtures::cell_center(1, 0);
+        let end = fixtures::cell_center(3, 0);
+        let tile_data = fixtures::create_tile_data_with(
+            0,
+            0,
+            &["..#.."],
+            &NavMeshCreateParams {
+                off_mesh_con_verts: &[[start.x, start.y, start.z, end.x, end.y, end.z]],
+                off_mesh_con_rad: &[1.0],
+                off_mesh_con_flags: &[fixtures::GROUND_FLAG],
+                off_mesh_con_areas: &[fixtures::GROUND_AREA],
+                off_mesh_con_dir: &[OffMeshConnectionDirection::BiDirectional],
+                off_mesh_con_user_id: &[1],
+                ..Default::default()
+            },
+        );
+
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(5, 1, 1)).unwrap();
+        nav_mesh.add_tile(tile_data).unwrap();
+
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        for (from, to) in [(0, 4), (4, 0)] {
+            let start_pos = fixtures::cell_center(from, 0);
+            let end_pos = fixtures::cell_center(to, 0);
+            let (start_ref, end_ref) =
+                fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+            let poly_path = nav_mesh_query
+                .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+                .unwrap();
+            let straight_path = nav_mesh_query
+                .find_straight_path(
+                    &start_pos,
+                    &end_pos,
+                    &poly_path,
+                    16,
+                    StraightPathOptions::empty(),
+                )
+                .unwrap();
+
+            let expanded = nav_mesh_query
+                .expand_offmesh_connections(&straight_path)
+                .unwrap();
+            assert_eq!(expanded.len(), straight_path.len());
+
+            let connections: Vec<_> = expanded
+                .iter()
+                .filter_map(|(_, _, _, end_points)| *end_points)
+                .collect();
+            let expected = if from < to {
+                (start, end)
+            } else {
+                (end, start)
+            };
+            assert_eq!(connections, vec![expected]);
+        }
+
+        // Waypoints flagged as off-mesh connections must reference one
+        let (ground_ref, _) = nav_mesh_query
+            .find_nearest_poly(&fixtures::cell_center(0, 0), &extents, &filter)
+            .unwrap();
+        let flagged_ground = [(
+            fixtures::cell_center(0, 0),
+            DtStraightPathFlags::OFFMESH_CONNECTION,
+            ground_ref,
+        )];
+        assert!(matches!(
+            nav_mesh_query.expand_offmesh_connections(&flagged_ground),
+            Err(DivertError::InvalidParam(_))
+        ));
+    }
+
+    #[test]
+    fn test_steer_target() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["....."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(4, 0);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+        let poly_path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+            .unwrap();
+
+        let (steer_pos, steer_flags, _) = nav_mesh_query
+            .steer_target(&poly_path, &start_pos, &end_pos, 0.5, 2.0, 3)
+            .unwrap()
+            .unwrap();
+        assert!(steer_pos.x > start_pos.x);
+        assert_eq!(steer_pos, end_pos);
+        assert!(steer_flags.contains(DtStraightPathFlags::END));
+
+        let arrived = nav_mesh_query
+            .steer_target(&[end_ref], &end_pos, &end_pos, 0.5, 2.0, 3)
+            .unwrap();
+        assert!(arrived.is_none());
+    }
+
+    #[test]
+    fn test_find_area_crossing_path() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...www"]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(5, 0);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+        let poly_path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+            .unwrap();
+
+        let path = nav_mesh_query
+            .find_area_crossing_path(&start_pos, &end_pos, &poly_path, 16)
+            .unwrap();
+
+        let crossing = Vector::new(3.0 * fixtures::CELL_SIZE as f64, 0.0, start_pos.z);
+        assert_eq!(
+            path,
+            vec![
+                (start_pos, fixtures::GROUND_AREA),
+                (crossing, fixtures::WATER_AREA),
+                (end_pos, fixtures::WATER_AREA),
+            ]
+        );
+
+        let all_crossings = nav_mesh_query
+            .find_straight_path(
+                &start_pos,
+                &end_pos,
+                &poly_path,
+                16,
+                StraightPathOptions::ALL_CROSSINGS,
+            )
+            .unwrap();
+        assert_eq!(all_crossings.len(), poly_path.len() + 1);
+
+        let default = nav_mesh_query
+            .find_straight_path(
+                &start_pos,
+                &end_pos,
+                &poly_path,
+                16,
+                StraightPathOptions::empty(),
+            )
+            .unwrap();
+        assert!(all_crossings.len() > default.len());
+
+        assert_eq!(
+            StraightPathOptions::from_bits(DT_STRAIGHTPATH_ALL_CROSSINGS),
+            Some(StraightPathOptions::ALL_CROSSINGS)
+        );
+        assert_eq!(
+            StraightPathOptions::AREA_CROSSINGS.bits(),
+            DT_STRAIGHTPATH_AREA_CROSSINGS
+        );
+        assert_eq!(StraightPathOptions::from_bits(0x04), None);
+    }
+}
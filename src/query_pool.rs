@@ -0,0 +1,197 @@
+use std::{
+    ops,
+    sync::{Condvar, Mutex, MutexGuard},
+};
+
+use crate::{DivertError, DivertResult, NavMeshQuery, NavMeshQueryConfig, SharedNavMesh};
+
+/// Fixed set of NavMeshQuery instances over one SharedNavMesh, rented out per task
+/// Queries are initialized once up front, amortizing the dtNavMeshQuery->init cost across tasks
+/// The pool is Sync, so it can be shared by reference between threads
+pub struct NavMeshQueryPool {
+    nav_mesh: SharedNavMesh,
+    size: usize,
+    queries: Mutex<Vec<NavMeshQuery<'static>>>,
+    returned: Condvar,
+}
+
+/// A NavMeshQuery rented from a NavMeshQueryPool, returned to the pool when dropped
+/// Settings changed through the guard (default extents, heuristic scale, ...) stay on the query
+pub struct QueryGuard<'p> {
+    pool: &'p NavMeshQueryPool,
+    query: Option<NavMeshQuery<'static>>,
+}
+
+impl NavMeshQueryPool {
+    /// Creates size queries on nav_mesh, each configured by config
+    /// Errors if size is zero, or creating any of the queries fails
+    pub fn new(
+        nav_mesh: &SharedNavMesh,
+        size: usize,
+        config: &NavMeshQueryConfig,
+    ) -> DivertResult<Self> {
+        if size == 0 {
+            return Err(DivertError::InvalidParam("size must not be zero"));
+        }
+
+        let queries = (0..size)
+            .map(|_| {
+                let mut query = NavMeshQuery::new_shared(nav_mesh, config.max_nodes)?;
+                query.set_default_extents(config.default_extents);
+                query.set_heuristic_scale(config.heuristic_scale)?;
+                Ok(query)
+            })
+            .collect::<DivertResult<Vec<_>>>()?;
+
+        Ok(Self {
+            nav_mesh: nav_mesh.clone(),
+            size,
+            queries: Mutex::new(queries),
+            returned: Condvar::new(),
+        })
+    }
+
+    /// Retrieves the nav mesh the pooled queries run on
+    pub fn nav_mesh(&self) -> &SharedNavMesh {
+        &self.nav_mesh
+    }
+
+    /// Retrieves the total number of queries in the pool, rented or not
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Retrieves the number of queries currently available to acquire
+    pub fn available(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Rents a query, blocking until one is returned if all of them are in use
+    pub fn acquire(&self) -> QueryGuard<'_> {
+        let mut queries = self.lock();
+        loop {
+            if let Some(query) = queries.pop() {
+                return self.guard(query);
+            }
+            queries = self
+                .returned
+                .wait(queries)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Rents a query, returning None instead of blocking if all of them are in use
+    pub fn try_acquire(&self) -> Option<QueryGuard<'_>> {
+        self.lock().pop().map(|query| self.guard(query))
+    }
+
+    fn guard(&self, query: NavMeshQuery<'static>) -> QueryGuard<'_> {
+        QueryGuard {
+            pool: self,
+            query: Some(query),
+        }
+    }
+
+    // A panic while holding the lock can't leave the Vec half modified, so poisoning is ignored
+    fn lock(&self) -> MutexGuard<'_, Vec<NavMeshQuery<'static>>> {
+        self.queries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ops::Deref for QueryGuard<'_> {
+    type Target = NavMeshQuery<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        self.query.as_ref().unwrap()
+    }
+}
+
+impl ops::DerefMut for QueryGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.query.as_mut().unwrap()
+    }
+}
+
+impl Drop for QueryGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(query) = self.query.take() {
+            self.pool.lock().push(query);
+            self.pool.returned.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NavMeshQueryPool;
+    use crate::{fixtures, DivertError, NavMeshQueryConfig, QueryFilter, Vector};
+
+    #[test]
+    fn test_query_pool_parallel_find_path() {
+        let shared = fixtures::nav_mesh_with_tile(&[".....", ".###.", "....."]).into_shared();
+        let config = NavMeshQueryConfig {
+            max_nodes: 100,
+            default_extents: Vector::new(1.0, 2.0, 1.0),
+            heuristic_scale: 1.0,
+        };
+        let pool = NavMeshQueryPool::new(&shared, 2, &config).unwrap();
+        assert_eq!(pool.size(), 2);
+        assert_eq!(pool.available(), 2);
+
+        let paths: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let query = pool.acquire();
+                        let filter = QueryFilter::new().unwrap();
+                        let start_pos = fixtures::cell_center(0, 1);
+                        let end_pos = fixtures::cell_center(4, 1);
+                        let (start_ref, _) = query
+                            .find_nearest_poly_default(&start_pos, &filter)
+                            .unwrap();
+                        let (end_ref, _) =
+                            query.find_nearest_poly_default(&end_pos, &filter).unwrap();
+
+                        query
+                            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 32)
+                            .unwrap()
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        assert!(paths[0].len() > 1);
+        assert!(paths.iter().all(|path| *path == paths[0]));
+        assert_eq!(pool.available(), 2);
+    }
+
+    #[test]
+    fn test_query_pool_exhausted() {
+        let shared = fixtures::nav_mesh_with_tile(&["..."]).into_shared();
+        let config = NavMeshQueryConfig {
+            max_nodes: 100,
+            default_extents: Vector::new(1.0, 2.0, 1.0),
+            heuristic_scale: 1.0,
+        };
+        let pool = NavMeshQueryPool::new(&shared, 1, &config).unwrap();
+        assert_eq!(pool.nav_mesh().tiles().count(), 1);
+
+        let query = pool.try_acquire().unwrap();
+        assert_eq!(query.config(), config);
+        assert!(pool.try_acquire().is_none());
+        drop(query);
+        assert!(pool.try_acquire().is_some());
+
+        assert!(matches!(
+            NavMeshQueryPool::new(&shared, 0, &config),
+            Err(DivertError::InvalidParam(_))
+        ));
+    }
+}
@@ -0,0 +1,217 @@
+use std::marker;
+
+use crate::{
+    binding::*, buffer_capacity, check_finite, result_len, DivertError, DivertResult, NavMeshQuery,
+    PolyRef, QueryFilter, Vector,
+};
+
+/// State of a sliced path query after an update
+///
+/// A sliced path query moves through the following states:
+/// - NavMeshQuery::init_sliced_find_path starts the query, or errors if the start/end are invalid
+/// - SlicedFindPath::update returns InProgress while the search has nodes left to expand
+/// - SlicedFindPath::update returns Completed once the search has finished
+/// - SlicedFindPath::finalize retrieves the path once Completed
+/// - SlicedFindPath::cancel abandons the search at any point, without retrieving a path
+///
+/// Any failed DtStatus aborts the query and is surfaced as a DivertError instead of a state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlicedPathState {
+    /// The search is not finished, keep calling update
+    InProgress,
+    /// The search is finished, call finalize to retrieve the path
+    Completed,
+}
+
+/// An in-progress sliced path query
+/// Mutably borrows the NavMeshQuery, as any other query would reset the search state,
+/// and borrows the QueryFilter, as Detour references it until the search is finalized
+pub struct SlicedFindPath<'q> {
+    handle: *mut DtNavMeshQuery,
+    _phantom: marker::PhantomData<(&'q mut DtNavMeshQuery, &'q DtQueryFilter)>,
+}
+
+impl<'a> NavMeshQuery<'a> {
+    /// Starts a sliced path query from one (poly, position) to another (poly, position)
+    /// The search is advanced with SlicedFindPath::update, spreading its cost over several calls
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn init_sliced_find_path<'q>(
+        &'q mut self,
+        start_ref: PolyRef,
+        end_ref: PolyRef,
+        start_pos: &Vector,
+        end_pos: &Vector,
+        filter: &'q QueryFilter,
+        options: u32,
+    ) -> DivertResult<SlicedFindPath<'q>> {
+        check_finite(start_pos, "start_pos must be finite")?;
+        check_finite(end_pos, "end_pos must be finite")?;
+
+        let init_status = unsafe {
+            dtNavMeshQuery_initSlicedFindPath(
+                self.handle,
+                start_ref,
+                end_ref,
+                start_pos,
+                end_pos,
+                filter.handle,
+                options,
+            )
+        };
+
+        if init_status.is_failed() {
+            return Err(DivertError::InitSlicedFindPathFailure(init_status));
+        }
+
+        Ok(SlicedFindPath {
+            handle: self.handle,
+            _phantom: marker::PhantomData,
+        })
+    }
+
+    /// Resets the query's sliced search state, abandoning any search that was started
+    /// A new sliced search can be started right away, without finalizing the old one
+    pub fn cancel_sliced_find_path(&mut self) {
+        unsafe { dtNavMeshQuery_cancelSlicedFindPath(self.handle) }
+    }
+}
+
+impl<'q> SlicedFindPath<'q> {
+    /// Performs up to max_iter search iterations
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn update(&mut self, max_iter: i32) -> DivertResult<SlicedPathState> {
+        let mut done_iters = 0;
+
+        let update_status =
+            unsafe { dtNavMeshQuery_updateSlicedFindPath(self.handle, max_iter, &mut done_iters) };
+
+        log::trace!("UpdateSlicedFindPathStatus: {:#?}", update_status);
+
+        if update_status.is_failed() {
+            return Err(DivertError::UpdateSlicedFindPathFailure(update_status));
+        }
+
+        if update_status.is_in_progress() {
+            Ok(SlicedPathState::InProgress)
+        } else {
+            Ok(SlicedPathState::Completed)
+        }
+    }
+
+    /// Abandons the search, for instance once its target is gone
+    /// Releases the NavMeshQuery and QueryFilter borrows without retrieving a path
+    pub fn cancel(self) {
+        unsafe { dtNavMeshQuery_cancelSlicedFindPath(self.handle) }
+    }
+
+    /// Retrieves the polygon path of a completed search
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn finalize(self, max_path: i32) -> DivertResult<Vec<PolyRef>> {
+        let mut path_count = 0;
+        let mut path: Vec<PolyRef> = Vec::with_capacity(buffer_capacity(max_path)?);
+
+        let finalize_status = unsafe {
+            dtNavMeshQuery_finalizeSlicedFindPath(
+                self.handle,
+                path.as_mut_ptr(),
+                &mut path_count,
+                max_path,
+            )
+        };
+
+        unsafe {
+            path.set_len(result_len(path_count, path.capacity()));
+        }
+
+        if finalize_status.is_failed() {
+            return Err(DivertError::FinalizeSlicedFindPathFailure(finalize_status));
+        }
+
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SlicedPathState;
+    use crate::{fixtures, NavMeshQuery, QueryFilter, Vector};
+
+    #[test]
+    fn test_sliced_find_path_state_transitions() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["......"]);
+        let mut nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let start_pos = fixtures::cell_center(0, 0);
+        let end_pos = fixtures::cell_center(5, 0);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+        let expected_path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+            .unwrap();
+
+        let mut sliced = nav_mesh_query
+            .init_sliced_find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 0)
+            .unwrap();
+
+        let mut in_progress_updates = 0;
+        while sliced.update(1).unwrap() == SlicedPathState::InProgress {
+            in_progress_updates += 1;
+            assert!(in_progress_updates < 100);
+        }
+        assert!(in_progress_updates > 0);
+
+        let path = sliced.finalize(16).unwrap();
+        assert_eq!(path, expected_path);
+    }
+
+    #[test]
+    fn test_sliced_find_path_cancel() {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["......", "......"]);
+        let mut nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+
+        let start_pos = fixtures::cell_center(0, 1);
+        let stale_pos = fixtures::cell_center(5, 1);
+        let end_pos = fixtures::cell_center(3, 0);
+        let (start_ref, _) = nav_mesh_query
+            .find_nearest_poly(&start_pos, &extents, &filter)
+            .unwrap();
+        let (stale_ref, _) = nav_mesh_query
+            .find_nearest_poly(&stale_pos, &extents, &filter)
+            .unwrap();
+        let (end_ref, _) = nav_mesh_query
+            .find_nearest_poly(&end_pos, &extents, &filter)
+            .unwrap();
+        let expected_path = nav_mesh_query
+            .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 16)
+            .unwrap();
+
+        let mut stale = nav_mesh_query
+            .init_sliced_find_path(start_ref, stale_ref, &start_pos, &stale_pos, &filter, 0)
+            .unwrap();
+        assert_eq!(stale.update(1).unwrap(), SlicedPathState::InProgress);
+        stale.cancel();
+
+        let mut sliced = nav_mesh_query
+            .init_sliced_find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 0)
+            .unwrap();
+        while sliced.update(1).unwrap() == SlicedPathState::InProgress {}
+        assert_eq!(sliced.finalize(16).unwrap(), expected_path);
+
+        // A search abandoned without cancel leaves Detour's sliced state behind
+        nav_mesh_query
+            .init_sliced_find_path(start_ref, stale_ref, &start_pos, &stale_pos, &filter, 0)
+            .unwrap()
+            .update(1)
+            .unwrap();
+        nav_mesh_query.cancel_sliced_find_path();
+
+        let mut sliced = nav_mesh_query
+            .init_sliced_find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 0)
+            .unwrap();
+        while sliced.update(1).unwrap() == SlicedPathState::InProgress {}
+        assert_eq!(sliced.finalize(16).unwrap(), expected_path);
+    }
+}
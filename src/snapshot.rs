@@ -0,0 +1,226 @@
+//! Snapshot format persisting a NavMesh's params and loaded tiles to a single stream
+//! All values are little endian:
+//! - magic b"DVNM" and format version (u32)
+//! - NavMeshParams: origin (3 * f64), tile_width (f64), tile_height (f64), max_tiles (i32), max_polys (i32)
+//! - tile count (u32), followed by each tile's data length (u32) and data bytes
+
+use std::{
+    io::{Read, Write},
+    mem, ptr,
+};
+
+use crate::{binding::*, DivertError, DivertResult, NavMesh, NavMeshParams};
+
+const SNAPSHOT_MAGIC: &[u8; 4] = b"DVNM";
+const SNAPSHOT_VERSION: u32 = 1;
+
+impl<'a> NavMesh<'a> {
+    /// Writes the nav mesh params and the data of every loaded tile to w
+    /// Errors if writing fails
+    pub fn save_snapshot(&self, mut w: impl Write) -> DivertResult<()> {
+        let params = unsafe { &*dtNavMesh_getParams(self.handle) };
+
+        w.write_all(SNAPSHOT_MAGIC)?;
+        w.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+        for value in params.origin {
+            w.write_all(&value.to_le_bytes())?;
+        }
+        w.write_all(&params.tile_width.to_le_bytes())?;
+        w.write_all(&params.tile_height.to_le_bytes())?;
+        w.write_all(&params.max_tiles.to_le_bytes())?;
+        w.write_all(&params.max_polys.to_le_bytes())?;
+
+        let tiles = self
+            .tiles()
+            .map(|tile_ref| self.tile_data(tile_ref))
+            .collect::<DivertResult<Vec<&[u8]>>>()?;
+
+        w.write_all(&(tiles.len() as u32).to_le_bytes())?;
+        for tile_data in tiles {
+            w.write_all(&(tile_data.len() as u32).to_le_bytes())?;
+            w.write_all(tile_data)?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates a NavMesh from a snapshot written by save_snapshot, adding each of its tiles
+    /// The loaded tiles match the snapshot, so none of them start out dirty
+    /// Each tile's length is checked against the size its header counts imply before the tile
+    /// reaches Detour, which trusts those counts
+    /// Errors if reading fails, the snapshot is malformed, or a tile can't be added
+    pub fn load_snapshot(mut r: impl Read) -> DivertResult<Self> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != SNAPSHOT_MAGIC {
+            return Err(DivertError::InvalidSnapshot("unrecognized magic"));
+        }
+
+        if read_u32(&mut r)? != SNAPSHOT_VERSION {
+            return Err(DivertError::InvalidSnapshot("unsupported version"));
+        }
+
+        let params = NavMeshParams {
+            origin: [read_f64(&mut r)?, read_f64(&mut r)?, read_f64(&mut r)?],
+            tile_width: read_f64(&mut r)?,
+            tile_height: read_f64(&mut r)?,
+            max_tiles: read_u32(&mut r)? as i32,
+            max_polys: read_u32(&mut r)? as i32,
+        };
+
+        let tile_count = read_u32(&mut r)?;
+        if tile_count > params.max_tiles as u32 {
+            return Err(DivertError::InvalidSnapshot("more tiles than max_tiles"));
+        }
+
+        let mut nav_mesh = NavMesh::new(&params)?;
+        for _ in 0..tile_count {
+            let data_size = read_u32(&mut r)? as usize;
+            let mut tile_data = Vec::new();
+            r.by_ref()
+                .take(data_size as u64)
+                .read_to_end(&mut tile_data)?;
+            if tile_data.len() != data_size {
+                return Err(DivertError::InvalidSnapshot("truncated tile data"));
+            }
+
+            check_tile_data_size(&tile_data)?;
+            nav_mesh.add_tile(tile_data)?;
+        }
+
+        nav_mesh.clear_dirty();
+        Ok(nav_mesh)
+    }
+}
+
+/// Errors with InvalidSnapshot unless tile_data is exactly as long as its header counts imply
+fn check_tile_data_size(tile_data: &[u8]) -> DivertResult<()> {
+    if tile_data.len() < mem::size_of::<DtMeshHeader>() {
+        return Err(DivertError::InvalidSnapshot(
+            "tile data is too short to hold a header",
+        ));
+    }
+
+    let header = unsafe { ptr::read_unaligned(tile_data.as_ptr().cast::<DtMeshHeader>()) };
+    let counts = [
+        header.poly_count(),
+        header.vert_count(),
+        header.max_link_count(),
+        header.detail_mesh_count(),
+        header.detail_vert_count(),
+        header.detail_tri_count(),
+        header.bv_node_count(),
+        header.off_mesh_con_count(),
+    ];
+    if counts.iter().any(|&count| count < 0) {
+        return Err(DivertError::InvalidSnapshot(
+            "tile header has a negative count",
+        ));
+    }
+
+    if unsafe { dtMeshHeader_getDataSize(&header) } != tile_data.len() {
+        return Err(DivertError::InvalidSnapshot(
+            "tile data length doesn't match its header counts",
+        ));
+    }
+
+    Ok(())
+}
+
+fn read_u32(r: &mut impl Read) -> DivertResult<u32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_f64(r: &mut impl Read) -> DivertResult<f64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{fixtures, DivertError, NavMesh, NavMeshQuery, QueryFilter};
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut nav_mesh = NavMesh::new(&fixtures::nav_mesh_params(3, 2, 4)).unwrap();
+        nav_mesh
+            .add_tile(fixtures::create_tile_data(0, 0, &["...", ".#."]))
+            .unwrap();
+        nav_mesh
+            .add_tile(fixtures::create_tile_data(1, 0, &["...", "..w"]))
+            .unwrap();
+
+        let mut snapshot = Vec::new();
+        nav_mesh.save_snapshot(&mut snapshot).unwrap();
+        let loaded = NavMesh::load_snapshot(snapshot.as_slice()).unwrap();
+        assert_eq!(loaded.tiles().count(), 2);
+        assert!(loaded.dirty_tiles().is_empty());
+
+        let find_path = |nav_mesh: &NavMesh| {
+            let nav_mesh_query = NavMeshQuery::new(nav_mesh, 100).unwrap();
+            let filter = QueryFilter::new().unwrap();
+
+            let start_pos = fixtures::cell_center(0, 1);
+            let end_pos = fixtures::cell_center(5, 1);
+            let (start_ref, end_ref) =
+                fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+            nav_mesh_query
+                .find_path(start_ref, end_ref, &start_pos, &end_pos, &filter, 32)
+                .unwrap()
+        };
+
+        let path = find_path(&nav_mesh);
+        assert!(path.len() > 1);
+        assert_eq!(find_path(&loaded), path);
+    }
+
+    #[test]
+    fn test_snapshot_invalid() {
+        assert!(matches!(
+            NavMesh::load_snapshot(&b"NOPE"[..]),
+            Err(DivertError::InvalidSnapshot(_))
+        ));
+
+        let mut snapshot = Vec::new();
+        fixtures::nav_mesh_with_tile(&[".."])
+            .save_snapshot(&mut snapshot)
+            .unwrap();
+        snapshot.truncate(snapshot.len() - 1);
+        assert!(NavMesh::load_snapshot(snapshot.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_corrupt_tile_counts() {
+        let mut snapshot = Vec::new();
+        fixtures::nav_mesh_with_tile(&["..", ".."])
+            .save_snapshot(&mut snapshot)
+            .unwrap();
+
+        // The tile data follows the snapshot header, the params, the tile count and its length,
+        // and its poly count follows magic, version, x, y, layer and user_id
+        let tile_offset = 4 + 4 + 5 * 8 + 4 + 4 + 4 + 4;
+        let poly_count_offset = tile_offset + 6 * 4;
+        let poly_count = i32::from_le_bytes(
+            snapshot[poly_count_offset..poly_count_offset + 4]
+                .try_into()
+                .unwrap(),
+        );
+        let corrupt = |poly_count: i32| {
+            let mut snapshot = snapshot.clone();
+            snapshot[poly_count_offset..poly_count_offset + 4]
+                .copy_from_slice(&poly_count.to_le_bytes());
+            NavMesh::load_snapshot(snapshot.as_slice())
+        };
+
+        assert!(corrupt(poly_count).is_ok());
+        for poly_count in [poly_count + 1, poly_count - 1, i32::MAX, -1] {
+            assert!(matches!(
+                corrupt(poly_count),
+                Err(DivertError::InvalidSnapshot(_))
+            ));
+        }
+    }
+}
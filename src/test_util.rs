@@ -0,0 +1,125 @@
+//! Assertion helpers for testing code built on top of divert
+//! PolyRefs differ between loads, so paths are compared by position rather than by reference
+
+use crate::{NavMeshQuery, PolyRef, Vector};
+
+/// Asserts a path starts near start_pos and ends near end_pos
+/// The closest point on the first and last poly must lie within tolerance of the matching endpoint
+/// Panics if the path is empty, or a closest point query fails
+pub fn assert_path_endpoints(
+    query: &NavMeshQuery,
+    path: &[PolyRef],
+    start_pos: &Vector,
+    end_pos: &Vector,
+    tolerance: f64,
+) {
+    let (first_poly, last_poly) = match (path.first(), path.last()) {
+        (Some(first_poly), Some(last_poly)) => (*first_poly, *last_poly),
+        _ => panic!("expected a non-empty path"),
+    };
+
+    assert_poly_near(query, first_poly, start_pos, tolerance, "start");
+    assert_poly_near(query, last_poly, end_pos, tolerance, "end");
+}
+
+fn assert_poly_near(
+    query: &NavMeshQuery,
+    poly_ref: PolyRef,
+    position: &Vector,
+    tolerance: f64,
+    endpoint: &str,
+) {
+    let (closest_point, _) = query
+        .closest_point_on_poly(poly_ref, position)
+        .unwrap_or_else(|err| panic!("closest point on {} poly failed: {}", endpoint, err));
+
+    let distance = closest_point.distance(*position);
+    assert!(
+        distance <= tolerance,
+        "{} poly {} is {} from {:?}, expected within {}",
+        endpoint,
+        poly_ref,
+        distance,
+        position,
+        tolerance
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::assert_path_endpoints;
+    use crate::{fixtures, NavMesh, NavMeshParams, NavMeshQuery, PolyRef, QueryFilter, Vector};
+
+    /// Asserts the endpoints of the path across a row of four cells, from cell 0 to cell 3
+    fn assert_row_path_endpoints(start_pos: &Vector, end_pos: &Vector) {
+        let nav_mesh = fixtures::nav_mesh_with_tile(&["...."]);
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+        let filter = QueryFilter::new().unwrap();
+        let extents = Vector::new(1.0, 2.0, 1.0);
+        let path_start = fixtures::cell_center(0, 0);
+        let path_end = fixtures::cell_center(3, 0);
+
+        let find_poly = |position: &Vector| -> PolyRef {
+            nav_mesh_query
+                .find_nearest_poly(position, &extents, &filter)
+                .unwrap()
+                .0
+        };
+        let path = nav_mesh_query
+            .find_path(
+                find_poly(&path_start),
+                find_poly(&path_end),
+                &path_start,
+                &path_end,
+                &filter,
+                8,
+            )
+            .unwrap();
+
+        assert_path_endpoints(&nav_mesh_query, &path, start_pos, end_pos, 0.5);
+    }
+
+    #[test]
+    fn test_assert_path_endpoints_within_tolerance() {
+        let offset = Vector::new(0.0, 0.3, 0.0);
+        assert_row_path_endpoints(
+            &(fixtures::cell_center(0, 0) + offset),
+            &(fixtures::cell_center(3, 0) - offset),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "start poly")]
+    fn test_assert_path_endpoints_wrong_start() {
+        assert_row_path_endpoints(&fixtures::cell_center(2, 0), &fixtures::cell_center(3, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "end poly")]
+    fn test_assert_path_endpoints_wrong_end() {
+        assert_row_path_endpoints(&fixtures::cell_center(0, 0), &fixtures::cell_center(1, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a non-empty path")]
+    fn test_assert_path_endpoints_empty_path() {
+        let nav_mesh_params = NavMeshParams {
+            origin: [0.0, 0.0, 0.0],
+            tile_width: 32.0,
+            tile_height: 32.0,
+            max_polys: 1000,
+            max_tiles: 1,
+        };
+
+        let nav_mesh = NavMesh::new(&nav_mesh_params).unwrap();
+        let nav_mesh_query = NavMeshQuery::new(&nav_mesh, 100).unwrap();
+
+        assert_path_endpoints(
+            &nav_mesh_query,
+            &[],
+            &Vector::default(),
+            &Vector::default(),
+            0.1,
+        );
+    }
+}
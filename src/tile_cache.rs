@@ -0,0 +1,677 @@
+use std::{collections::BTreeSet, ffi::c_void, mem, ptr, slice};
+
+use crate::{
+    binding::*, check_finite, input_len, DivertError, DivertResult, NavMesh, TileRef, Vector,
+};
+
+/// Typedef to DtTileCacheParams
+/// width and height are the tile size in cells, matching the NavMesh tile_width / cs
+pub type TileCacheParams = DtTileCacheParams;
+
+/// Reference to a temporary obstacle, wrapping Detour's 32 bit dtObstacleRef
+/// The null reference (0) is ObstacleRef::default()
+#[repr(transparent)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObstacleRef(u32);
+
+impl ObstacleRef {
+    /// Wraps a raw dtObstacleRef value
+    pub const fn from_raw(raw: u32) -> Self {
+        Self(raw)
+    }
+
+    /// Retrieves the raw dtObstacleRef value
+    pub const fn as_raw(self) -> u32 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for ObstacleRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Reference to a compressed tile layer held by a TileCache, wrapping Detour's dtCompressedTileRef
+/// The null reference (0) is CompressedTileRef::default()
+#[repr(transparent)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompressedTileRef(u32);
+
+impl CompressedTileRef {
+    /// Wraps a raw dtCompressedTileRef value
+    pub const fn from_raw(raw: u32) -> Self {
+        Self(raw)
+    }
+
+    /// Retrieves the raw dtCompressedTileRef value
+    pub const fn as_raw(self) -> u32 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for CompressedTileRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Compresses the layer grids a TileCache stores, mirroring dtTileCacheCompressor
+/// Layers are compressed by TileCache::build_layer and decompressed each time a tile is rebuilt
+pub trait TileCacheCompressor {
+    /// Upper bound of the compressed size of buffer_size bytes
+    fn max_compressed_size(&self, buffer_size: usize) -> usize;
+
+    /// Compresses buffer into compressed, returning the number of bytes written
+    fn compress(&mut self, buffer: &[u8], compressed: &mut [u8]) -> DivertResult<usize>;
+
+    /// Decompresses compressed into buffer, returning the number of bytes written
+    fn decompress(&mut self, compressed: &[u8], buffer: &mut [u8]) -> DivertResult<usize>;
+}
+
+/// Stores layer grids as is, trading memory for the cheapest possible tile rebuilds
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoCompression;
+
+impl TileCacheCompressor for NoCompression {
+    fn max_compressed_size(&self, buffer_size: usize) -> usize {
+        buffer_size
+    }
+
+    fn compress(&mut self, buffer: &[u8], compressed: &mut [u8]) -> DivertResult<usize> {
+        copy_into(buffer, compressed)
+    }
+
+    fn decompress(&mut self, compressed: &[u8], buffer: &mut [u8]) -> DivertResult<usize> {
+        copy_into(compressed, buffer)
+    }
+}
+
+fn copy_into(input: &[u8], output: &mut [u8]) -> DivertResult<usize> {
+    output
+        .get_mut(..input.len())
+        .ok_or(DivertError::InvalidParam("output buffer is too small"))?
+        .copy_from_slice(input);
+
+    Ok(input.len())
+}
+
+/// Adjusts the polys of every tile a TileCache builds, mirroring dtTileCacheMeshProcess
+/// Polys start out with their layer area and no flags, so a default QueryFilter rejects them
+/// until process assigns flags, typically derived from the area
+/// Implemented for closures taking (poly_areas, poly_flags)
+pub trait TileCacheMeshProcess {
+    /// Updates the area and flags of each poly of the tile about to be added
+    fn process(&mut self, poly_areas: &mut [u8], poly_flags: &mut [u16]);
+}
+
+impl<F: FnMut(&mut [u8], &mut [u16])> TileCacheMeshProcess for F {
+    fn process(&mut self, poly_areas: &mut [u8], poly_flags: &mut [u16]) {
+        self(poly_areas, poly_flags)
+    }
+}
+
+/// Safe representation of dtTileCacheLayerHeader, describing the grid passed to TileCache::build_layer
+/// The magic and version are filled in when the layer is built
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TileCacheLayerHeader {
+    /// Tile x location within the nav mesh tile grid
+    pub tile_x: i32,
+    /// Tile y location within the nav mesh tile grid
+    pub tile_y: i32,
+    /// Tile layer at its location within the nav mesh tile grid
+    pub tile_layer: i32,
+    /// Minimum bounds of the layer's AABB
+    pub b_min: [f64; 3],
+    /// Maximum bounds of the layer's AABB
+    pub b_max: [f64; 3],
+    /// Lowest cell height
+    pub h_min: u16,
+    /// Highest cell height
+    pub h_max: u16,
+    /// Number of cells along x
+    pub width: u8,
+    /// Number of cells along z
+    pub height: u8,
+    /// First usable cell along x
+    pub min_x: u8,
+    /// Last usable cell along x
+    pub max_x: u8,
+    /// First usable cell along z
+    pub min_y: u8,
+    /// Last usable cell along z
+    pub max_y: u8,
+}
+
+impl TileCacheLayerHeader {
+    fn to_dt_header(self) -> DtTileCacheLayerHeader {
+        DtTileCacheLayerHeader {
+            magic: DT_TILECACHE_MAGIC,
+            version: DT_TILECACHE_VERSION,
+            tx: self.tile_x,
+            ty: self.tile_y,
+            tlayer: self.tile_layer,
+            b_min: self.b_min,
+            b_max: self.b_max,
+            h_min: self.h_min,
+            h_max: self.h_max,
+            width: self.width,
+            height: self.height,
+            min_x: self.min_x,
+            max_x: self.max_x,
+            min_y: self.min_y,
+            max_y: self.max_y,
+        }
+    }
+}
+
+type BoxedCompressor = Box<dyn TileCacheCompressor + Send>;
+type BoxedMeshProcess = Box<dyn TileCacheMeshProcess + Send>;
+
+/// Slice over a buffer handed to a callback by Detour, empty for null or non-positive sizes
+unsafe fn callback_slice<'s, T>(data: *const T, len: i32) -> &'s [T] {
+    match usize::try_from(len) {
+        Ok(len) if !data.is_null() => slice::from_raw_parts(data, len),
+        _ => &[],
+    }
+}
+
+/// Mutable slice over a buffer handed to a callback by Detour, empty for null or non-positive sizes
+unsafe fn callback_slice_mut<'s, T>(data: *mut T, len: i32) -> &'s mut [T] {
+    match usize::try_from(len) {
+        Ok(len) if !data.is_null() => slice::from_raw_parts_mut(data, len),
+        _ => &mut [],
+    }
+}
+
+extern "C" fn max_compressed_size_callback(user: *mut c_void, buffer_size: i32) -> i32 {
+    let compressor = unsafe { &*(user as *const BoxedCompressor) };
+    let max_size = compressor.max_compressed_size(usize::try_from(buffer_size).unwrap_or(0));
+
+    i32::try_from(max_size).unwrap_or(i32::MAX)
+}
+
+/// Stores the size written by a compressor callback, failing if it overran the output buffer
+fn callback_result(result: DivertResult<usize>, capacity: usize, size: *mut i32) -> DtStatus {
+    match result {
+        Ok(written) if written <= capacity => {
+            unsafe { *size = written as i32 };
+            DtStatus::SUCCESS
+        }
+        Ok(_) => DtStatus::failure_with(DtStatus::BUFFER_TOO_SMALL),
+        Err(_) => DtStatus::FAILURE,
+    }
+}
+
+extern "C" fn compress_callback(
+    user: *mut c_void,
+    buffer: *const u8,
+    buffer_size: i32,
+    compressed: *mut u8,
+    max_compressed_size: i32,
+    compressed_size: *mut i32,
+) -> DtStatus {
+    let compressor = unsafe { &mut *(user as *mut BoxedCompressor) };
+    let buffer = unsafe { callback_slice(buffer, buffer_size) };
+    let compressed = unsafe { callback_slice_mut(compressed, max_compressed_size) };
+
+    let capacity = compressed.len();
+    callback_result(
+        compressor.compress(buffer, compressed),
+        capacity,
+        compressed_size,
+    )
+}
+
+extern "C" fn decompress_callback(
+    user: *mut c_void,
+    compressed: *const u8,
+    compressed_size: i32,
+    buffer: *mut u8,
+    max_buffer_size: i32,
+    buffer_size: *mut i32,
+) -> DtStatus {
+    let compressor = unsafe { &mut *(user as *mut BoxedCompressor) };
+    let compressed = unsafe { callback_slice(compressed, compressed_size) };
+    let buffer = unsafe { callback_slice_mut(buffer, max_buffer_size) };
+
+    let capacity = buffer.len();
+    callback_result(
+        compressor.decompress(compressed, buffer),
+        capacity,
+        buffer_size,
+    )
+}
+
+extern "C" fn mesh_process_callback(
+    user: *mut c_void,
+    poly_count: i32,
+    poly_areas: *mut u8,
+    poly_flags: *mut u16,
+) {
+    let mesh_process = unsafe { &mut *(user as *mut BoxedMeshProcess) };
+    let poly_areas = unsafe { callback_slice_mut(poly_areas, poly_count) };
+    let poly_flags = unsafe { callback_slice_mut(poly_flags, poly_count) };
+
+    mesh_process.process(poly_areas, poly_flags);
+}
+
+/// Safe bindings to dtTileCache
+/// Handles life time of the dtTileCache and will release resources when dropped
+/// Keeps compressed layers of the nav mesh tiles and rebuilds the tiles of a NavMesh
+/// whenever temporary obstacles are added or removed
+/// The compressor and mesh process callbacks run on the calling thread, a panic in them aborts
+pub struct TileCache {
+    handle: *mut DtTileCache,
+    alloc: *mut DtTileCacheAlloc,
+    compressor: *mut DtTileCacheCompressor,
+    mesh_process: *mut DtTileCacheMeshProcess,
+    // Double boxed so the callbacks receive a thin pointer, kept alive until the dtTileCache is freed
+    _compressor: Box<BoxedCompressor>,
+    _mesh_process: Box<BoxedMeshProcess>,
+}
+
+// SAFETY: The dtTileCache, its allocator and the compressor and mesh process bridges are owned by
+// this TileCache alone and only reached through its methods, which take &mut self to mutate them
+// The bridges' user pointers point into _compressor and _mesh_process, whose boxed trait objects
+// are bounded by Send, so the callbacks may run on whichever thread owns the TileCache
+// The dtTileCache keeps no pointer to a NavMesh, each call building tiles takes one by &mut
+// Detour keeps no thread-local state, so all of them can be used and freed from any thread
+unsafe impl Send for TileCache {}
+
+/// Provides functionality to interact with TileCache and its underlying dtTileCache
+impl TileCache {
+    /// Allocates and initializes a dtTileCache, compressing layers with compressor and
+    /// adjusting the polys of every built tile with mesh_process
+    /// Errors if max_tiles or max_obstacles is negative, allocation returns a null pointer,
+    /// or the dtTileCache->init function fails
+    pub fn new(
+        params: &TileCacheParams,
+        compressor: impl TileCacheCompressor + Send + 'static,
+        mesh_process: impl TileCacheMeshProcess + Send + 'static,
+    ) -> DivertResult<Self> {
+        if params.max_tiles < 0 || params.max_obstacles < 0 {
+            return Err(DivertError::InvalidParam(
+                "max_tiles and max_obstacles must not be negative",
+            ));
+        }
+
+        let mut boxed_compressor: Box<BoxedCompressor> = Box::new(Box::new(compressor));
+        let mut boxed_mesh_process: Box<BoxedMeshProcess> = Box::new(Box::new(mesh_process));
+
+        let tile_cache = unsafe {
+            Self {
+                handle: dtTileCache_alloc(),
+                alloc: dtTileCacheAlloc_alloc(),
+                compressor: dtTileCacheCompressor_alloc(
+                    &mut *boxed_compressor as *mut BoxedCompressor as *mut c_void,
+                    max_compressed_size_callback,
+                    compress_callback,
+                    decompress_callback,
+                ),
+                mesh_process: dtTileCacheMeshProcess_alloc(
+                    &mut *boxed_mesh_process as *mut BoxedMeshProcess as *mut c_void,
+                    mesh_process_callback,
+                ),
+                _compressor: boxed_compressor,
+                _mesh_process: boxed_mesh_process,
+            }
+        };
+
+        if tile_cache.handle.is_null()
+            || tile_cache.alloc.is_null()
+            || tile_cache.compressor.is_null()
+            || tile_cache.mesh_process.is_null()
+        {
+            return Err(DivertError::NullPtr());
+        }
+
+        let init_status = unsafe {
+            dtTileCache_init(
+                tile_cache.handle,
+                params,
+                tile_cache.alloc,
+                tile_cache.compressor,
+                tile_cache.mesh_process,
+            )
+        };
+        if init_status.is_failed() {
+            return Err(DivertError::TileCacheInitFailure(init_status));
+        }
+
+        Ok(tile_cache)
+    }
+
+    /// Builds a compressed layer from a grid of width * height cells, laid out row by row along z
+    /// heights are cell heights in ch units above b_min, areas the cell area ids
+    /// (0 is unwalkable), and cons the neighbour connections (low nibble) and tile border portals
+    /// (high nibble) of each cell, using Recast's direction ids
+    /// The returned bytes can be passed to add_tile
+    /// Errors if a grid doesn't hold width * height cells, or dtBuildTileCacheLayer fails
+    pub fn build_layer(
+        &mut self,
+        header: &TileCacheLayerHeader,
+        heights: &[u8],
+        areas: &[u8],
+        cons: &[u8],
+    ) -> DivertResult<Vec<u8>> {
+        let cell_count = header.width as usize * header.height as usize;
+        if [heights, areas, cons]
+            .iter()
+            .any(|grid| grid.len() != cell_count)
+        {
+            return Err(DivertError::InvalidParam(
+                "heights, areas and cons must hold width * height cells",
+            ));
+        }
+
+        let mut dt_header = header.to_dt_header();
+        let mut data: *mut u8 = ptr::null_mut();
+        let mut data_size = 0;
+
+        let build_status = unsafe {
+            dtTileCache_buildTileCacheLayer(
+                self.handle,
+                &mut dt_header,
+                heights.as_ptr(),
+                areas.as_ptr(),
+                cons.as_ptr(),
+                &mut data,
+                &mut data_size,
+            )
+        };
+        if build_status.is_failed() || data.is_null() {
+            return Err(DivertError::BuildTileCacheLayerFailure(build_status));
+        }
+
+        let layer_data = unsafe { slice::from_raw_parts(data, data_size as usize) }.to_vec();
+        unsafe { dtNavMeshData_free(data) };
+
+        Ok(layer_data)
+    }
+
+    /// Adds a compressed layer built by build_layer, the data is copied into the tile cache
+    /// The nav mesh tile isn't built until build_nav_mesh_tiles_at is called for its location
+    /// Errors if the data is too short to hold a layer header, or dtTileCache->addTile fails
+    pub fn add_tile(&mut self, data: &[u8]) -> DivertResult<CompressedTileRef> {
+        if data.len() < mem::size_of::<DtTileCacheLayerHeader>() {
+            return Err(DivertError::InvalidParam(
+                "data is too short to hold a tile cache layer header",
+            ));
+        }
+
+        let mut tile_ref = CompressedTileRef::default();
+        let add_tile_status = unsafe {
+            dtTileCache_addTile(
+                self.handle,
+                data.as_ptr(),
+                input_len(data.len())?,
+                &mut tile_ref,
+            )
+        };
+        if add_tile_status.is_failed() {
+            return Err(DivertError::TileCacheAddTileFailure(add_tile_status));
+        }
+
+        Ok(tile_ref)
+    }
+
+    /// Builds the nav mesh tiles of every layer at the tile grid location, replacing loaded tiles
+    /// The rebuilt tiles are marked dirty in nav_mesh
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn build_nav_mesh_tiles_at(
+        &mut self,
+        tile_x: i32,
+        tile_y: i32,
+        nav_mesh: &mut NavMesh,
+    ) -> DivertResult<()> {
+        let loaded: BTreeSet<TileRef> = nav_mesh.tiles().collect();
+        let build_status = unsafe {
+            dtTileCache_buildNavMeshTilesAt(self.handle, tile_x, tile_y, nav_mesh.handle)
+        };
+        nav_mesh.sync_loaded_tiles(&loaded);
+
+        if build_status.is_failed() {
+            return Err(DivertError::BuildNavMeshTilesAtFailure(build_status));
+        }
+
+        Ok(())
+    }
+
+    /// Requests a cylinder obstacle standing on pos, applied by the following update calls
+    /// Errors if pos isn't finite, or the obstacle or request pool is full
+    pub fn add_obstacle(
+        &mut self,
+        pos: &Vector,
+        radius: f64,
+        height: f64,
+    ) -> DivertResult<ObstacleRef> {
+        check_finite(pos, "pos must be finite")?;
+
+        let mut obstacle_ref = ObstacleRef::default();
+        let add_status =
+            unsafe { dtTileCache_addObstacle(self.handle, pos, radius, height, &mut obstacle_ref) };
+        if add_status.is_failed() {
+            return Err(DivertError::AddObstacleFailure(add_status));
+        }
+
+        Ok(obstacle_ref)
+    }
+
+    /// Requests an axis aligned box obstacle, applied by the following update calls
+    /// Errors if b_min or b_max isn't finite, or the obstacle or request pool is full
+    pub fn add_box_obstacle(
+        &mut self,
+        b_min: &Vector,
+        b_max: &Vector,
+    ) -> DivertResult<ObstacleRef> {
+        check_finite(b_min, "b_min must be finite")?;
+        check_finite(b_max, "b_max must be finite")?;
+
+        let mut obstacle_ref = ObstacleRef::default();
+        let add_status =
+            unsafe { dtTileCache_addBoxObstacle(self.handle, b_min, b_max, &mut obstacle_ref) };
+        if add_status.is_failed() {
+            return Err(DivertError::AddBoxObstacleFailure(add_status));
+        }
+
+        Ok(obstacle_ref)
+    }
+
+    /// Requests the removal of an obstacle, applied by the following update calls
+    /// Removing an obstacle that no longer exists succeeds without effect
+    /// Errors if the request pool is full
+    pub fn remove_obstacle(&mut self, obstacle_ref: ObstacleRef) -> DivertResult<()> {
+        let remove_status = unsafe { dtTileCache_removeObstacle(self.handle, obstacle_ref) };
+        if remove_status.is_failed() {
+            return Err(DivertError::RemoveObstacleFailure(remove_status));
+        }
+
+        Ok(())
+    }
+
+    /// Processes pending obstacle requests and rebuilds at most one nav mesh tile they touch
+    /// The rebuilt tiles are marked dirty in nav_mesh
+    /// Returns whether updates are still pending, call again until it returns false
+    /// Errors if ffi function returns a failed DtStatus
+    pub fn update(&mut self, dt: f64, nav_mesh: &mut NavMesh) -> DivertResult<bool> {
+        let loaded: BTreeSet<TileRef> = nav_mesh.tiles().collect();
+        let mut up_to_date = false;
+        let update_status =
+            unsafe { dtTileCache_update(self.handle, dt, nav_mesh.handle, &mut up_to_date) };
+        nav_mesh.sync_loaded_tiles(&loaded);
+
+        if update_status.is_failed() {
+            return Err(DivertError::TileCacheUpdateFailure(update_status));
+        }
+
+        Ok(!up_to_date)
+    }
+}
+
+impl Drop for TileCache {
+    /// Frees the dtTileCache with dtFreeTileCache, then the allocator and callback bridges it used
+    fn drop(&mut self) {
+        unsafe {
+            dtTileCache_free(self.handle);
+            dtTileCacheMeshProcess_free(self.mesh_process);
+            dtTileCacheCompressor_free(self.compressor);
+            dtTileCacheAlloc_free(self.alloc);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        NoCompression, TileCache, TileCacheCompressor, TileCacheLayerHeader, TileCacheParams,
+    };
+    use crate::{fixtures, DivertError, NavMesh, NavMeshParams, NavMeshQuery, QueryFilter, Vector};
+
+    const CELLS: u8 = 8;
+    const WALKABLE_AREA: u8 = 63;
+
+    fn tile_cache_params() -> TileCacheParams {
+        TileCacheParams {
+            orig: [0.0; 3],
+            cs: 1.0,
+            ch: 1.0,
+            width: CELLS as i32,
+            height: CELLS as i32,
+            walkable_height: 2.0,
+            walkable_radius: 0.5,
+            walkable_climb: 1.0,
+            max_simplification_error: 1.3,
+            max_tiles: 1,
+            max_obstacles: 8,
+        }
+    }
+
+    fn nav_mesh() -> NavMesh<'static> {
+        NavMesh::new(&NavMeshParams {
+            origin: [0.0; 3],
+            tile_width: CELLS as f64,
+            tile_height: CELLS as f64,
+            max_tiles: 1,
+            max_polys: 256,
+        })
+        .unwrap()
+    }
+
+    /// Flat walkable layer covering tile (0, 0), every cell connected to its in-bounds neighbours
+    fn flat_layer(tile_cache: &mut TileCache) -> Vec<u8> {
+        let header = TileCacheLayerHeader {
+            b_max: [CELLS as f64, 2.0, CELLS as f64],
+            width: CELLS,
+            height: CELLS,
+            max_x: CELLS - 1,
+            max_y: CELLS - 1,
+            ..Default::default()
+        };
+
+        let cells = CELLS as usize * CELLS as usize;
+        let last = CELLS as usize - 1;
+        let cons: Vec<u8> = (0..cells)
+            .map(|i| {
+                let (x, z) = (i % CELLS as usize, i / CELLS as usize);
+                [x > 0, z < last, x < last, z > 0]
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &connected)| connected)
+                    .fold(0, |con, (dir, _)| con | 1 << dir)
+            })
+            .collect();
+
+        tile_cache
+            .build_layer(&header, &vec![0; cells], &vec![WALKABLE_AREA; cells], &cons)
+            .unwrap()
+    }
+
+    fn crosses_tile(nav_mesh: &NavMesh) -> bool {
+        let nav_mesh_query = NavMeshQuery::new(nav_mesh, 256).unwrap();
+        let filter = QueryFilter::new().unwrap();
+
+        let start_pos = Vector::new(4.0, 0.0, 1.0);
+        let end_pos = Vector::new(4.0, 0.0, 7.0);
+        let (start_ref, end_ref) =
+            fixtures::endpoints(&nav_mesh_query, &start_pos, &end_pos, &filter);
+
+        nav_mesh_query
+            .find_path_detailed(start_ref, end_ref, &start_pos, &end_pos, &filter, 64)
+            .unwrap()
+            .complete
+    }
+
+    #[test]
+    fn test_tile_cache_obstacles() {
+        let mut tile_cache = TileCache::new(
+            &tile_cache_params(),
+            NoCompression,
+            |_: &mut [u8], poly_flags: &mut [u16]| poly_flags.fill(1),
+        )
+        .unwrap();
+        let mut nav_mesh = nav_mesh();
+
+        let layer = flat_layer(&mut tile_cache);
+        tile_cache.add_tile(&layer).unwrap();
+        tile_cache
+            .build_nav_mesh_tiles_at(0, 0, &mut nav_mesh)
+            .unwrap();
+        assert_eq!(nav_mesh.tiles().count(), 1);
+        assert_eq!(nav_mesh.dirty_tiles().len(), 1);
+        assert!(crosses_tile(&nav_mesh));
+        nav_mesh.clear_dirty();
+
+        let wall = tile_cache
+            .add_box_obstacle(
+                &Vector::new(-1.0, -1.0, 3.5),
+                &Vector::new(CELLS as f64 + 1.0, 2.0, 4.5),
+            )
+            .unwrap();
+        while tile_cache.update(0.0, &mut nav_mesh).unwrap() {}
+        assert!(!nav_mesh.dirty_tiles().is_empty());
+        assert!(!crosses_tile(&nav_mesh));
+
+        tile_cache.remove_obstacle(wall).unwrap();
+        while tile_cache.update(0.0, &mut nav_mesh).unwrap() {}
+        assert!(crosses_tile(&nav_mesh));
+
+        let pillar = tile_cache
+            .add_obstacle(&Vector::new(4.0, 0.0, 4.0), 1.0, 2.0)
+            .unwrap();
+        assert_ne!(pillar, wall);
+        while tile_cache.update(0.0, &mut nav_mesh).unwrap() {}
+        assert!(crosses_tile(&nav_mesh));
+    }
+
+    #[test]
+    fn test_tile_cache_invalid() {
+        let mut tile_cache = TileCache::new(
+            &tile_cache_params(),
+            NoCompression,
+            |_: &mut [u8], _: &mut [u16]| {},
+        )
+        .unwrap();
+
+        let header = TileCacheLayerHeader {
+            width: 2,
+            height: 2,
+            ..Default::default()
+        };
+        assert!(matches!(
+            tile_cache.build_layer(&header, &[0; 4], &[0; 4], &[0; 3]),
+            Err(DivertError::InvalidParam(_))
+        ));
+        assert!(matches!(
+            tile_cache.add_tile(&[0; 4]),
+            Err(DivertError::InvalidParam(_))
+        ));
+        assert!(matches!(
+            tile_cache.add_obstacle(&Vector::new(f64::NAN, 0.0, 0.0), 1.0, 1.0),
+            Err(DivertError::InvalidParam(_))
+        ));
+
+        let mut compressed = [0; 2];
+        assert!(NoCompression.compress(&[1, 2, 3], &mut compressed).is_err());
+    }
+}
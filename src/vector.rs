@@ -0,0 +1,227 @@
+use crate::Vector;
+
+/// Provides basic math functions used with 3D Vectors
+/// Vector is a typedef to glam's DVec3, so functionality glam lacks is added through this trait
+/// Operations glam already provides (dot, length, distance, angle_between, project_onto, reject_from,
+/// abs, signum, ...) are used directly, note glam's signum follows f64::signum and maps 0.0 to 1.0
+pub trait VectorExt {
+    /// Reflects the vector off a surface with the given unit normal
+    /// Used to compute bounce/slide directions from a wall hit normal
+    fn reflect(&self, normal: &Vector) -> Vector;
+
+    /// Point halfway between the vector and other
+    /// Used for marker placement and edge centers
+    fn midpoint(&self, other: &Vector) -> Vector;
+
+    /// Copy of the vector with its vertical component replaced by height
+    /// Vectors are stored in Detour order, so the vertical component is y
+    fn with_height(&self, height: f64) -> Vector;
+
+    /// Whether the vector's length is below epsilon
+    /// Guards against normalizing degenerate directions, complementing glam's normalize_or_zero
+    fn is_zero(&self, epsilon: f64) -> bool;
+
+    /// Converts the vector from Detour's y-up frame to a z-up frame, returned as (x, y, z)
+    /// Detour stores positions as (y, z, x) of the z-up frame, matching the yzx ordering of
+    /// earlier releases, so the z-up x is Detour's z, the z-up y is Detour's x, and up is Detour's y
+    fn to_z_up(&self) -> Vector;
+
+    /// Converts a z-up position (x, y, z) into Detour's y-up frame, the inverse of to_z_up
+    fn from_z_up(x: f64, y: f64, z: f64) -> Vector;
+}
+
+impl VectorExt for Vector {
+    fn reflect(&self, normal: &Vector) -> Vector {
+        *self - *normal * (2.0 * self.dot(*normal))
+    }
+
+    fn midpoint(&self, other: &Vector) -> Vector {
+        (*self + *other) * 0.5
+    }
+
+    fn with_height(&self, height: f64) -> Vector {
+        Vector::new(self.x, height, self.z)
+    }
+
+    fn is_zero(&self, epsilon: f64) -> bool {
+        self.length_squared() < epsilon * epsilon
+    }
+
+    fn to_z_up(&self) -> Vector {
+        Vector::new(self.z, self.x, self.y)
+    }
+
+    fn from_z_up(x: f64, y: f64, z: f64) -> Vector {
+        Vector::new(y, z, x)
+    }
+}
+
+/// Average of the points, such as the center of a poly or of a group of agents
+/// Returns None if points is empty
+pub fn centroid(points: &[Vector]) -> Option<Vector> {
+    if points.is_empty() {
+        return None;
+    }
+
+    let sum = points.iter().fold(Vector::ZERO, |sum, point| sum + *point);
+    Some(sum / points.len() as f64)
+}
+
+/// Min and max corners of the box spanned by center and half extents, as (min, max)
+/// Matches how Detour queries interpret their search extents
+pub fn extents_bounds(center: &Vector, extents: &Vector) -> (Vector, Vector) {
+    (*center - *extents, *center + *extents)
+}
+
+/// Up axis of an application's positions, so z-up applications can convert to and from Detour's
+/// y-up Vectors and get height and horizontal distance semantics without manual swizzling
+/// Positions passed to and returned by the crate's queries are always in Detour's y-up frame
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UpAxis {
+    /// Positions are y-up (x, y, z), like Detour's, and are used as is
+    #[default]
+    Y,
+    /// Positions are z-up (x, y, z), converted like VectorExt::from_z_up and VectorExt::to_z_up
+    Z,
+}
+
+impl UpAxis {
+    /// Converts an application position into Detour's y-up frame
+    pub fn to_detour(self, position: &Vector) -> Vector {
+        match self {
+            UpAxis::Y => *position,
+            UpAxis::Z => Vector::from_z_up(position.x, position.y, position.z),
+        }
+    }
+
+    /// Converts a Detour position into the application's frame, the inverse of to_detour
+    pub fn from_detour(self, position: &Vector) -> Vector {
+        match self {
+            UpAxis::Y => *position,
+            UpAxis::Z => position.to_z_up(),
+        }
+    }
+
+    /// Vertical component of an application position
+    pub fn height(self, position: &Vector) -> f64 {
+        match self {
+            UpAxis::Y => position.y,
+            UpAxis::Z => position.z,
+        }
+    }
+
+    /// Copy of an application position with its vertical component replaced by height
+    pub fn with_height(self, position: &Vector, height: f64) -> Vector {
+        match self {
+            UpAxis::Y => Vector::new(position.x, height, position.z),
+            UpAxis::Z => Vector::new(position.x, position.y, height),
+        }
+    }
+
+    /// Distance between two application positions ignoring their vertical components
+    pub fn distance_2d(self, a: &Vector, b: &Vector) -> f64 {
+        self.with_height(a, 0.0).distance(self.with_height(b, 0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{centroid, extents_bounds, UpAxis, VectorExt};
+    use crate::Vector;
+
+    #[test]
+    fn test_reflect() {
+        let direction = Vector::new(1.0, -1.0, 0.5);
+        let normal = Vector::new(0.0, 1.0, 0.0);
+
+        assert_eq!(direction.reflect(&normal), Vector::new(1.0, 1.0, 0.5));
+    }
+
+    #[test]
+    fn test_is_zero() {
+        let tiny = Vector::new(1e-9, -1e-9, 1e-9);
+        assert!(tiny.is_zero(1e-6));
+        assert!(!tiny.is_zero(1e-12));
+
+        assert!(!Vector::X.is_zero(1e-6));
+        assert!(Vector::ZERO.is_zero(f64::EPSILON));
+    }
+
+    #[test]
+    fn test_midpoint() {
+        let a = Vector::new(1.0, 2.0, -4.0);
+        let b = Vector::new(3.0, -2.0, 8.0);
+
+        assert_eq!(a.midpoint(&b), Vector::new(2.0, 0.0, 2.0));
+        assert_eq!(b.midpoint(&a), a.midpoint(&b));
+    }
+
+    #[test]
+    fn test_with_height() {
+        let position = Vector::new(3.0, 1.5, -7.0);
+
+        assert_eq!(position.with_height(4.0), Vector::new(3.0, 4.0, -7.0));
+    }
+
+    #[test]
+    fn test_centroid() {
+        assert_eq!(centroid(&[]), None);
+
+        let center = Vector::new(5.0, 1.0, -3.0);
+        let points = [
+            center + Vector::new(2.0, 0.0, 0.0),
+            center + Vector::new(-2.0, 0.0, 0.0),
+            center + Vector::new(0.0, 0.0, 2.0),
+            center + Vector::new(0.0, 0.0, -2.0),
+        ];
+        assert_eq!(centroid(&points), Some(center));
+        assert_eq!(centroid(&points[..1]), Some(points[0]));
+    }
+
+    #[test]
+    fn test_z_up_conversion() {
+        let detour = Vector::from_z_up(1.0, 2.0, 3.0);
+        assert_eq!(detour, Vector::new(2.0, 3.0, 1.0));
+        assert_eq!(
+            detour.with_height(5.0).to_z_up(),
+            Vector::new(1.0, 2.0, 5.0)
+        );
+        assert_eq!(detour.to_z_up(), Vector::new(1.0, 2.0, 3.0));
+
+        let z_up = Vector::new(-4.0, 0.5, 7.0);
+        assert_eq!(Vector::from_z_up(z_up.x, z_up.y, z_up.z).to_z_up(), z_up);
+    }
+
+    #[test]
+    fn test_up_axis() {
+        let a = Vector::new(1.0, 2.0, 3.0);
+        let b = Vector::new(4.0, 6.0, -1.0);
+
+        assert_eq!(UpAxis::Y.to_detour(&a), a);
+        assert_eq!(UpAxis::Y.height(&a), 2.0);
+        assert_eq!(UpAxis::Y.with_height(&a, 9.0), Vector::new(1.0, 9.0, 3.0));
+        assert_eq!(UpAxis::Y.distance_2d(&a, &b), 5.0);
+
+        assert_eq!(UpAxis::Z.to_detour(&a), Vector::new(2.0, 3.0, 1.0));
+        assert_eq!(UpAxis::Z.height(&a), 3.0);
+        assert_eq!(UpAxis::Z.with_height(&a, 9.0), Vector::new(1.0, 2.0, 9.0));
+        assert_eq!(UpAxis::Z.distance_2d(&a, &b), 5.0);
+
+        for up_axis in [UpAxis::Y, UpAxis::Z] {
+            let detour = up_axis.to_detour(&a);
+            assert_eq!(detour.y, up_axis.height(&a));
+            assert_eq!(up_axis.from_detour(&detour), a);
+        }
+    }
+
+    #[test]
+    fn test_extents_bounds() {
+        let center = Vector::new(10.0, 0.5, -4.0);
+        let extents = Vector::new(2.0, 4.0, 2.0);
+
+        assert_eq!(
+            extents_bounds(&center, &extents),
+            (Vector::new(8.0, -3.5, -6.0), Vector::new(12.0, 4.5, -2.0))
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Checks that tile data passed to a NavMesh is released on every path, Detour only ever owning
+//! its own dtAlloc'ed copies
+//! The counting allocator is installed for this test binary only, so the unit tests don't run under it
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use divert_f64::{
+    create_nav_mesh_data, DivertError, NavMesh, NavMeshCreateParams, NavMeshParams, TileFlags,
+    TileRef, DT_VERTS_PER_POLYGON,
+};
+
+thread_local! {
+    static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+/// System allocator tracking the bytes allocated and not yet freed by the current thread
+/// Counting per thread keeps the tests running in parallel from skewing each other's counts
+struct CountingAllocator;
+
+impl CountingAllocator {
+    fn track(delta: isize) {
+        // Threads being torn down have no counter left, their allocations aren't tracked
+        let _ = LIVE_BYTES.try_with(|live_bytes| live_bytes.set(live_bytes.get() + delta));
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::track(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        Self::track(-(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Self::track(new_size as isize - layout.size() as isize);
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Bytes allocated through Rust's global allocator and not yet freed by the current thread
+/// Detour copies transferred data into its own dtAlloc'ed buffer, so the transferred Vec is released
+/// like any other
+fn thread_live_bytes() -> isize {
+    LIVE_BYTES.with(Cell::get)
+}
+
+const TILE_SIZE: u16 = 4;
+const NULL_IDX: u16 = 0xffff;
+
+fn nav_mesh_params(max_tiles: i32) -> NavMeshParams {
+    NavMeshParams {
+        origin: [0.0, 0.0, 0.0],
+        tile_width: TILE_SIZE as f64,
+        tile_height: TILE_SIZE as f64,
+        max_tiles,
+        max_polys: 256,
+    }
+}
+
+/// Builds tile data holding a single square poly covering the tile at (tile_x, 0)
+fn create_tile_data(tile_x: i32) -> Vec<u8> {
+    let verts = [
+        [0, 1, 0],
+        [0, 1, TILE_SIZE],
+        [TILE_SIZE, 1, TILE_SIZE],
+        [TILE_SIZE, 1, 0],
+    ];
+    let mut polys = vec![NULL_IDX; 2 * DT_VERTS_PER_POLYGON as usize];
+    polys[..4].copy_from_slice(&[0, 1, 2, 3]);
+
+    let tile_min_x = tile_x as f64 * TILE_SIZE as f64;
+    let params = NavMeshCreateParams {
+        verts: &verts,
+        polys: &polys,
+        poly_flags: &[1],
+        poly_areas: &[0],
+        nvp: DT_VERTS_PER_POLYGON,
+        tile_x,
+        b_min: [tile_min_x, -1.0, 0.0],
+        b_max: [tile_min_x + TILE_SIZE as f64, 1.0, TILE_SIZE as f64],
+        walkable_height: 2.0,
+        walkable_radius: 0.5,
+        walkable_climb: 1.0,
+        cs: 1.0,
+        ch: 1.0,
+        build_bv_tree: true,
+        ..Default::default()
+    };
+
+    create_nav_mesh_data(&params).unwrap()
+}
+
+#[test]
+fn test_add_tile_failure_reclaims_data() {
+    let mut nav_mesh = NavMesh::new(&nav_mesh_params(1)).unwrap();
+    nav_mesh.add_tile(create_tile_data(0)).unwrap();
+
+    let tile_data = create_tile_data(0);
+    let live_bytes = thread_live_bytes();
+    let tile_len = tile_data.len() as isize;
+    assert!(matches!(
+        nav_mesh.add_tile(tile_data),
+        Err(DivertError::AlreadyOccupied())
+    ));
+    assert_eq!(thread_live_bytes(), live_bytes - tile_len);
+
+    let tile_data = create_tile_data(0);
+    let live_bytes = thread_live_bytes();
+    assert!(nav_mesh
+        .add_tile_with(tile_data, TileFlags::empty(), TileRef::default())
+        .is_err());
+    assert_eq!(thread_live_bytes(), live_bytes - tile_len);
+
+    let mut wrong_magic = create_tile_data(1);
+    wrong_magic[0] ^= 0xff;
+    let live_bytes = thread_live_bytes();
+    assert!(nav_mesh.add_tile(wrong_magic).is_err());
+    assert_eq!(thread_live_bytes(), live_bytes - tile_len);
+}
+
+#[test]
+fn test_tile_data_is_released() {
+    let mut nav_mesh = NavMesh::new(&nav_mesh_params(2)).unwrap();
+    let retained_ref = nav_mesh
+        .add_tile_with(create_tile_data(1), TileFlags::empty(), TileRef::default())
+        .unwrap();
+
+    // The transferred data is dropped once Detour holds its copy
+    let tile_data = create_tile_data(0);
+    let tile_len = tile_data.len() as isize;
+    let live_bytes = thread_live_bytes();
+    let freed_ref = nav_mesh
+        .add_tile_with(tile_data, TileFlags::FREE_DATA, TileRef::default())
+        .unwrap();
+    assert_eq!(thread_live_bytes(), live_bytes - tile_len);
+
+    // Detour frees its copy with dtFree, the retained data is dropped by the NavMesh
+    let tile_len = nav_mesh.tile_data(retained_ref).unwrap().len() as isize;
+    let live_bytes = thread_live_bytes();
+    nav_mesh.remove_tile(freed_ref).unwrap();
+    assert_eq!(thread_live_bytes(), live_bytes);
+    nav_mesh.remove_tile(retained_ref).unwrap();
+    assert_eq!(thread_live_bytes(), live_bytes - tile_len);
+
+    // Dropping the NavMesh drops the data of the tiles it still retains
+    nav_mesh
+        .add_tile_with(create_tile_data(1), TileFlags::empty(), retained_ref)
+        .unwrap();
+    nav_mesh.clear_dirty();
+    let live_bytes = thread_live_bytes();
+    drop(nav_mesh);
+    assert!(thread_live_bytes() <= live_bytes - tile_len);
+}